@@ -0,0 +1,21 @@
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=NLZSS11_UPDATE_HEADER");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap();
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/nlzss11.h", out_dir));
+            // the checked in header is the published one, only overwrite it when asked to
+            if std::env::var_os("NLZSS11_UPDATE_HEADER").is_some() {
+                bindings.write_to_file(format!("{}/include/nlzss11.h", crate_dir));
+            }
+        }
+        Err(e) => {
+            // don't break the build over the header, the checked in one is still usable
+            println!("cargo:warning=could not generate nlzss11.h: {}", e);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+#ifndef NLZSS11_H
+#define NLZSS11_H
+
+/* This file is generated by cbindgen from nlzss11-capi/src/lib.rs, do not edit it by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of every fallible function in this library.
+ */
+typedef enum nlzss11_status {
+  NLZSS11_STATUS_OK = 0,
+  /**
+   * The input doesn't start with the 0x11 magic.
+   */
+  NLZSS11_STATUS_INVALID_MAGIC = 1,
+  /**
+   * The input is truncated or references data that doesn't exist.
+   */
+  NLZSS11_STATUS_INVALID_INDEX = 2,
+  /**
+   * The output buffer is too small, the required size is written to the length out parameter.
+   */
+  NLZSS11_STATUS_BUFFER_TOO_SMALL = 3,
+  /**
+   * A required pointer argument was null.
+   */
+  NLZSS11_STATUS_NULL_POINTER = 4,
+  /**
+   * Any other error while decompressing.
+   */
+  NLZSS11_STATUS_OTHER = 5,
+  /**
+   * The end of the compressed stream has been reached, returned by the streaming functions.
+   */
+  NLZSS11_STATUS_STREAM_END = 6,
+  /**
+   * The input is bigger than the 4 GiB the header can hold.
+   */
+  NLZSS11_STATUS_INPUT_TOO_LARGE = 7,
+} nlzss11_status;
+
+/**
+ * Opaque state for decompressing data that arrives in pieces.
+ */
+typedef struct nlzss11_decompressor nlzss11_decompressor;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Maximum size of the compressed data for an input of `src_len` bytes.
+ */
+size_t nlzss11_compress_bound(size_t src_len);
+
+/**
+ * Reads the decompressed size from the header of compressed data.
+ *
+ * # Safety
+ *
+ * `src` must point to `src_len` readable bytes, `out_size` must be a valid pointer.
+ */
+enum nlzss11_status nlzss11_decompressed_size(const uint8_t *src, size_t src_len, size_t *out_size);
+
+/**
+ * Compresses `src` into `dst`.
+ *
+ * On success and on `NLZSS11_STATUS_BUFFER_TOO_SMALL` the size of the compressed data is
+ * written to `dst_len`. Inputs over 4 GiB fail with `NLZSS11_STATUS_INPUT_TOO_LARGE`.
+ *
+ * # Safety
+ *
+ * `src` must point to `src_len` readable bytes, `dst` to `dst_capacity` writable bytes
+ * and `dst_len` must be a valid pointer.
+ */
+enum nlzss11_status nlzss11_compress(const uint8_t *src,
+                                     size_t src_len,
+                                     uint8_t *dst,
+                                     size_t dst_capacity,
+                                     size_t *dst_len);
+
+/**
+ * Decompresses `src` into `dst`.
+ *
+ * On success and on `NLZSS11_STATUS_BUFFER_TOO_SMALL` the size of the decompressed data is
+ * written to `dst_len`.
+ *
+ * # Safety
+ *
+ * `src` must point to `src_len` readable bytes, `dst` to `dst_capacity` writable bytes
+ * and `dst_len` must be a valid pointer.
+ */
+enum nlzss11_status nlzss11_decompress(const uint8_t *src,
+                                       size_t src_len,
+                                       uint8_t *dst,
+                                       size_t dst_capacity,
+                                       size_t *dst_len);
+
+/**
+ * Returns a static, null terminated description of a status code.
+ */
+const char *nlzss11_status_message(enum nlzss11_status status);
+
+/**
+ * Creates a new streaming decompressor, it has to be freed with `nlzss11_decompress_free`.
+ */
+struct nlzss11_decompressor *nlzss11_decompress_init(void);
+
+/**
+ * Decompresses as much of `src` into `dst` as possible.
+ *
+ * The number of bytes read from `src` and written to `dst` are stored in `src_consumed` and
+ * `dst_written`. Call it again with the rest of the input or with more output space until it
+ * returns `NLZSS11_STATUS_STREAM_END`.
+ *
+ * # Safety
+ *
+ * `ctx` must come from `nlzss11_decompress_init`, `src` must point to `src_len` readable bytes,
+ * `dst` to `dst_capacity` writable bytes and `src_consumed` and `dst_written` must be valid
+ * pointers.
+ */
+enum nlzss11_status nlzss11_decompress_update(struct nlzss11_decompressor *ctx,
+                                              const uint8_t *src,
+                                              size_t src_len,
+                                              size_t *src_consumed,
+                                              uint8_t *dst,
+                                              size_t dst_capacity,
+                                              size_t *dst_written);
+
+/**
+ * Checks that the whole stream has been decompressed, to be called when there is no more input.
+ *
+ * # Safety
+ *
+ * `ctx` must come from `nlzss11_decompress_init`.
+ */
+enum nlzss11_status nlzss11_decompress_finish(const struct nlzss11_decompressor *ctx);
+
+/**
+ * Frees a streaming decompressor, passing null does nothing.
+ *
+ * # Safety
+ *
+ * `ctx` must come from `nlzss11_decompress_init` and must not be used afterwards.
+ */
+void nlzss11_decompress_free(struct nlzss11_decompressor *ctx);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NLZSS11_H */
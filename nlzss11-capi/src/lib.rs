@@ -0,0 +1,425 @@
+//! C ABI for the nlzss11 crate.
+//!
+//! The header `include/nlzss11.h` is generated from this file by cbindgen, building with the
+//! `NLZSS11_UPDATE_HEADER` environment variable set updates it, otherwise it only ends up in
+//! `OUT_DIR`.
+//! All functions take the input as pointer + length and write into a caller provided buffer,
+//! use `nlzss11_compress_bound` and `nlzss11_decompressed_size` to find out how big it has to be.
+
+use std::slice;
+
+use nlzss11::{
+    decompress_to_slice, decompressed_size, CompressError, DecompressError, Decompressor,
+};
+
+/// Result of every fallible function in this library.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nlzss11Status {
+    Ok = 0,
+    /// The input doesn't start with the 0x11 magic.
+    InvalidMagic = 1,
+    /// The input is truncated or references data that doesn't exist.
+    InvalidIndex = 2,
+    /// The output buffer is too small, the required size is written to the length out parameter.
+    BufferTooSmall = 3,
+    /// A required pointer argument was null.
+    NullPointer = 4,
+    /// Any other error while decompressing.
+    Other = 5,
+    /// The end of the compressed stream has been reached, returned by the streaming functions.
+    StreamEnd = 6,
+    /// The input is bigger than the 4 GiB the header can hold.
+    InputTooLarge = 7,
+}
+
+impl From<DecompressError> for Nlzss11Status {
+    fn from(e: DecompressError) -> Self {
+        match e {
+            DecompressError::InvalidMagic => Nlzss11Status::InvalidMagic,
+            DecompressError::InvalidIndex(_) => Nlzss11Status::InvalidIndex,
+            _ => Nlzss11Status::Other,
+        }
+    }
+}
+
+impl From<CompressError> for Nlzss11Status {
+    fn from(e: CompressError) -> Self {
+        match e {
+            CompressError::InputTooLarge(_) => Nlzss11Status::InputTooLarge,
+            _ => Nlzss11Status::Other,
+        }
+    }
+}
+
+/// Builds a slice from a C pointer + length, a null pointer is only allowed for empty slices.
+unsafe fn input_slice<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    if ptr.is_null() {
+        if len == 0 {
+            Some(&[])
+        } else {
+            None
+        }
+    } else {
+        Some(slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// Builds a writable slice from a C pointer + length, a null pointer is only allowed for empty
+/// slices.
+unsafe fn output_slice<'a>(ptr: *mut u8, len: usize) -> Option<&'a mut [u8]> {
+    if len == 0 {
+        Some(&mut [])
+    } else if ptr.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts_mut(ptr, len))
+    }
+}
+
+/// Compresses with the same compressor as `nlzss11::compress`, but returns an error instead of
+/// panicking for inputs the header can't hold, panics must not unwind into C.
+#[cfg(feature = "zlib")]
+fn try_compress(src: &[u8]) -> Result<Vec<u8>, CompressError> {
+    if src.len() as u64 > u64::from(u32::MAX) {
+        return Err(CompressError::InputTooLarge(src.len()));
+    }
+    Ok(nlzss11::compress(src))
+}
+
+#[cfg(not(feature = "zlib"))]
+use nlzss11::try_compress;
+
+/// Copies `result` into the C buffer or reports how much space would be needed.
+unsafe fn write_output(
+    result: &[u8],
+    dst: *mut u8,
+    dst_capacity: usize,
+    dst_len: *mut usize,
+) -> Nlzss11Status {
+    *dst_len = result.len();
+    if result.len() > dst_capacity {
+        return Nlzss11Status::BufferTooSmall;
+    }
+    if !result.is_empty() {
+        if dst.is_null() {
+            return Nlzss11Status::NullPointer;
+        }
+        std::ptr::copy_nonoverlapping(result.as_ptr(), dst, result.len());
+    }
+    Nlzss11Status::Ok
+}
+
+/// Maximum size of the compressed data for an input of `src_len` bytes.
+#[no_mangle]
+pub extern "C" fn nlzss11_compress_bound(src_len: usize) -> usize {
+    nlzss11::compress_bound(src_len)
+}
+
+/// Reads the decompressed size from the header of compressed data.
+///
+/// # Safety
+///
+/// `src` must point to `src_len` readable bytes, `out_size` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nlzss11_decompressed_size(
+    src: *const u8,
+    src_len: usize,
+    out_size: *mut usize,
+) -> Nlzss11Status {
+    let Some(src) = input_slice(src, src_len) else {
+        return Nlzss11Status::NullPointer;
+    };
+    if out_size.is_null() {
+        return Nlzss11Status::NullPointer;
+    }
+    match decompressed_size(src) {
+        Ok(size) => {
+            *out_size = usize::try_from(size).unwrap_or(usize::MAX);
+            Nlzss11Status::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Compresses `src` into `dst`.
+///
+/// On success and on `NLZSS11_STATUS_BUFFER_TOO_SMALL` the size of the compressed data is
+/// written to `dst_len`. Inputs over 4 GiB fail with `NLZSS11_STATUS_INPUT_TOO_LARGE`.
+///
+/// # Safety
+///
+/// `src` must point to `src_len` readable bytes, `dst` to `dst_capacity` writable bytes
+/// and `dst_len` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nlzss11_compress(
+    src: *const u8,
+    src_len: usize,
+    dst: *mut u8,
+    dst_capacity: usize,
+    dst_len: *mut usize,
+) -> Nlzss11Status {
+    let Some(src) = input_slice(src, src_len) else {
+        return Nlzss11Status::NullPointer;
+    };
+    if dst_len.is_null() {
+        return Nlzss11Status::NullPointer;
+    }
+    match try_compress(src) {
+        Ok(result) => write_output(&result, dst, dst_capacity, dst_len),
+        Err(e) => e.into(),
+    }
+}
+
+/// Decompresses `src` into `dst`.
+///
+/// On success and on `NLZSS11_STATUS_BUFFER_TOO_SMALL` the size of the decompressed data is
+/// written to `dst_len`.
+///
+/// # Safety
+///
+/// `src` must point to `src_len` readable bytes, `dst` to `dst_capacity` writable bytes
+/// and `dst_len` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nlzss11_decompress(
+    src: *const u8,
+    src_len: usize,
+    dst: *mut u8,
+    dst_capacity: usize,
+    dst_len: *mut usize,
+) -> Nlzss11Status {
+    let Some(src) = input_slice(src, src_len) else {
+        return Nlzss11Status::NullPointer;
+    };
+    if dst_len.is_null() {
+        return Nlzss11Status::NullPointer;
+    }
+    let size = match decompressed_size(src) {
+        Ok(size) => usize::try_from(size).unwrap_or(usize::MAX),
+        Err(e) => return e.into(),
+    };
+    *dst_len = size;
+    if size > dst_capacity {
+        return Nlzss11Status::BufferTooSmall;
+    }
+    let Some(dst) = output_slice(dst, dst_capacity) else {
+        return Nlzss11Status::NullPointer;
+    };
+    match decompress_to_slice(src, dst) {
+        Ok(written) => {
+            *dst_len = written;
+            Nlzss11Status::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Returns a static, null terminated description of a status code.
+#[no_mangle]
+pub extern "C" fn nlzss11_status_message(status: Nlzss11Status) -> *const std::ffi::c_char {
+    let msg: &'static [u8] = match status {
+        Nlzss11Status::Ok => b"ok\0",
+        Nlzss11Status::InvalidMagic => b"invalid magic\0",
+        Nlzss11Status::InvalidIndex => b"invalid index\0",
+        Nlzss11Status::BufferTooSmall => b"output buffer too small\0",
+        Nlzss11Status::NullPointer => b"null pointer\0",
+        Nlzss11Status::Other => b"other error\0",
+        Nlzss11Status::StreamEnd => b"end of stream\0",
+        Nlzss11Status::InputTooLarge => b"input too large\0",
+    };
+    msg.as_ptr().cast()
+}
+
+/// Opaque state for decompressing data that arrives in pieces.
+pub struct Nlzss11Decompressor(Decompressor);
+
+/// Creates a new streaming decompressor, it has to be freed with `nlzss11_decompress_free`.
+#[no_mangle]
+pub extern "C" fn nlzss11_decompress_init() -> *mut Nlzss11Decompressor {
+    Box::into_raw(Box::new(Nlzss11Decompressor(Decompressor::new())))
+}
+
+/// Decompresses as much of `src` into `dst` as possible.
+///
+/// The number of bytes read from `src` and written to `dst` are stored in `src_consumed` and
+/// `dst_written`. Call it again with the rest of the input or with more output space until it
+/// returns `NLZSS11_STATUS_STREAM_END`.
+///
+/// # Safety
+///
+/// `ctx` must come from `nlzss11_decompress_init`, `src` must point to `src_len` readable bytes,
+/// `dst` to `dst_capacity` writable bytes and `src_consumed` and `dst_written` must be valid
+/// pointers.
+#[no_mangle]
+pub unsafe extern "C" fn nlzss11_decompress_update(
+    ctx: *mut Nlzss11Decompressor,
+    src: *const u8,
+    src_len: usize,
+    src_consumed: *mut usize,
+    dst: *mut u8,
+    dst_capacity: usize,
+    dst_written: *mut usize,
+) -> Nlzss11Status {
+    let Some(ctx) = ctx.as_mut() else {
+        return Nlzss11Status::NullPointer;
+    };
+    let Some(src) = input_slice(src, src_len) else {
+        return Nlzss11Status::NullPointer;
+    };
+    if src_consumed.is_null() || dst_written.is_null() {
+        return Nlzss11Status::NullPointer;
+    }
+    let Some(dst) = output_slice(dst, dst_capacity) else {
+        return Nlzss11Status::NullPointer;
+    };
+    match ctx.0.decompress(src, dst) {
+        Ok((consumed, written)) => {
+            *src_consumed = consumed;
+            *dst_written = written;
+            if ctx.0.is_finished() {
+                Nlzss11Status::StreamEnd
+            } else {
+                Nlzss11Status::Ok
+            }
+        }
+        Err(e) => {
+            *src_consumed = 0;
+            *dst_written = 0;
+            e.into()
+        }
+    }
+}
+
+/// Checks that the whole stream has been decompressed, to be called when there is no more input.
+///
+/// # Safety
+///
+/// `ctx` must come from `nlzss11_decompress_init`.
+#[no_mangle]
+pub unsafe extern "C" fn nlzss11_decompress_finish(
+    ctx: *const Nlzss11Decompressor,
+) -> Nlzss11Status {
+    let Some(ctx) = ctx.as_ref() else {
+        return Nlzss11Status::NullPointer;
+    };
+    match ctx.0.finish() {
+        Ok(()) => Nlzss11Status::Ok,
+        Err(e) => e.into(),
+    }
+}
+
+/// Frees a streaming decompressor, passing null does nothing.
+///
+/// # Safety
+///
+/// `ctx` must come from `nlzss11_decompress_init` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nlzss11_decompress_free(ctx: *mut Nlzss11Decompressor) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nlzss11::compress;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = b"abcabcabcabcabcabcabcabcabcabcabc hello hello hello".repeat(20);
+        let mut compressed = vec![0; nlzss11_compress_bound(data.len())];
+        let mut compressed_len = 0;
+        let status = unsafe {
+            nlzss11_compress(
+                data.as_ptr(),
+                data.len(),
+                compressed.as_mut_ptr(),
+                compressed.len(),
+                &mut compressed_len,
+            )
+        };
+        assert_eq!(status, Nlzss11Status::Ok);
+
+        let mut size = 0;
+        let status =
+            unsafe { nlzss11_decompressed_size(compressed.as_ptr(), compressed_len, &mut size) };
+        assert_eq!(status, Nlzss11Status::Ok);
+        assert_eq!(size, data.len());
+
+        let mut too_small = [0; 4];
+        let mut out_len = 0;
+        let status = unsafe {
+            nlzss11_decompress(
+                compressed.as_ptr(),
+                compressed_len,
+                too_small.as_mut_ptr(),
+                too_small.len(),
+                &mut out_len,
+            )
+        };
+        assert_eq!(status, Nlzss11Status::BufferTooSmall);
+        assert_eq!(out_len, data.len());
+
+        // a bigger buffer than needed is fine, only the start is written
+        let mut decompressed = vec![0; size + 10];
+        let status = unsafe {
+            nlzss11_decompress(
+                compressed.as_ptr(),
+                compressed_len,
+                decompressed.as_mut_ptr(),
+                decompressed.len(),
+                &mut out_len,
+            )
+        };
+        assert_eq!(status, Nlzss11Status::Ok);
+        assert_eq!(out_len, data.len());
+        assert_eq!(decompressed[..out_len], data);
+
+        let prefixed = [b"LZ77".as_slice(), &compressed[..compressed_len]].concat();
+        let status =
+            unsafe { nlzss11_decompressed_size(prefixed.as_ptr(), prefixed.len(), &mut size) };
+        assert_eq!(status, Nlzss11Status::Ok);
+        assert_eq!(size, data.len());
+    }
+
+    #[test]
+    pub fn test_streaming() {
+        let data = b"streaming streaming streaming data".repeat(30);
+        let compressed = compress(&data);
+        let ctx = nlzss11_decompress_init();
+        let mut decompressed = Vec::new();
+        let mut out = [0; 64];
+        let mut status = Nlzss11Status::Ok;
+        for mut chunk in compressed.chunks(5) {
+            loop {
+                let mut consumed = 0;
+                let mut written = 0;
+                status = unsafe {
+                    nlzss11_decompress_update(
+                        ctx,
+                        chunk.as_ptr(),
+                        chunk.len(),
+                        &mut consumed,
+                        out.as_mut_ptr(),
+                        out.len(),
+                        &mut written,
+                    )
+                };
+                assert!(matches!(
+                    status,
+                    Nlzss11Status::Ok | Nlzss11Status::StreamEnd
+                ));
+                decompressed.extend_from_slice(&out[..written]);
+                chunk = &chunk[consumed..];
+                if consumed == 0 && written == 0 {
+                    break;
+                }
+            }
+        }
+        assert_eq!(status, Nlzss11Status::StreamEnd);
+        assert_eq!(unsafe { nlzss11_decompress_finish(ctx) }, Nlzss11Status::Ok);
+        unsafe { nlzss11_decompress_free(ctx) };
+        assert_eq!(decompressed, data);
+    }
+}
@@ -0,0 +1,255 @@
+//! Decompression only version of nlzss11 for places without std and without an allocator,
+//! like Wii/GameCube homebrew. The output is written into a caller provided buffer and no
+//! other memory is used.
+//!
+//! To get a static library for C code, build it for your target with the `ffi` and
+//! `panic-handler` features, for example with a devkitPPC target specification:
+//!
+//! ```sh
+//! cargo +nightly rustc -p nlzss11-decode --release --features ffi,panic-handler \
+//!     --target powerpc-unknown-eabi.json -Z build-std=core --crate-type staticlib \
+//!     -- -C panic=abort
+//! ```
+//!
+//! and declare the functions in C:
+//!
+//! ```c
+//! int32_t nlzss11_decoded_size(const uint8_t *src, size_t src_len);
+//! int32_t nlzss11_decode(const uint8_t *src, size_t src_len, uint8_t *dst, size_t dst_len);
+//! ```
+//!
+//! Both return a negative [`DecodeError`] code on failure, sizes above `i32::MAX` are reported
+//! as [`DecodeError::SizeTooLarge`].
+
+#![cfg_attr(not(test), no_std)]
+
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum DecodeError {
+    /// The data doesn't start with the 0x11 magic.
+    InvalidMagic = -1,
+    /// The data ends before the decompressed size has been reached.
+    Truncated = -2,
+    /// A backreference points before the start of the output.
+    InvalidBackreference = -3,
+    /// The output buffer is smaller than the decompressed size.
+    OutputTooSmall = -4,
+    /// The decompressed size doesn't fit into the return value of the C functions.
+    SizeTooLarge = -5,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DecodeError::InvalidMagic => "invalid magic",
+            DecodeError::Truncated => "truncated data",
+            DecodeError::InvalidBackreference => "invalid backreference",
+            DecodeError::OutputTooSmall => "output buffer too small",
+            DecodeError::SizeTooLarge => "decompressed size too large",
+        })
+    }
+}
+
+/// Reads the decompressed size and the size of the header.
+fn read_header(src: &[u8]) -> Result<(usize, usize), DecodeError> {
+    let header = src.get(..4).ok_or(DecodeError::Truncated)?;
+    if header[0] != 0x11 {
+        return Err(DecodeError::InvalidMagic);
+    }
+    let size = u32::from_le_bytes([header[1], header[2], header[3], 0]);
+    if size != 0 {
+        return Ok((size as usize, 4));
+    }
+    let ext = src.get(4..8).ok_or(DecodeError::Truncated)?;
+    Ok((
+        u32::from_le_bytes([ext[0], ext[1], ext[2], ext[3]]) as usize,
+        8,
+    ))
+}
+
+/// Size of the decompressed data, read from the header.
+pub fn decoded_size(src: &[u8]) -> Result<usize, DecodeError> {
+    read_header(src).map(|(size, _)| size)
+}
+
+/// Decompresses `src` into `dst` and returns the decompressed size.
+pub fn decode(src: &[u8], dst: &mut [u8]) -> Result<usize, DecodeError> {
+    let (size, mut pos) = read_header(src)?;
+    let dst = dst.get_mut(..size).ok_or(DecodeError::OutputTooSmall)?;
+    let byte = |pos: &mut usize| {
+        let b = src.get(*pos).copied().ok_or(DecodeError::Truncated);
+        *pos += 1;
+        b
+    };
+    let mut out = 0;
+    let mut group_header = 0u8;
+    let mut remaining_chunks = 0;
+    while out < size {
+        // one byte indicates if the next 8 blocks are literals or backreferences
+        if remaining_chunks == 0 {
+            group_header = byte(&mut pos)?;
+            remaining_chunks = 8;
+        }
+        if group_header & 0x80 == 0 {
+            dst[out] = byte(&mut pos)?;
+            out += 1;
+        } else {
+            let b0 = byte(&mut pos)? as usize;
+            let b1 = byte(&mut pos)? as usize;
+            let (length, distance) = match b0 >> 4 {
+                0 => {
+                    let b2 = byte(&mut pos)? as usize;
+                    (((b0 << 4) | (b1 >> 4)) + 0x11, (((b1 & 0xF) << 8) | b2) + 1)
+                }
+                1 => {
+                    let b2 = byte(&mut pos)? as usize;
+                    let b3 = byte(&mut pos)? as usize;
+                    (
+                        (((b0 & 0xF) << 12) | (b1 << 4) | (b2 >> 4)) + 0x111,
+                        (((b2 & 0xF) << 8) | b3) + 1,
+                    )
+                }
+                _ => ((b0 >> 4) + 1, (((b0 & 0xF) << 8) | b1) + 1),
+            };
+            if distance > out {
+                return Err(DecodeError::InvalidBackreference);
+            }
+            let length = length.min(size - out);
+            // byte by byte, the regions can overlap
+            for i in out..out + length {
+                dst[i] = dst[i - distance];
+            }
+            out += length;
+        }
+        group_header <<= 1;
+        remaining_chunks -= 1;
+    }
+    Ok(size)
+}
+
+#[cfg(feature = "ffi")]
+mod ffi {
+    use core::slice;
+
+    use super::{decode, decoded_size, DecodeError};
+
+    unsafe fn src_slice<'a>(src: *const u8, src_len: usize) -> &'a [u8] {
+        if src.is_null() {
+            &[]
+        } else {
+            slice::from_raw_parts(src, src_len)
+        }
+    }
+
+    /// The size from the header, an error if it doesn't fit into the return value.
+    fn checked_size(src: &[u8]) -> Result<i32, DecodeError> {
+        let size = decoded_size(src)?;
+        i32::try_from(size).map_err(|_| DecodeError::SizeTooLarge)
+    }
+
+    /// # Safety
+    ///
+    /// `src` must point to `src_len` readable bytes.
+    #[no_mangle]
+    pub unsafe extern "C" fn nlzss11_decoded_size(src: *const u8, src_len: usize) -> i32 {
+        match checked_size(src_slice(src, src_len)) {
+            Ok(size) => size,
+            Err(e) => e as i32,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `src` must point to `src_len` readable bytes and `dst` to `dst_len` writable bytes.
+    #[no_mangle]
+    pub unsafe extern "C" fn nlzss11_decode(
+        src: *const u8,
+        src_len: usize,
+        dst: *mut u8,
+        dst_len: usize,
+    ) -> i32 {
+        let dst: &mut [u8] = if dst.is_null() {
+            &mut []
+        } else {
+            slice::from_raw_parts_mut(dst, dst_len)
+        };
+        let src = src_slice(src, src_len);
+        // checked before decoding, so nothing is written if the size can't be returned
+        match checked_size(src).and_then(|size| decode(src, dst).map(|_| size)) {
+            Ok(size) => size,
+            Err(e) => e as i32,
+        }
+    }
+}
+
+#[cfg(all(feature = "panic-handler", not(test)))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, decoded_size, DecodeError};
+
+    #[test]
+    pub fn test_decode() {
+        let data: Vec<u8> = (0..50000u32)
+            .map(|i| (i % 300) as u8 ^ (i / 5000) as u8)
+            .chain([7; 70000])
+            .collect();
+        let compressed = nlzss11::compress(&data);
+        assert_eq!(decoded_size(&compressed), Ok(data.len()));
+        let mut out = vec![0; data.len()];
+        assert_eq!(decode(&compressed, &mut out), Ok(data.len()));
+        assert_eq!(out, data);
+
+        assert_eq!(
+            decode(&compressed, &mut out[..10]),
+            Err(DecodeError::OutputTooSmall)
+        );
+        assert_eq!(
+            decode(&compressed[..compressed.len() - 1], &mut out),
+            Err(DecodeError::Truncated)
+        );
+        assert_eq!(
+            decode(&[0x10, 0, 0, 0], &mut out),
+            Err(DecodeError::InvalidMagic)
+        );
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    pub fn test_ffi() {
+        use super::ffi::{nlzss11_decode, nlzss11_decoded_size};
+
+        let compressed = nlzss11::compress(b"ffi ffi ffi ffi");
+        let mut out = [0; 15];
+        unsafe {
+            assert_eq!(
+                nlzss11_decoded_size(compressed.as_ptr(), compressed.len()),
+                15
+            );
+            assert_eq!(
+                nlzss11_decode(compressed.as_ptr(), compressed.len(), out.as_mut_ptr(), 15),
+                15
+            );
+        }
+        assert_eq!(&out, b"ffi ffi ffi ffi");
+
+        // 4 GiB can't be returned as i32
+        let huge = [0x11, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+        unsafe {
+            assert_eq!(
+                nlzss11_decoded_size(huge.as_ptr(), huge.len()),
+                DecodeError::SizeTooLarge as i32
+            );
+            assert_eq!(
+                nlzss11_decode(huge.as_ptr(), huge.len(), out.as_mut_ptr(), 15),
+                DecodeError::SizeTooLarge as i32
+            );
+        }
+    }
+}
@@ -0,0 +1,35 @@
+using System;
+using System.Linq;
+using System.Text;
+using Xunit;
+
+namespace Nlzss11.Tests
+{
+    public class Lzss11Tests
+    {
+        [Fact]
+        public void Roundtrip()
+        {
+            var data = Encoding.ASCII.GetBytes(string.Concat(Enumerable.Repeat("hello nlzss11 ", 100)));
+            var compressed = Lzss11.Compress(data);
+            Assert.True(compressed.Length < data.Length);
+            Assert.Equal(data.Length, Lzss11.GetDecompressedSize(compressed));
+            Assert.Equal(data, Lzss11.Decompress(compressed));
+        }
+
+        [Fact]
+        public void InvalidData()
+        {
+            var e = Assert.Throws<Nlzss11Exception>(() => Lzss11.Decompress(new byte[] { 0x10, 0, 0, 0 }));
+            Assert.Equal(Nlzss11Status.InvalidMagic, e.Status);
+        }
+
+        [Fact]
+        public void DestinationTooSmall()
+        {
+            var compressed = Lzss11.Compress(new byte[100]);
+            var e = Assert.Throws<Nlzss11Exception>(() => Lzss11.Decompress(compressed, new byte[10]));
+            Assert.Equal(Nlzss11Status.BufferTooSmall, e.Status);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+using System;
+using System.Runtime.InteropServices;
+
+namespace Nlzss11
+{
+    /// <summary>Thrown when the native library reports an error.</summary>
+    public class Nlzss11Exception : Exception
+    {
+        public Nlzss11Status Status { get; }
+
+        public Nlzss11Exception(Nlzss11Status status)
+            : base(Marshal.PtrToStringAnsi(NativeMethods.nlzss11_status_message(status)))
+        {
+            Status = status;
+        }
+    }
+
+    /// <summary>Compression and decompression of nlzss11 data.</summary>
+    public static unsafe class Lzss11
+    {
+        /// <summary>Maximum size of the compressed data for an input of <paramref name="length"/> bytes.</summary>
+        public static int CompressBound(int length)
+        {
+            return checked((int)NativeMethods.nlzss11_compress_bound((UIntPtr)length));
+        }
+
+        /// <summary>Reads the decompressed size from the header of compressed data.</summary>
+        public static int GetDecompressedSize(ReadOnlySpan<byte> compressed)
+        {
+            UIntPtr size;
+            fixed (byte* src = compressed)
+            {
+                Check(NativeMethods.nlzss11_decompressed_size(src, (UIntPtr)compressed.Length, &size));
+            }
+            return checked((int)size);
+        }
+
+        public static byte[] Compress(ReadOnlySpan<byte> data)
+        {
+            var output = new byte[CompressBound(data.Length)];
+            int length = Compress(data, output);
+            Array.Resize(ref output, length);
+            return output;
+        }
+
+        /// <summary>Compresses into <paramref name="destination"/> and returns the compressed size.</summary>
+        public static int Compress(ReadOnlySpan<byte> data, Span<byte> destination)
+        {
+            UIntPtr written;
+            fixed (byte* src = data)
+            fixed (byte* dst = destination)
+            {
+                Check(NativeMethods.nlzss11_compress(src, (UIntPtr)data.Length, dst, (UIntPtr)destination.Length, &written));
+            }
+            return (int)written;
+        }
+
+        public static byte[] Decompress(ReadOnlySpan<byte> compressed)
+        {
+            var output = new byte[GetDecompressedSize(compressed)];
+            Decompress(compressed, output);
+            return output;
+        }
+
+        /// <summary>Decompresses into <paramref name="destination"/> and returns the decompressed size.</summary>
+        public static int Decompress(ReadOnlySpan<byte> compressed, Span<byte> destination)
+        {
+            UIntPtr written;
+            fixed (byte* src = compressed)
+            fixed (byte* dst = destination)
+            {
+                Check(NativeMethods.nlzss11_decompress(src, (UIntPtr)compressed.Length, dst, (UIntPtr)destination.Length, &written));
+            }
+            return (int)written;
+        }
+
+        private static void Check(Nlzss11Status status)
+        {
+            if (status != Nlzss11Status.Ok)
+            {
+                throw new Nlzss11Exception(status);
+            }
+        }
+    }
+}
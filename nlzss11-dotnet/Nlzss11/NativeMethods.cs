@@ -0,0 +1,39 @@
+using System;
+using System.Runtime.InteropServices;
+
+namespace Nlzss11
+{
+    /// <summary>Status codes returned by the native library, see nlzss11.h.</summary>
+    public enum Nlzss11Status
+    {
+        Ok = 0,
+        InvalidMagic = 1,
+        InvalidIndex = 2,
+        BufferTooSmall = 3,
+        NullPointer = 4,
+        Other = 5,
+        StreamEnd = 6,
+        InputTooLarge = 7,
+    }
+
+    /// <summary>P/Invoke declarations for the functions in nlzss11.h.</summary>
+    internal static unsafe class NativeMethods
+    {
+        private const string Library = "nlzss11_capi";
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern UIntPtr nlzss11_compress_bound(UIntPtr srcLen);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern Nlzss11Status nlzss11_decompressed_size(byte* src, UIntPtr srcLen, UIntPtr* outSize);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern Nlzss11Status nlzss11_compress(byte* src, UIntPtr srcLen, byte* dst, UIntPtr dstCapacity, UIntPtr* dstLen);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern Nlzss11Status nlzss11_decompress(byte* src, UIntPtr srcLen, byte* dst, UIntPtr dstCapacity, UIntPtr* dstLen);
+
+        [DllImport(Library, CallingConvention = CallingConvention.Cdecl)]
+        public static extern IntPtr nlzss11_status_message(Nlzss11Status status);
+    }
+}
@@ -0,0 +1,270 @@
+//! Mounts a directory of `.LZ` files as read-only filesystem, every file shows up without the
+//! `.LZ` extension and reading it returns the decompressed contents.
+//!
+//! Files are only decompressed when they are read and kept in a cache of limited size, the
+//! sizes shown in directory listings come from the headers.
+//!
+//! Needs libfuse (Linux) or macFUSE, which is why this isn't part of the workspace.
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+use clap::Parser;
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request, FUSE_ROOT_ID,
+};
+
+const TTL: Duration = Duration::from_secs(60);
+
+#[derive(Parser, Debug)]
+#[clap(about = "mount a directory of nlzss11 compressed files as their decompressed contents")]
+struct Args {
+    #[clap(help = "Directory containing the .LZ files")]
+    source: PathBuf,
+    #[clap(help = "Where to mount the decompressed view")]
+    mountpoint: PathBuf,
+    #[clap(
+        long,
+        default_value_t = 256,
+        help = "Maximum size of decompressed files kept in memory, in MiB"
+    )]
+    cache_size: usize,
+}
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    size: u64,
+    mtime: SystemTime,
+}
+
+/// Decompressed files, the least recently used ones get dropped first.
+struct Cache {
+    max_size: usize,
+    size: usize,
+    files: HashMap<u64, Rc<Vec<u8>>>,
+    // least recently used first
+    order: Vec<u64>,
+}
+
+impl Cache {
+    fn get(&mut self, ino: u64) -> Option<Rc<Vec<u8>>> {
+        let data = self.files.get(&ino)?.clone();
+        self.order.retain(|&i| i != ino);
+        self.order.push(ino);
+        Some(data)
+    }
+
+    fn insert(&mut self, ino: u64, data: Rc<Vec<u8>>) {
+        while self.size + data.len() > self.max_size && !self.order.is_empty() {
+            let evicted = self.order.remove(0);
+            if let Some(evicted) = self.files.remove(&evicted) {
+                self.size -= evicted.len();
+            }
+        }
+        self.size += data.len();
+        self.files.insert(ino, data);
+        self.order.push(ino);
+    }
+}
+
+struct DecompressedFs {
+    entries: Vec<Entry>,
+    cache: Cache,
+    uid: u32,
+    gid: u32,
+}
+
+/// Reads just the header to get the decompressed size.
+fn read_decompressed_size(path: &Path) -> Option<u64> {
+    let mut header = [0; 8];
+    let mut file = File::open(path).ok()?;
+    let mut len = 0;
+    while len < header.len() {
+        match file.read(&mut header[len..]).ok()? {
+            0 => break,
+            read => len += read,
+        }
+    }
+    nlzss11::decompressed_size(&header[..len]).ok()
+}
+
+fn scan_dir(source: &Path) -> std::io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for dir_entry in std::fs::read_dir(source)? {
+        let dir_entry = dir_entry?;
+        let file_name = dir_entry.file_name();
+        let Some(name) = file_name.to_str().and_then(|n| n.strip_suffix(".LZ")) else {
+            continue;
+        };
+        let path = dir_entry.path();
+        // files that don't look compressed are skipped
+        let Some(size) = read_decompressed_size(&path) else {
+            continue;
+        };
+        let mtime = dir_entry
+            .metadata()?
+            .modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push(Entry {
+            name: name.to_string(),
+            path,
+            size,
+            mtime,
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+impl DecompressedFs {
+    fn entry(&self, ino: u64) -> Option<&Entry> {
+        self.entries
+            .get(ino.checked_sub(FUSE_ROOT_ID + 1)? as usize)
+    }
+
+    fn attr(&self, ino: u64) -> Option<FileAttr> {
+        let (kind, perm, size, mtime) = if ino == FUSE_ROOT_ID {
+            (FileType::Directory, 0o555, 0, SystemTime::UNIX_EPOCH)
+        } else {
+            let entry = self.entry(ino)?;
+            (FileType::RegularFile, 0o444, entry.size, entry.mtime)
+        };
+        Some(FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink: if kind == FileType::Directory { 2 } else { 1 },
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        })
+    }
+
+    fn decompressed(&mut self, ino: u64) -> Result<Rc<Vec<u8>>, i32> {
+        if let Some(data) = self.cache.get(ino) {
+            return Ok(data);
+        }
+        let entry = self.entry(ino).ok_or(libc::ENOENT)?;
+        let compressed = std::fs::read(&entry.path).map_err(|_| libc::EIO)?;
+        let data = Rc::new(nlzss11::decompress(&compressed).map_err(|_| libc::EIO)?);
+        self.cache.insert(ino, data.clone());
+        Ok(data)
+    }
+}
+
+impl Filesystem for DecompressedFs {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let found = (parent == FUSE_ROOT_ID)
+            .then(|| {
+                self.entries
+                    .iter()
+                    .position(|e| OsStr::new(&e.name) == name)
+            })
+            .flatten();
+        match found.and_then(|index| self.attr(index as u64 + FUSE_ROOT_ID + 1)) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+        match self.attr(ino) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        match self.decompressed(ino) {
+            Ok(data) => {
+                let start = (offset.max(0) as usize).min(data.len());
+                let end = (start + size as usize).min(data.len());
+                reply.data(&data[start..end]);
+            }
+            Err(errno) => reply.error(errno),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        if ino != FUSE_ROOT_ID {
+            reply.error(libc::ENOTDIR);
+            return;
+        }
+        let dots = [
+            (FUSE_ROOT_ID, FileType::Directory, "."),
+            (FUSE_ROOT_ID, FileType::Directory, ".."),
+        ];
+        let files = self.entries.iter().enumerate().map(|(index, e)| {
+            (
+                index as u64 + FUSE_ROOT_ID + 1,
+                FileType::RegularFile,
+                e.name.as_str(),
+            )
+        });
+        for (i, (ino, kind, name)) in dots
+            .into_iter()
+            .chain(files)
+            .enumerate()
+            .skip(offset as usize)
+        {
+            // the offset passed back to us is the one of the next entry
+            if reply.add(ino, i as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let args = Args::parse();
+    let fs = DecompressedFs {
+        entries: scan_dir(&args.source)?,
+        cache: Cache {
+            max_size: args.cache_size << 20,
+            size: 0,
+            files: HashMap::new(),
+            order: Vec::new(),
+        },
+        uid: unsafe { libc::getuid() },
+        gid: unsafe { libc::getgid() },
+    };
+    let options = [
+        MountOption::RO,
+        MountOption::FSName("nlzss11".to_string()),
+        MountOption::DefaultPermissions,
+    ];
+    fuser::mount2(fs, &args.mountpoint, &options)
+}
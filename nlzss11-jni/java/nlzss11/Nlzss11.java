@@ -0,0 +1,47 @@
+package nlzss11;
+
+import java.nio.ByteBuffer;
+
+/** Compression and decompression of Nintendo's LZSS (LZ11) format. */
+public final class Nlzss11 {
+    static {
+        System.loadLibrary("nlzss11_jni");
+    }
+
+    private Nlzss11() {}
+
+    public static native byte[] compress(byte[] data);
+
+    public static native byte[] decompress(byte[] data) throws Nlzss11Exception;
+
+    private static native byte[] compressDirect(ByteBuffer data, int offset, int len);
+
+    private static native byte[] decompressDirect(ByteBuffer data, int offset, int len)
+            throws Nlzss11Exception;
+
+    /** Compresses the remaining bytes of {@code data}, consuming them. */
+    public static byte[] compress(ByteBuffer data) {
+        if (!data.isDirect()) {
+            return compress(remaining(data));
+        }
+        byte[] compressed = compressDirect(data, data.position(), data.remaining());
+        data.position(data.limit());
+        return compressed;
+    }
+
+    /** Decompresses the remaining bytes of {@code data}, consuming them. */
+    public static byte[] decompress(ByteBuffer data) throws Nlzss11Exception {
+        if (!data.isDirect()) {
+            return decompress(remaining(data));
+        }
+        byte[] decompressed = decompressDirect(data, data.position(), data.remaining());
+        data.position(data.limit());
+        return decompressed;
+    }
+
+    private static byte[] remaining(ByteBuffer data) {
+        byte[] array = new byte[data.remaining()];
+        data.get(array);
+        return array;
+    }
+}
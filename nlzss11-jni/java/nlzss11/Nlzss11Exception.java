@@ -0,0 +1,10 @@
+package nlzss11;
+
+import java.io.IOException;
+
+/** Thrown when data isn't valid nlzss11 compressed data. */
+public class Nlzss11Exception extends IOException {
+    public Nlzss11Exception(String message) {
+        super(message);
+    }
+}
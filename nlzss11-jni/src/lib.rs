@@ -0,0 +1,126 @@
+//! JNI bindings for the nlzss11 crate, the native methods of `nlzss11.Nlzss11` in `java/`.
+//!
+//! Invalid compressed data throws `nlzss11.Nlzss11Exception`, `null` arrays throw
+//! `NullPointerException`.
+
+use std::slice;
+
+use jni::objects::{JByteArray, JByteBuffer, JClass};
+use jni::sys::{jbyteArray, jint};
+use jni::JNIEnv;
+use nlzss11::DecompressError;
+
+const EXCEPTION_CLASS: &str = "nlzss11/Nlzss11Exception";
+
+enum Error {
+    Decompress(DecompressError),
+    Jni(jni::errors::Error),
+    InvalidBuffer(&'static str),
+}
+
+impl From<jni::errors::Error> for Error {
+    fn from(e: jni::errors::Error) -> Self {
+        Error::Jni(e)
+    }
+}
+
+/// Returns the array to java or throws the matching exception and returns `null`.
+fn into_java(env: &mut JNIEnv, result: Result<JByteArray, Error>) -> jbyteArray {
+    let (class, message) = match result {
+        Ok(array) => return array.into_raw(),
+        // the exception is already pending
+        Err(Error::Jni(jni::errors::Error::JavaException)) => return std::ptr::null_mut(),
+        Err(Error::Jni(jni::errors::Error::NullPtr(what))) => {
+            ("java/lang/NullPointerException", what.to_string())
+        }
+        Err(Error::Jni(e)) => ("java/lang/RuntimeException", e.to_string()),
+        Err(Error::Decompress(e)) => (EXCEPTION_CLASS, e.to_string()),
+        Err(Error::InvalidBuffer(message)) => {
+            ("java/lang/IllegalArgumentException", message.to_string())
+        }
+    };
+    if !env.exception_check().unwrap_or(true) {
+        let _ = env.throw_new(class, message);
+    }
+    std::ptr::null_mut()
+}
+
+/// Views `len` bytes starting at `offset` of a direct buffer.
+fn direct_buffer<'a>(
+    env: &JNIEnv,
+    buf: &'a JByteBuffer,
+    offset: jint,
+    len: jint,
+) -> Result<&'a [u8], Error> {
+    let ptr = env.get_direct_buffer_address(buf)?;
+    let capacity = env.get_direct_buffer_capacity(buf)?;
+    let (Ok(offset), Ok(len)) = (usize::try_from(offset), usize::try_from(len)) else {
+        return Err(Error::InvalidBuffer("negative offset or length"));
+    };
+    if offset.checked_add(len).is_none_or(|end| end > capacity) {
+        return Err(Error::InvalidBuffer("range out of bounds"));
+    }
+    if len == 0 {
+        return Ok(&[]);
+    }
+    // the buffer can't be freed while the reference is alive
+    Ok(unsafe { slice::from_raw_parts(ptr.add(offset), len) })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_nlzss11_Nlzss11_compress<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    data: JByteArray<'local>,
+) -> jbyteArray {
+    let result = (|| {
+        let data = env.convert_byte_array(&data)?;
+        Ok(env.byte_array_from_slice(&nlzss11::compress(&data))?)
+    })();
+    into_java(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_nlzss11_Nlzss11_decompress<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    data: JByteArray<'local>,
+) -> jbyteArray {
+    let result = (|| {
+        let data = env.convert_byte_array(&data)?;
+        let decompressed = nlzss11::decompress(&data).map_err(Error::Decompress)?;
+        Ok(env.byte_array_from_slice(&decompressed)?)
+    })();
+    into_java(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_nlzss11_Nlzss11_compressDirect<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    buf: JByteBuffer<'local>,
+    offset: jint,
+    len: jint,
+) -> jbyteArray {
+    let result = (|| {
+        let data = direct_buffer(&env, &buf, offset, len)?;
+        Ok(env.byte_array_from_slice(&nlzss11::compress(data))?)
+    })();
+    into_java(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_nlzss11_Nlzss11_decompressDirect<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    buf: JByteBuffer<'local>,
+    offset: jint,
+    len: jint,
+) -> jbyteArray {
+    let result = (|| {
+        let data = direct_buffer(&env, &buf, offset, len)?;
+        let decompressed = nlzss11::decompress(data).map_err(Error::Decompress)?;
+        Ok(env.byte_array_from_slice(&decompressed)?)
+    })();
+    into_java(&mut env, result)
+}
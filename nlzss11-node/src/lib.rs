@@ -0,0 +1,71 @@
+//! Node.js bindings for the nlzss11 crate.
+//!
+//! `compress` and `decompress` run on the libuv thread pool and return promises,
+//! `compressSync` and `decompressSync` block the calling thread.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+fn to_napi_error(e: nlzss11::DecompressError) -> Error {
+    Error::new(Status::InvalidArg, e.to_string())
+}
+
+pub struct CompressTask {
+    data: Buffer,
+}
+
+impl Task for CompressTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(nlzss11::compress(&self.data))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+pub struct DecompressTask {
+    data: Buffer,
+}
+
+impl Task for DecompressTask {
+    type Output = Vec<u8>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        nlzss11::decompress(&self.data).map_err(to_napi_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into())
+    }
+}
+
+/// Compresses `data` in the background.
+#[napi(ts_return_type = "Promise<Buffer>")]
+pub fn compress(data: Buffer) -> AsyncTask<CompressTask> {
+    AsyncTask::new(CompressTask { data })
+}
+
+/// Decompresses `data` in the background, rejects if it isn't valid compressed data.
+#[napi(ts_return_type = "Promise<Buffer>")]
+pub fn decompress(data: Buffer) -> AsyncTask<DecompressTask> {
+    AsyncTask::new(DecompressTask { data })
+}
+
+/// Compresses `data` on the calling thread.
+#[napi]
+pub fn compress_sync(data: Buffer) -> Buffer {
+    nlzss11::compress(&data).into()
+}
+
+/// Decompresses `data` on the calling thread, throws if it isn't valid compressed data.
+#[napi]
+pub fn decompress_sync(data: Buffer) -> Result<Buffer> {
+    nlzss11::decompress(&data)
+        .map(Buffer::from)
+        .map_err(to_napi_error)
+}
@@ -0,0 +1,226 @@
+//! Python bindings for the nlzss11 crate, built with maturin into the `nlzss11` module.
+//!
+//! All functions accept any object supporting the buffer protocol (`bytes`, `bytearray`,
+//! `memoryview`, numpy arrays, `mmap`, ...) as input and read contiguous buffers in place.
+
+use std::borrow::Cow;
+use std::slice;
+
+use pyo3::buffer::PyBuffer;
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+create_exception!(
+    nlzss11,
+    DecompressError,
+    PyValueError,
+    "Raised when the data isn't valid nlzss11 compressed data."
+);
+
+/// Level used by `compress` if none is given, same as the rust `compress` function.
+#[cfg(feature = "zlib")]
+const DEFAULT_LEVEL: i32 = 7;
+/// `Level::DEFAULT`
+#[cfg(not(feature = "zlib"))]
+const DEFAULT_LEVEL: i32 = 6;
+
+#[cfg(feature = "zlib")]
+fn compress_with_level(data: &[u8], level: i32) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    nlzss11::compress_with_zlib_into(data, &mut out, level);
+    out
+}
+
+/// `level` has to be checked with `check_level`.
+#[cfg(not(feature = "zlib"))]
+fn compress_with_level(data: &[u8], level: i32) -> Vec<u8> {
+    nlzss11::compress_with_level(data, nlzss11::Level::new(level as u8))
+}
+
+fn check_level(level: i32) -> PyResult<()> {
+    if !(0..=9).contains(&level) {
+        return Err(PyValueError::new_err(format!(
+            "level has to be between 0 and 9, got {}",
+            level
+        )));
+    }
+    Ok(())
+}
+
+/// The contents of `buf`, without copying them if it is contiguous.
+///
+/// The GIL has to be held while the data is used, other Python threads could change the buffer
+/// otherwise.
+fn read_buffer<'a>(py: Python<'_>, buf: &'a PyBuffer<u8>) -> PyResult<Cow<'a, [u8]>> {
+    if !buf.is_c_contiguous() {
+        return Ok(Cow::Owned(buf.to_vec(py)?));
+    }
+    if buf.len_bytes() == 0 {
+        return Ok(Cow::Borrowed(&[]));
+    }
+    // SAFETY: the buffer is contiguous with `len_bytes` bytes and stays alive as long as `buf`,
+    // no Python code can change it while the caller holds the GIL
+    let data = unsafe { slice::from_raw_parts(buf.buf_ptr() as *const u8, buf.len_bytes()) };
+    Ok(Cow::Borrowed(data))
+}
+
+/// The contents of `buf`, which has to be checked with `check_writable` and `check_no_overlap`.
+///
+/// The GIL has to be held while the data is used, other Python threads could access the buffer
+/// otherwise.
+fn write_buffer(buf: &mut PyBuffer<u8>) -> &mut [u8] {
+    if buf.len_bytes() == 0 {
+        return &mut [];
+    }
+    // SAFETY: the buffer is writable and contiguous with `len_bytes` bytes and stays alive as
+    // long as `buf`, it doesn't overlap the input and no Python code can access it while the
+    // caller holds the GIL
+    unsafe { slice::from_raw_parts_mut(buf.buf_ptr() as *mut u8, buf.len_bytes()) }
+}
+
+/// Checks that `buf` can be written to.
+fn check_writable(buf: &PyBuffer<u8>) -> PyResult<()> {
+    if buf.readonly() {
+        return Err(PyValueError::new_err("output buffer has to be writable"));
+    }
+    if !buf.is_c_contiguous() {
+        return Err(PyValueError::new_err("output buffer has to be contiguous"));
+    }
+    Ok(())
+}
+
+/// Rejects an output buffer sharing memory with the input.
+fn check_no_overlap(data: &PyBuffer<u8>, out: &PyBuffer<u8>) -> PyResult<()> {
+    let data_start = data.buf_ptr() as usize;
+    let out_start = out.buf_ptr() as usize;
+    if data.len_bytes() != 0
+        && out.len_bytes() != 0
+        && data_start < out_start + out.len_bytes()
+        && out_start < data_start + data.len_bytes()
+    {
+        return Err(PyValueError::new_err(
+            "input and output buffer must not overlap",
+        ));
+    }
+    Ok(())
+}
+
+/// Copies `data` to the start of `out`, which has to be checked with `check_writable` and
+/// `check_no_overlap`.
+///
+/// Only call this while holding the GIL, so no Python code can access `out` at the same time.
+fn write_to_buffer(out: &mut PyBuffer<u8>, data: &[u8]) -> PyResult<()> {
+    if data.len() > out.len_bytes() {
+        return Err(PyValueError::new_err(format!(
+            "output buffer too small, {} bytes are needed",
+            data.len()
+        )));
+    }
+    write_buffer(out)[..data.len()].copy_from_slice(data);
+    Ok(())
+}
+
+/// Compresses `data`, `level` has to be between 0 and 9.
+///
+/// With `release_gil=True` other Python threads can run in the meantime, which needs a copy
+/// of `data` so they can't change it in the middle.
+#[pyfunction]
+#[pyo3(signature = (data, level = DEFAULT_LEVEL, *, release_gil = false))]
+fn compress<'py>(
+    py: Python<'py>,
+    data: PyBuffer<u8>,
+    level: i32,
+    release_gil: bool,
+) -> PyResult<Bound<'py, PyBytes>> {
+    check_level(level)?;
+    let compressed = if release_gil {
+        let data = data.to_vec(py)?;
+        py.allow_threads(|| compress_with_level(&data, level))
+    } else {
+        compress_with_level(&read_buffer(py, &data)?, level)
+    };
+    Ok(PyBytes::new(py, &compressed))
+}
+
+/// Compresses `data` into the writable buffer `out` and returns the compressed size.
+///
+/// Raises `ValueError` if `out` is too small or overlaps `data`. `out` is always big enough
+/// with `compress_bound(len(data))` bytes.
+#[pyfunction]
+#[pyo3(signature = (data, out, level = DEFAULT_LEVEL))]
+fn compress_into(
+    py: Python<'_>,
+    data: PyBuffer<u8>,
+    mut out: PyBuffer<u8>,
+    level: i32,
+) -> PyResult<usize> {
+    check_level(level)?;
+    check_writable(&out)?;
+    check_no_overlap(&data, &out)?;
+    let data = read_buffer(py, &data)?;
+    let compressed = compress_with_level(&data, level);
+    write_to_buffer(&mut out, &compressed)?;
+    Ok(compressed.len())
+}
+
+/// The biggest size the compressed data of `len` bytes can have.
+#[pyfunction]
+fn compress_bound(len: usize) -> usize {
+    nlzss11::compress_bound(len)
+}
+
+/// Decompresses `data`, raises `DecompressError` if it is invalid.
+///
+/// With `release_gil=True` other Python threads can run in the meantime, which needs a copy
+/// of `data` so they can't change it in the middle.
+#[pyfunction]
+#[pyo3(signature = (data, *, release_gil = false))]
+fn decompress<'py>(
+    py: Python<'py>,
+    data: PyBuffer<u8>,
+    release_gil: bool,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let decompressed = if release_gil {
+        let data = data.to_vec(py)?;
+        py.allow_threads(|| nlzss11::decompress(&data))
+    } else {
+        nlzss11::decompress(&read_buffer(py, &data)?)
+    }
+    .map_err(|e| DecompressError::new_err(e.to_string()))?;
+    Ok(PyBytes::new(py, &decompressed))
+}
+
+/// Decompresses `data` into the writable buffer `out` and returns the decompressed size.
+///
+/// Raises `ValueError` if `out` is too small or overlaps `data` and `DecompressError` if `data`
+/// is invalid.
+#[pyfunction]
+fn decompress_into(py: Python<'_>, data: PyBuffer<u8>, mut out: PyBuffer<u8>) -> PyResult<usize> {
+    check_writable(&out)?;
+    check_no_overlap(&data, &out)?;
+    let data = read_buffer(py, &data)?;
+    let size =
+        nlzss11::decompressed_size(&data).map_err(|e| DecompressError::new_err(e.to_string()))?;
+    if size > out.len_bytes() as u64 {
+        return Err(PyValueError::new_err(format!(
+            "output buffer too small, {} bytes are needed",
+            size
+        )));
+    }
+    nlzss11::decompress_to_slice(&data, write_buffer(&mut out))
+        .map_err(|e| DecompressError::new_err(e.to_string()))
+}
+
+#[pymodule]
+#[pyo3(name = "nlzss11")]
+fn nlzss11_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(compress, m)?)?;
+    m.add_function(wrap_pyfunction!(compress_into, m)?)?;
+    m.add_function(wrap_pyfunction!(compress_bound, m)?)?;
+    m.add_function(wrap_pyfunction!(decompress, m)?)?;
+    m.add_function(wrap_pyfunction!(decompress_into, m)?)?;
+    m.add("DecompressError", m.py().get_type::<DecompressError>())?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+# run with `maturin develop && pytest` in the nlzss11-py directory
+import pytest
+
+import nlzss11
+
+
+def test_roundtrip():
+    data = b"abcabcabcabcabcabc hello hello hello" * 100
+    compressed = nlzss11.compress(data)
+    assert compressed[0] == 0x11
+    assert len(compressed) < len(data)
+    assert nlzss11.decompress(compressed) == data
+
+
+def test_level():
+    data = bytes(range(256)) * 16
+    for level in range(10):
+        assert nlzss11.decompress(nlzss11.compress(data, level=level)) == data
+    # level 0 only stores literals
+    assert len(nlzss11.compress(data, level=0)) > len(nlzss11.compress(data, level=9))
+    with pytest.raises(ValueError):
+        nlzss11.compress(data, level=10)
+    with pytest.raises(ValueError):
+        nlzss11.compress(data, level=-1)
+
+
+def test_invalid_data():
+    with pytest.raises(nlzss11.DecompressError):
+        nlzss11.decompress(b"\x10\x00\x00\x10")
+    with pytest.raises(ValueError):
+        nlzss11.decompress(b"\x11")
+
+
+def test_buffer_protocol():
+    data = b"buffer protocol " * 100
+    compressed = nlzss11.compress(bytearray(data))
+    assert nlzss11.decompress(memoryview(compressed)) == data
+    assert nlzss11.decompress(bytearray(compressed)) == data
+
+
+def test_release_gil():
+    data = b"release the gil " * 100
+    compressed = nlzss11.compress(data, release_gil=True)
+    assert compressed == nlzss11.compress(data)
+    assert nlzss11.decompress(compressed, release_gil=True) == data
+
+
+def test_into():
+    data = b"preallocated output " * 100
+    out = bytearray(len(data) + 10)
+    compressed_len = nlzss11.compress_into(data, out)
+    compressed = bytes(out[:compressed_len])
+    assert nlzss11.decompress(compressed) == data
+    with pytest.raises(ValueError):
+        nlzss11.compress_into(data, bytearray(10))
+    random = bytes((i * 167 + i // 7) % 256 for i in range(1000))
+    out = bytearray(nlzss11.compress_bound(len(random)))
+    compressed_len = nlzss11.compress_into(random, out)
+    assert nlzss11.decompress(out[:compressed_len]) == random
+
+    out = bytearray(len(data))
+    assert nlzss11.decompress_into(compressed, memoryview(out)) == len(data)
+    assert out == data
+    with pytest.raises(ValueError):
+        nlzss11.decompress_into(compressed, bytearray(10))
+    with pytest.raises(ValueError):
+        nlzss11.decompress_into(compressed, bytes(len(data)))
+
+
+def test_overlap():
+    data = b"overlapping buffers " * 10
+    buf = bytearray(nlzss11.compress(data)) + bytearray(len(data))
+    view = memoryview(buf)
+    with pytest.raises(ValueError):
+        nlzss11.decompress_into(buf, buf)
+    with pytest.raises(ValueError):
+        nlzss11.decompress_into(view[:40], view[20:])
+    with pytest.raises(ValueError):
+        nlzss11.compress_into(view[20:], view[:40])
+
+
+def test_numpy():
+    np = pytest.importorskip("numpy")
+    data = np.arange(1000, dtype=np.uint8)
+    compressed = nlzss11.compress(data)
+    out = np.zeros(1000, dtype=np.uint8)
+    nlzss11.decompress_into(compressed, out)
+    assert (out == data).all()
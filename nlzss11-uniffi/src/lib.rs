@@ -0,0 +1,36 @@
+//! UniFFI bindings for the nlzss11 crate.
+//!
+//! Generate the Swift/Kotlin sources from the built library, for example:
+//!
+//! ```sh
+//! cargo run -p nlzss11-uniffi --bin uniffi-bindgen -- generate \
+//!     --library target/release/libnlzss11_uniffi.so --language kotlin --out-dir out
+//! ```
+
+uniffi::setup_scaffolding!();
+
+#[derive(uniffi::Error, thiserror::Error, Debug)]
+pub enum Nlzss11Error {
+    #[error("invalid compressed data: {message}")]
+    InvalidData { message: String },
+}
+
+impl From<nlzss11::DecompressError> for Nlzss11Error {
+    fn from(e: nlzss11::DecompressError) -> Self {
+        Nlzss11Error::InvalidData {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Compresses `data`.
+#[uniffi::export]
+pub fn compress(data: Vec<u8>) -> Vec<u8> {
+    nlzss11::compress(&data)
+}
+
+/// Decompresses `data`, fails if it isn't valid compressed data.
+#[uniffi::export]
+pub fn decompress(data: Vec<u8>) -> Result<Vec<u8>, Nlzss11Error> {
+    Ok(nlzss11::decompress(&data)?)
+}
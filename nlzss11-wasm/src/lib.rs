@@ -0,0 +1,99 @@
+//! WebAssembly bindings for the nlzss11 crate.
+//!
+//! Build the npm package with `wasm-pack build --target web` (or `--target bundler`),
+//! both functions take and return `Uint8Array`s.
+//!
+//! Large files can be decompressed progressively with [`DecompressionTransformer`], which
+//! implements the transformer interface of Web Streams:
+//!
+//! ```js
+//! const response = await fetch("file.LZ");
+//! const decompressed = response.body.pipeThrough(
+//!     new TransformStream(new DecompressionTransformer()),
+//! );
+//! ```
+
+use nlzss11::Decompressor;
+use wasm_bindgen::prelude::*;
+
+/// Decompressed data is passed on in chunks of at most this size.
+const OUTPUT_CHUNK_SIZE: usize = 0x10000;
+
+#[wasm_bindgen]
+extern "C" {
+    pub type TransformStreamDefaultController;
+
+    #[wasm_bindgen(method)]
+    fn enqueue(this: &TransformStreamDefaultController, chunk: Vec<u8>);
+}
+
+/// Compresses `data`.
+#[wasm_bindgen]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    nlzss11::compress(data)
+}
+
+/// Decompresses `data`, throws an `Error` if it isn't valid compressed data.
+#[wasm_bindgen]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, JsError> {
+    nlzss11::decompress(data).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Transformer for a `TransformStream` that decompresses the `Uint8Array` chunks written to it.
+///
+/// Only the last 4 KiB of decompressed data are kept, the stream errors if the data is invalid
+/// or ends early.
+#[wasm_bindgen]
+pub struct DecompressionTransformer {
+    decompressor: Decompressor,
+    buf: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecompressionTransformer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        DecompressionTransformer {
+            decompressor: Decompressor::new(),
+            buf: vec![0; OUTPUT_CHUNK_SIZE],
+        }
+    }
+
+    /// Size of the decompressed data, once the header has been read.
+    #[wasm_bindgen(getter, js_name = decompressedSize)]
+    pub fn decompressed_size(&self) -> Option<usize> {
+        self.decompressor.decompressed_size()
+    }
+
+    pub fn transform(
+        &mut self,
+        mut chunk: &[u8],
+        controller: &TransformStreamDefaultController,
+    ) -> Result<(), JsError> {
+        loop {
+            let (consumed, written) = self
+                .decompressor
+                .decompress(chunk, &mut self.buf)
+                .map_err(|e| JsError::new(&e.to_string()))?;
+            if written > 0 {
+                controller.enqueue(self.buf[..written].to_vec());
+            }
+            chunk = &chunk[consumed..];
+            if consumed == 0 && written == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn flush(&mut self, _controller: &TransformStreamDefaultController) -> Result<(), JsError> {
+        self.decompressor
+            .finish()
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+}
+
+impl Default for DecompressionTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
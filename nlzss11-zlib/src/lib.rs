@@ -22,7 +22,7 @@ impl Lzss11Writer {
         buffer.push(0x11);
         // handle very big archives
         if uncompressed_size < 0xFF_FF_FF {
-            buffer.extend_from_slice(&uncompressed_size.to_be_bytes()[..3]);
+            buffer.extend_from_slice(&uncompressed_size.to_le_bytes()[..3]);
         } else {
             buffer.extend_from_slice(&[0,0,0]);
             buffer.extend_from_slice(&uncompressed_size.to_le_bytes());
@@ -83,6 +83,7 @@ extern "C" fn handle_match(user_data: *mut c_void, distance: u32, length: u32) {
     writer.handle_match(distance, length);
 }
 
+/// Compresses with the match finder of zlib-ng at the zlib compression `level`.
 pub fn compress_with_zlib_into(data: &[u8], out_buf: &mut Vec<u8>, level: i32) {
     let mut writer = Lzss11Writer::new(out_buf, data.len() as u32);
     let mut dummy = [0u8; 8];
@@ -97,4 +98,16 @@ pub fn compress_with_zlib_into(data: &[u8], out_buf: &mut Vec<u8>, level: i32) {
 
 #[cfg(test)]
 mod tests {
+    use super::Lzss11Writer;
+
+    #[test]
+    pub fn test_header() {
+        let mut out = Vec::new();
+        let mut writer = Lzss11Writer::new(&mut out, 0x012345);
+        writer.finish();
+        assert_eq!(writer.result, [0x11, 0x45, 0x23, 0x01, 0]);
+        let mut writer = Lzss11Writer::new(&mut out, 0x01234567);
+        writer.finish();
+        assert_eq!(writer.result, [0x11, 0, 0, 0, 0x67, 0x45, 0x23, 0x01, 0]);
+    }
 }
@@ -0,0 +1,211 @@
+//! Compression and decompression speed on a few kinds of input, run with `cargo bench`.
+//!
+//! The inputs are generated, so the numbers can be compared between machines and commits
+//! without any files.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nlzss11::{compress, compress_with_level, decompress, decompress_to_slice, Level};
+
+const INPUT_LEN: usize = 256 * 1024;
+
+struct XorShift(u32);
+
+impl XorShift {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
+
+/// Sentences of random words, some much more common than others like in real text.
+fn text() -> Vec<u8> {
+    const WORDS: &[&str] = &[
+        "the",
+        "of",
+        "and",
+        "a",
+        "to",
+        "in",
+        "is",
+        "you",
+        "that",
+        "it",
+        "he",
+        "was",
+        "for",
+        "on",
+        "are",
+        "as",
+        "with",
+        "his",
+        "they",
+        "at",
+        "be",
+        "this",
+        "have",
+        "from",
+        "sword",
+        "sky",
+        "link",
+        "zelda",
+        "loftwing",
+        "skyloft",
+        "temple",
+        "goddess",
+        "harp",
+        "island",
+        "statue",
+        "knight",
+        "academy",
+        "dungeon",
+        "compressed",
+        "archive",
+        "message",
+        "stage",
+        "room",
+    ];
+    let mut random = XorShift(0x9E3779B9);
+    let mut out = Vec::with_capacity(INPUT_LEN + 16);
+    while out.len() < INPUT_LEN {
+        let sentence_len = 4 + random.next() % 12;
+        for i in 0..sentence_len {
+            // squaring skews the choice towards the front of the list
+            let index = (random.next() % WORDS.len() as u32).pow(2) / WORDS.len() as u32;
+            let word = WORDS[index as usize].as_bytes();
+            if i == 0 {
+                out.push(word[0].to_ascii_uppercase());
+                out.extend_from_slice(&word[1..]);
+            } else {
+                out.push(b' ');
+                out.extend_from_slice(word);
+            }
+        }
+        out.extend_from_slice(if random.next().is_multiple_of(4) {
+            b".\n"
+        } else {
+            b". "
+        });
+    }
+    out.truncate(INPUT_LEN);
+    out
+}
+
+/// Records of ids, floats, flags and names like in game data files.
+fn binary() -> Vec<u8> {
+    let mut random = XorShift(0x2545F491);
+    let mut out = Vec::with_capacity(INPUT_LEN + 48);
+    let mut id = 0u32;
+    while out.len() < INPUT_LEN {
+        id += 1 + random.next() % 3;
+        out.extend_from_slice(&id.to_be_bytes());
+        for axis in 0..3 {
+            let position = (random.next() % 20000) as f32 / 10.0 - 1000.0;
+            let position = if axis == 1 {
+                position.round()
+            } else {
+                position
+            };
+            out.extend_from_slice(&position.to_be_bytes());
+        }
+        out.extend_from_slice(&((random.next() % 8) as u16).to_be_bytes());
+        out.extend_from_slice(&[0xFF, 0xFF]);
+        let mut name = [0; 16];
+        let actor = format!("Actor{:02}", random.next() % 40);
+        name[..actor.len()].copy_from_slice(actor.as_bytes());
+        out.extend_from_slice(&name);
+        out.extend_from_slice(&[0; 8]);
+    }
+    out.truncate(INPUT_LEN);
+    out
+}
+
+/// Compressed text, which has hardly any matches left.
+fn already_compressed() -> Vec<u8> {
+    let mut out = Vec::with_capacity(INPUT_LEN);
+    let mut seed = 1;
+    while out.len() < INPUT_LEN {
+        let mut text = text();
+        // different text every time, so the pieces don't repeat each other
+        text.rotate_left(seed * 4099);
+        seed += 1;
+        out.extend_from_slice(&compress(&text));
+    }
+    out.truncate(INPUT_LEN);
+    out
+}
+
+/// Long runs of the same byte and short patterns repeated many times.
+fn repetitive() -> Vec<u8> {
+    let mut random = XorShift(0x1B873593);
+    let mut out = Vec::with_capacity(INPUT_LEN + 0x2000);
+    while out.len() < INPUT_LEN {
+        let len = (random.next() % 0x2000) as usize;
+        if random.next().is_multiple_of(2) {
+            out.resize(out.len() + len, random.next() as u8);
+        } else {
+            let pattern: Vec<u8> = (0..1 + random.next() % 16)
+                .map(|_| random.next() as u8)
+                .collect();
+            out.extend(pattern.iter().cycle().take(len));
+        }
+    }
+    out.truncate(INPUT_LEN);
+    out
+}
+
+fn inputs() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("text", text()),
+        ("binary", binary()),
+        ("already compressed", already_compressed()),
+        ("repetitive", repetitive()),
+    ]
+}
+
+fn bench_compress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compress");
+    group.sample_size(10);
+    for (name, data) in inputs() {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        for level in [
+            Level::FAST,
+            Level::new(3),
+            Level::DEFAULT,
+            Level::new(8),
+            Level::BEST,
+        ] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("level {}", level.get()), name),
+                &data,
+                |b, data| b.iter(|| compress_with_level(data, level)),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_decompress(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decompress");
+    for (name, data) in inputs() {
+        let compressed = compress(&data);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("vec", name),
+            &compressed,
+            |b, compressed| b.iter(|| decompress(compressed).unwrap()),
+        );
+        // only the decode loop, without allocating the output
+        let mut out = vec![0; data.len()];
+        group.bench_with_input(
+            BenchmarkId::new("slice", name),
+            &compressed,
+            |b, compressed| b.iter(|| decompress_to_slice(compressed, &mut out).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_compress, bench_decompress);
+criterion_main!(benches);
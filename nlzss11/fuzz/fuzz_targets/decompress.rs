@@ -0,0 +1,14 @@
+//! Decompressing arbitrary data must return an error instead of panicking.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = nlzss11::decompress_with_limit(data, 1 << 24);
+    let _ = nlzss11::decompress_with_mode(data, nlzss11::DecompressMode::Strict);
+    let mut out = [0; 0x1000];
+    let _ = nlzss11::decompress_to_slice(data, &mut out);
+    let _ = nlzss11::tokens::parse_tokens(data);
+    let mut decompressor = nlzss11::Decompressor::new();
+    let _ = decompressor.push(data, &mut Vec::new());
+});
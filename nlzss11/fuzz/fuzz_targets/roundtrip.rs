@@ -0,0 +1,47 @@
+//! Compressing arbitrary data with any settings must give a stream that decompresses to the same
+//! data, with our decoder in strict mode and with the reference decoder.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nlzss11::{CompressionOptions, Compressor, DecompressMode, Level};
+
+fuzz_target!(|input: &[u8]| {
+    // the first byte picks the settings, the rest is the data
+    let Some((&settings, data)) = input.split_first() else {
+        return;
+    };
+    // the empty stream has a size of 0 in the short header, which decoders take as the start
+    // of an extended header
+    if data.is_empty() {
+        return;
+    }
+    let mut options = CompressionOptions::new()
+        .level(Level::new(settings % 10))
+        .vram_safe(settings & 0x10 != 0);
+    if settings & 0x20 != 0 {
+        options = options.window_size(0x100);
+    }
+    if settings & 0x40 != 0 {
+        options = options.max_match(0x110);
+    }
+    if settings & 0x80 != 0 {
+        options = options.min_match(3);
+    }
+    let compressed = nlzss11::compress_with_options(data, &options);
+    assert_eq!(
+        nlzss11::decompress_with_mode(&compressed, DecompressMode::Strict).unwrap(),
+        data
+    );
+    assert_eq!(
+        nlzss11::reference::decompress_strict(&compressed).unwrap(),
+        data
+    );
+
+    let canonical = nlzss11::compress_canonical(data);
+    assert_eq!(nlzss11::decompress(&canonical).unwrap(), data);
+    let mut compressor = Compressor::new();
+    for chunk in data.chunks(usize::from(settings) + 1) {
+        compressor.feed(chunk);
+    }
+    assert_eq!(compressor.finish(), canonical);
+});
@@ -0,0 +1,343 @@
+//! Readers for the archive formats that are usually stored nlzss11 compressed, enabled with the
+//! `archive` feature.
+//!
+//! Only reading is supported, the archives are parsed into a list of paths pointing into the
+//! decompressed data.
+
+use std::ops::Range;
+use std::path::Path;
+
+use byteorder::{ByteOrder, BE, LE};
+
+use crate::{decompress, DecompressError};
+
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum ArchiveError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("decompression failed: {0}")]
+    Decompress(#[from] DecompressError),
+    #[error("invalid archive: {0}")]
+    InvalidArchive(&'static str),
+}
+
+fn read_compressed(path: &Path) -> Result<Vec<u8>, ArchiveError> {
+    let compressed = std::fs::read(path)?;
+    Ok(decompress(&compressed)?)
+}
+
+fn get_range(data: &[u8], range: Range<usize>) -> Result<&[u8], ArchiveError> {
+    data.get(range)
+        .ok_or(ArchiveError::InvalidArchive("out of bounds"))
+}
+
+/// `a + b`, an error instead of overflowing with offsets from a broken archive on 32 bit
+/// targets.
+fn add(a: usize, b: usize) -> Result<usize, ArchiveError> {
+    a.checked_add(b)
+        .ok_or(ArchiveError::InvalidArchive("offset out of range"))
+}
+
+/// The length of `count` entries of `len` bytes.
+fn table_len(count: usize, len: usize) -> Result<usize, ArchiveError> {
+    count
+        .checked_mul(len)
+        .ok_or(ArchiveError::InvalidArchive("table out of range"))
+}
+
+fn read_name(data: &[u8], offset: usize) -> Result<String, ArchiveError> {
+    let rest = data
+        .get(offset..)
+        .ok_or(ArchiveError::InvalidArchive("name out of bounds"))?;
+    let len = rest
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or(ArchiveError::InvalidArchive("unterminated name"))?;
+    Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+}
+
+/// Decompressed archive with the location of every file.
+#[derive(Debug, Clone)]
+struct Files {
+    data: Vec<u8>,
+    files: Vec<(String, Range<usize>)>,
+}
+
+impl Files {
+    fn files(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.files
+            .iter()
+            .map(|(path, range)| (path.as_str(), &self.data[range.clone()]))
+    }
+
+    fn get(&self, path: &str) -> Option<&[u8]> {
+        let path = path.trim_start_matches('/');
+        self.files
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, range)| &self.data[range.clone()])
+    }
+}
+
+const U8_MAGIC: u32 = 0x55AA382D;
+
+/// U8 archive (`.arc`), used by most Wii games.
+#[derive(Debug, Clone)]
+pub struct U8Archive {
+    inner: Files,
+}
+
+impl U8Archive {
+    pub fn parse(data: Vec<u8>) -> Result<Self, ArchiveError> {
+        let header = get_range(&data, 0..0x10)?;
+        if BE::read_u32(header) != U8_MAGIC {
+            return Err(ArchiveError::InvalidArchive("invalid U8 magic"));
+        }
+        let nodes_offset = BE::read_u32(&header[4..]) as usize;
+        let root = get_range(&data, nodes_offset..add(nodes_offset, 12)?)?;
+        let node_count = BE::read_u32(&root[8..]) as usize;
+        let strings_offset = add(nodes_offset, table_len(node_count, 12)?)?;
+        let nodes = get_range(&data, nodes_offset..strings_offset)?;
+
+        let mut files = Vec::new();
+        // directories we are currently in, with the index of their first node after them
+        let mut dirs: Vec<(String, usize)> = Vec::new();
+        for index in 1..node_count {
+            while dirs.last().is_some_and(|&(_, end)| end <= index) {
+                dirs.pop();
+            }
+            let node = &nodes[index * 12..][..12];
+            let name_offset = BE::read_u24(&node[1..]) as usize;
+            let name = read_name(&data, add(strings_offset, name_offset)?)?;
+            let mut path = dirs
+                .iter()
+                .map(|(dir, _)| format!("{}/", dir))
+                .collect::<String>();
+            path.push_str(&name);
+            match node[0] {
+                0 => {
+                    let offset = BE::read_u32(&node[4..]) as usize;
+                    let size = BE::read_u32(&node[8..]) as usize;
+                    let end = add(offset, size)?;
+                    get_range(&data, offset..end)?;
+                    files.push((path, offset..end));
+                }
+                1 => {
+                    let end = BE::read_u32(&node[8..]) as usize;
+                    dirs.push((name, end));
+                }
+                _ => return Err(ArchiveError::InvalidArchive("invalid U8 node type")),
+            }
+        }
+        Ok(U8Archive {
+            inner: Files { data, files },
+        })
+    }
+
+    /// All files with their full path, separated by `/`.
+    pub fn files(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.inner.files()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.inner.get(path)
+    }
+
+    /// The decompressed archive.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.inner.data
+    }
+}
+
+/// Reads and decompresses the file at `path` and parses it as U8 archive.
+pub fn open_compressed_u8(path: impl AsRef<Path>) -> Result<U8Archive, ArchiveError> {
+    U8Archive::parse(read_compressed(path.as_ref())?)
+}
+
+/// SARC archive, used by most Wii U and Switch games.
+#[derive(Debug, Clone)]
+pub struct Sarc {
+    inner: Files,
+}
+
+impl Sarc {
+    pub fn parse(data: Vec<u8>) -> Result<Self, ArchiveError> {
+        let header = get_range(&data, 0..0x14)?;
+        if &header[..4] != b"SARC" {
+            return Err(ArchiveError::InvalidArchive("invalid SARC magic"));
+        }
+        let big_endian = match &header[6..8] {
+            [0xFE, 0xFF] => true,
+            [0xFF, 0xFE] => false,
+            _ => return Err(ArchiveError::InvalidArchive("invalid SARC byte order mark")),
+        };
+        let read_u16 = |buf: &[u8]| {
+            if big_endian {
+                BE::read_u16(buf)
+            } else {
+                LE::read_u16(buf)
+            }
+        };
+        let read_u32 = |buf: &[u8]| {
+            if big_endian {
+                BE::read_u32(buf)
+            } else {
+                LE::read_u32(buf)
+            }
+        };
+        let header_len = read_u16(&header[4..]) as usize;
+        let data_offset = read_u32(&header[0xC..]) as usize;
+
+        let sfat = get_range(&data, header_len..add(header_len, 0xC)?)?;
+        if &sfat[..4] != b"SFAT" {
+            return Err(ArchiveError::InvalidArchive("invalid SFAT magic"));
+        }
+        let sfat_len = read_u16(&sfat[4..]) as usize;
+        let node_count = read_u16(&sfat[6..]) as usize;
+        let nodes_offset = add(header_len, sfat_len)?;
+        let sfnt_offset = add(nodes_offset, table_len(node_count, 0x10)?)?;
+        let nodes = get_range(&data, nodes_offset..sfnt_offset)?;
+
+        let sfnt = get_range(&data, sfnt_offset..add(sfnt_offset, 8)?)?;
+        if &sfnt[..4] != b"SFNT" {
+            return Err(ArchiveError::InvalidArchive("invalid SFNT magic"));
+        }
+        let names_offset = add(sfnt_offset, read_u16(&sfnt[4..]) as usize)?;
+
+        let mut files = Vec::with_capacity(node_count);
+        for node in nodes.chunks_exact(0x10) {
+            let attributes = read_u32(&node[4..]);
+            let name = if attributes >> 24 == 1 {
+                read_name(
+                    &data,
+                    add(names_offset, (attributes & 0xFFFF) as usize * 4)?,
+                )?
+            } else {
+                // unnamed files are only identified by their hash
+                format!("{:08x}", read_u32(node))
+            };
+            let start = add(data_offset, read_u32(&node[8..]) as usize)?;
+            let end = add(data_offset, read_u32(&node[0xC..]) as usize)?;
+            get_range(&data, start..end)?;
+            files.push((name, start..end));
+        }
+        Ok(Sarc {
+            inner: Files { data, files },
+        })
+    }
+
+    /// All files with their full path, separated by `/`.
+    pub fn files(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.inner.files()
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        self.inner.get(path)
+    }
+
+    /// The decompressed archive.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.inner.data
+    }
+}
+
+/// Reads and decompresses the file at `path` and parses it as SARC archive.
+pub fn open_compressed_sarc(path: impl AsRef<Path>) -> Result<Sarc, ArchiveError> {
+    Sarc::parse(read_compressed(path.as_ref())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArchiveError, Sarc, U8Archive};
+
+    #[test]
+    pub fn test_u8() {
+        let mut arc = Vec::new();
+        arc.extend_from_slice(&0x55AA382Du32.to_be_bytes());
+        arc.extend_from_slice(&0x20u32.to_be_bytes());
+        arc.extend_from_slice(&(4 * 12 + 0x10u32).to_be_bytes());
+        arc.extend_from_slice(&0x80u32.to_be_bytes());
+        arc.resize(0x20, 0);
+        // root, dir "dir" with one file and a file after it
+        for node in [
+            [0x0100_0000u32, 0, 4],
+            [0x0100_0001, 0, 3],
+            [0x0000_0005, 0x80, 3],
+            [0x0000_000B, 0x84, 2],
+        ] {
+            for v in node {
+                arc.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        arc.extend_from_slice(b"\0dir\0a.bin\0b.bin\0");
+        arc.resize(0x80, 0);
+        arc.extend_from_slice(b"abc\0de");
+
+        let arc = U8Archive::parse(arc).unwrap();
+        let files: Vec<_> = arc.files().collect();
+        assert_eq!(
+            files,
+            [
+                ("dir/a.bin", b"abc".as_slice()),
+                ("b.bin", b"de".as_slice())
+            ]
+        );
+        assert_eq!(arc.get("/dir/a.bin"), Some(b"abc".as_slice()));
+    }
+
+    #[test]
+    pub fn test_u8_out_of_range() {
+        let mut arc = Vec::new();
+        arc.extend_from_slice(&0x55AA382Du32.to_be_bytes());
+        arc.extend_from_slice(&0x10u32.to_be_bytes());
+        arc.resize(0x10, 0);
+        // a root claiming 2 nodes and a file ending past 4 GiB
+        for v in [0x0100_0000u32, 0, 2, 0x0000_0000, u32::MAX, u32::MAX] {
+            arc.extend_from_slice(&v.to_be_bytes());
+        }
+        arc.push(0);
+        assert!(matches!(
+            U8Archive::parse(arc.clone()),
+            Err(ArchiveError::InvalidArchive(_))
+        ));
+        // nodes at the very end of the address space
+        arc[4..8].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            U8Archive::parse(arc),
+            Err(ArchiveError::InvalidArchive(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_sarc() {
+        let mut sarc = Vec::new();
+        sarc.extend_from_slice(b"SARC");
+        sarc.extend_from_slice(&[0x14, 0x00, 0xFF, 0xFE]);
+        sarc.extend_from_slice(&0u32.to_le_bytes());
+        sarc.extend_from_slice(&0x40u32.to_le_bytes());
+        sarc.extend_from_slice(&[0x00, 0x01, 0x00, 0x00]);
+        sarc.extend_from_slice(b"SFAT");
+        sarc.extend_from_slice(&[0x0C, 0x00, 0x01, 0x00]);
+        sarc.extend_from_slice(&0x65u32.to_le_bytes());
+        for v in [0x1234u32, 0x0100_0000, 0, 3] {
+            sarc.extend_from_slice(&v.to_le_bytes());
+        }
+        sarc.extend_from_slice(b"SFNT");
+        sarc.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]);
+        sarc.extend_from_slice(b"x.txt\0");
+        sarc.resize(0x40, 0);
+        sarc.extend_from_slice(b"xyz");
+
+        let parsed = Sarc::parse(sarc.clone()).unwrap();
+        assert_eq!(parsed.get("x.txt"), Some(b"xyz".as_slice()));
+
+        // data offset and file end at the end of the address space
+        sarc[0xC..0x10].copy_from_slice(&u32::MAX.to_le_bytes());
+        sarc[0x2C..0x30].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Sarc::parse(sarc),
+            Err(ArchiveError::InvalidArchive(_))
+        ));
+    }
+}
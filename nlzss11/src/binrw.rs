@@ -0,0 +1,131 @@
+//! Integration with [`binrw`](::binrw), enabled with the `binrw` feature.
+
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+
+use ::binrw::{BinRead, BinResult, BinWrite, Endian};
+
+use crate::{compress, decompress_with_consumed};
+
+/// Wraps a value that is stored nlzss11 compressed.
+///
+/// Reading decompresses the stream at the current position and parses `T` from the result, the
+/// reader is left right after the compressed stream. Writing serializes `T` and writes it
+/// compressed.
+///
+/// ```ignore
+/// #[binrw]
+/// struct Archive {
+///     header: Header,
+///     contents: NlzssWrapped<Contents>,
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NlzssWrapped<T>(pub T);
+
+impl<T> NlzssWrapped<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for NlzssWrapped<T> {
+    fn from(value: T) -> Self {
+        NlzssWrapped(value)
+    }
+}
+
+impl<T> Deref for NlzssWrapped<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for NlzssWrapped<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: BinRead> BinRead for NlzssWrapped<T> {
+    type Args<'a> = T::Args<'a>;
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let start = reader.stream_position()?;
+        // the length of the compressed stream is only known after decompressing it
+        let mut compressed = Vec::new();
+        reader.read_to_end(&mut compressed)?;
+        let (decompressed, consumed) =
+            decompress_with_consumed(&compressed).map_err(|e| ::binrw::Error::Custom {
+                pos: start,
+                err: Box::new(e),
+            })?;
+        reader.seek(SeekFrom::Start(start + consumed as u64))?;
+        T::read_options(&mut Cursor::new(decompressed), endian, args).map(NlzssWrapped)
+    }
+}
+
+impl<T: BinWrite> BinWrite for NlzssWrapped<T> {
+    type Args<'a> = T::Args<'a>;
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        let mut uncompressed = Cursor::new(Vec::new());
+        self.0.write_options(&mut uncompressed, endian, args)?;
+        writer.write_all(&compress(uncompressed.get_ref()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use ::binrw::{binrw, BinRead, BinWrite};
+
+    use super::NlzssWrapped;
+
+    #[binrw]
+    #[brw(big)]
+    #[derive(Debug, PartialEq)]
+    struct Inner {
+        count: u32,
+        #[br(count = count)]
+        values: Vec<u16>,
+    }
+
+    #[binrw]
+    #[brw(big)]
+    #[derive(Debug, PartialEq)]
+    struct Outer {
+        magic: u32,
+        inner: NlzssWrapped<Inner>,
+        trailer: u32,
+    }
+
+    #[test]
+    pub fn test_roundtrip() {
+        let outer = Outer {
+            magic: 0x1234,
+            inner: NlzssWrapped(Inner {
+                count: 100,
+                values: (0..100).map(|i| i % 7).collect(),
+            }),
+            trailer: 0xABCD,
+        };
+        let mut written = Cursor::new(Vec::new());
+        outer.write(&mut written).unwrap();
+        written.set_position(0);
+        assert_eq!(Outer::read(&mut written).unwrap(), outer);
+    }
+}
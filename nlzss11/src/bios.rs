@@ -0,0 +1,126 @@
+//! Checks that compressed data can be decompressed by the BIOS decompression functions of the
+//! GBA and DS, which only support LZ10, Huffman and RLE and are stricter than this crate.
+//!
+//! The data also has to be at a 4 byte aligned address, which is up to the caller.
+
+use crate::lz10::Lz10;
+use crate::lzss::{read_header, Counter, LzssVariant};
+use crate::{huffman, rle, DecompressError, LzssCode};
+
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum BiosError {
+    #[error("format {0:#04x} isn't supported by the BIOS")]
+    UnsupportedFormat(u8),
+    #[error("the BIOS can't read the extended size header")]
+    ExtendedHeader,
+    #[error("invalid data: {0}")]
+    Invalid(#[from] DecompressError),
+    #[error("backreference at {offset:#x} reaches before the start of the output")]
+    BackrefBeforeStart { offset: usize },
+    #[error("backreference at {offset:#x} copies the previous byte, which doesn't work in VRAM")]
+    VramUnsafeDistance { offset: usize },
+    #[error("decompressed size {size:#x} isn't a multiple of {multiple}")]
+    UnalignedSize { size: usize, multiple: usize },
+    #[error("compressed length {0:#x} isn't a multiple of 4")]
+    UnalignedLength(usize),
+}
+
+/// Checks that `data` decompresses the same with the BIOS as with this crate.
+///
+/// With `vram` the data has to work with the VRAM functions as well, which write 16 bits at a
+/// time and so can't copy from the byte right before.
+pub fn check(data: &[u8], vram: bool) -> Result<(), BiosError> {
+    let magic = *data
+        .first()
+        .ok_or(DecompressError::LibraryError("Too short"))?;
+    let (size, header_len) = read_header(data, magic)?;
+    if header_len != 4 {
+        return Err(BiosError::ExtendedHeader);
+    }
+    match magic {
+        0x10 => check_lz10(data, size, vram)?,
+        0x30 => {
+            rle::decode(data, &mut Counter(0))?;
+        }
+        0x24 | 0x28 => {
+            // the bitstream is read in 32 bit words, which are also written at once
+            let len = huffman::decode(data, &mut Counter(0))?;
+            if len % 4 != 0 {
+                return Err(BiosError::UnalignedLength(len));
+            }
+            if size % 4 != 0 {
+                return Err(BiosError::UnalignedSize { size, multiple: 4 });
+            }
+        }
+        _ => return Err(BiosError::UnsupportedFormat(magic)),
+    }
+    if vram && size % 2 != 0 {
+        return Err(BiosError::UnalignedSize { size, multiple: 2 });
+    }
+    Ok(())
+}
+
+fn check_lz10(data: &[u8], size: usize, vram: bool) -> Result<(), BiosError> {
+    let mut pos = 4;
+    let mut out_pos = 0;
+    let mut group_header = 0;
+    let mut remaining_chunks = 0;
+    while out_pos < size {
+        if remaining_chunks == 0 {
+            group_header = *data.get(pos).ok_or(DecompressError::InvalidIndex(pos))?;
+            pos += 1;
+            remaining_chunks = 8;
+        }
+        if !Lz10::is_backref(group_header, 8 - remaining_chunks) {
+            if pos >= data.len() {
+                return Err(DecompressError::InvalidIndex(pos).into());
+            }
+            pos += 1;
+            out_pos += 1;
+        } else {
+            let (LzssCode { distance, length }, advance) =
+                Lz10::read_code(data.get(pos..).unwrap_or_default())
+                    .ok_or(DecompressError::InvalidIndex(pos))?;
+            if distance as usize > out_pos {
+                return Err(BiosError::BackrefBeforeStart { offset: pos });
+            }
+            if vram && distance == 1 {
+                return Err(BiosError::VramUnsafeDistance { offset: pos });
+            }
+            pos += advance;
+            out_pos += length as usize;
+        }
+        remaining_chunks -= 1;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::{check, BiosError};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_check() {
+        let data = sample(4000, 0);
+        check(&crate::lz10::compress(&data), true).unwrap();
+        check(&crate::rle::compress(&data), true).unwrap();
+        check(&crate::huffman::compress_8bit(&data), true).unwrap();
+        assert!(matches!(
+            check(&crate::compress(&data), false),
+            Err(BiosError::UnsupportedFormat(0x11))
+        ));
+        // "aaaa" as a literal and a backreference with distance 1
+        let lz10 = [0x10, 4, 0, 0, 0x40, b'a', 0x00, 0x00];
+        check(&lz10, false).unwrap();
+        assert!(matches!(
+            check(&lz10, true),
+            Err(BiosError::VramUnsafeDistance { offset: 6 })
+        ));
+        assert!(matches!(
+            check(&[0x10, 3, 0, 0, 0x80, 0x00, 0x00], false),
+            Err(BiosError::BackrefBeforeStart { offset: 5 })
+        ));
+    }
+}
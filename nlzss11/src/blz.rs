@@ -0,0 +1,278 @@
+//! The backwards LZSS (BLZ) used for DS ARM9 binaries and overlays.
+//!
+//! The data is decompressed from the end towards the start, in place: a footer at the end of the
+//! file tells how much of the file is compressed and how much bigger it gets, the decompressed
+//! data then grows over the compressed data without overwriting parts that are still needed.
+//! Everything in front of the compressed part is stored uncompressed.
+
+use alloc::vec::Vec;
+
+use byteorder::{ByteOrder, LE};
+
+#[cfg(feature = "compress")]
+use crate::lzss::{match_length, MatchSearcher};
+use crate::DecompressError;
+
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 0xF + MIN_LENGTH;
+const MIN_DISTANCE: usize = 3;
+const MAX_DISTANCE: usize = 0xFFF + MIN_DISTANCE;
+/// Bytes at the start of ARM9 binaries (the secure area) that stay uncompressed.
+const ARM9_RAW_LEN: usize = 0x4000;
+
+struct Footer {
+    /// Length of the compressed part including the footer.
+    enc_len: usize,
+    /// Length of the footer including padding.
+    hdr_len: usize,
+    /// How much longer the decompressed data is.
+    inc_len: usize,
+}
+
+/// `None` if the data isn't compressed.
+fn read_footer(data: &[u8]) -> Result<Option<Footer>, DecompressError> {
+    if data.len() < 4 {
+        return Err(DecompressError::LibraryError("Too short"));
+    }
+    let inc_len = LE::read_u32(&data[data.len() - 4..]) as usize;
+    if inc_len == 0 {
+        return Ok(None);
+    }
+    if data.len() < 8 {
+        return Err(DecompressError::LibraryError("Too short"));
+    }
+    let word = LE::read_u32(&data[data.len() - 8..]);
+    let enc_len = (word & 0xFFFFFF) as usize;
+    let hdr_len = (word >> 24) as usize;
+    if hdr_len < 8 || enc_len < hdr_len || enc_len > data.len() {
+        return Err(DecompressError::LibraryError("invalid footer"));
+    }
+    Ok(Some(Footer {
+        enc_len,
+        hdr_len,
+        inc_len,
+    }))
+}
+
+/// Decompresses `buf` in place, it grows to the decompressed size.
+///
+/// Errors if the output would overwrite compressed data that hasn't been read yet, which
+/// would corrupt the data when decompressing in place on the console.
+#[cfg(feature = "decompress")]
+pub fn decompress_in_place(buf: &mut Vec<u8>) -> Result<(), DecompressError> {
+    let Some(footer) = read_footer(buf)? else {
+        buf.truncate(buf.len() - 4);
+        return Ok(());
+    };
+    let raw_start = buf.len() - footer.enc_len;
+    let mut read_pos = buf.len() - footer.hdr_len;
+    let raw_len = buf.len() + footer.inc_len;
+    buf.resize(raw_len, 0);
+    let mut write_pos = raw_len;
+
+    let mut flags = 0;
+    let mut mask = 0;
+    while write_pos > raw_start {
+        if mask == 0 {
+            if read_pos == raw_start {
+                break;
+            }
+            read_pos -= 1;
+            flags = buf[read_pos];
+            mask = 0x80;
+        }
+        if flags & mask == 0 {
+            if read_pos == raw_start {
+                break;
+            }
+            read_pos -= 1;
+            write_pos -= 1;
+            if write_pos < read_pos {
+                return Err(DecompressError::InvalidIndex(read_pos));
+            }
+            buf[write_pos] = buf[read_pos];
+        } else {
+            if read_pos < raw_start + 2 {
+                break;
+            }
+            read_pos -= 2;
+            // LLLLDDDD DDDDDDDD, stored backwards
+            // L + 3, D + 3
+            let pair = u16::from_le_bytes([buf[read_pos], buf[read_pos + 1]]) as usize;
+            let length = ((pair >> 12) + MIN_LENGTH).min(write_pos - raw_start);
+            let distance = (pair & 0xFFF) + MIN_DISTANCE;
+            if write_pos - 1 + distance >= raw_len {
+                return Err(DecompressError::InvalidIndex(read_pos));
+            }
+            for _ in 0..length {
+                write_pos -= 1;
+                if write_pos < read_pos {
+                    return Err(DecompressError::InvalidIndex(read_pos));
+                }
+                buf[write_pos] = buf[write_pos + distance];
+            }
+        }
+        mask >>= 1;
+    }
+    if write_pos != raw_start {
+        return Err(DecompressError::InvalidIndex(read_pos));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let mut buf = data.to_vec();
+    decompress_in_place(&mut buf)?;
+    Ok(buf)
+}
+
+/// Compresses `data`, if that doesn't make it smaller it is stored uncompressed.
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    compress_with_raw_prefix(data, 0)
+}
+
+/// Compresses an ARM9 binary, leaving the secure area in the first 16 KiB uncompressed.
+#[cfg(feature = "compress")]
+pub fn compress_arm9(data: &[u8]) -> Vec<u8> {
+    compress_with_raw_prefix(data, ARM9_RAW_LEN)
+}
+
+#[cfg(feature = "compress")]
+fn compress_with_raw_prefix(data: &[u8], min_raw_len: usize) -> Vec<u8> {
+    // compress forwards in the reversed data, the start of the file ends up last
+    let reversed: Vec<u8> = data[min_raw_len.min(data.len())..]
+        .iter()
+        .rev()
+        .copied()
+        .collect();
+    let mut searcher = MatchSearcher::new();
+    let mut stream = Vec::with_capacity(reversed.len());
+    let mut flag_pos = 0;
+    let mut flag_count = 8;
+    // how far the output can get ahead of the input at most, decompressing in place needs
+    // the end of the output to stay ahead by more than that
+    let mut max_ahead = isize::MIN;
+    // (output length, compressed length) at the best place to stop compressing
+    let mut best = (0, 0);
+
+    let mut pos = 0;
+    while pos < reversed.len() {
+        if flag_count == 8 {
+            flag_pos = stream.len();
+            stream.push(0);
+            flag_count = 0;
+        }
+        let mut code = searcher
+            .get_lz_code(
+                &reversed,
+                pos as u32,
+                MAX_DISTANCE as u32,
+                MAX_LENGTH as u32,
+            )
+            .map(|(distance, length)| (distance as usize, length as usize));
+        if code.is_some_and(|(distance, _)| distance < MIN_DISTANCE) && pos >= MIN_DISTANCE {
+            // repetitions with a short period also repeat with a longer one
+            let rest = &reversed[pos..];
+            let length = match_length(
+                &rest[..rest.len().min(MAX_LENGTH)],
+                &reversed[pos - MIN_DISTANCE..],
+            );
+            code = Some((MIN_DISTANCE, length));
+        }
+        match code {
+            Some((distance, length)) if distance >= MIN_DISTANCE && length >= MIN_LENGTH => {
+                stream[flag_pos] |= 0x80 >> flag_count;
+                let pair = ((length - MIN_LENGTH) << 12 | (distance - MIN_DISTANCE)) as u16;
+                stream.extend_from_slice(&pair.to_be_bytes());
+                for p in pos..pos + length {
+                    searcher.submit_val(&reversed, p as u32);
+                }
+                max_ahead = max_ahead.max((pos + length - 1) as isize - stream.len() as isize);
+                pos += length;
+            }
+            _ => {
+                stream.push(reversed[pos]);
+                searcher.submit_val(&reversed, pos as u32);
+                max_ahead = max_ahead.max(pos as isize - stream.len() as isize);
+                pos += 1;
+            }
+        }
+        flag_count += 1;
+        let saved = pos as isize - stream.len() as isize;
+        if saved > max_ahead && saved > best.0 as isize - best.1 as isize {
+            best = (pos, stream.len());
+        }
+    }
+
+    let (compressed_len, stream_len) = best;
+    let raw_len = data.len() - compressed_len;
+    let mut out_buf = data[..raw_len].to_vec();
+    out_buf.extend(stream[..stream_len].iter().rev());
+    let mut hdr_len = 8;
+    while !out_buf.len().is_multiple_of(4) {
+        out_buf.push(0xFF);
+        hdr_len += 1;
+    }
+    if compressed_len <= stream_len + hdr_len {
+        // not worth it
+        let mut out_buf = data.to_vec();
+        out_buf.extend_from_slice(&[0; 4]);
+        return out_buf;
+    }
+    let enc_len = stream_len + hdr_len;
+    out_buf.extend_from_slice(&(enc_len as u32 | (hdr_len as u32) << 24).to_le_bytes());
+    let inc_len = data.len() - (out_buf.len() + 4);
+    out_buf.extend_from_slice(&(inc_len as u32).to_le_bytes());
+    out_buf
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, compress_arm9, decompress, decompress_in_place};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(30000, 5000);
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(compressed.len() % 4, 0);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+
+        let mut buf = compress_arm9(&data);
+        assert_eq!(buf[..0x4000], data[..0x4000]);
+        decompress_in_place(&mut buf).unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    pub fn test_short() {
+        // around the point where the footer costs more than compressing saves
+        for len in 0..64 {
+            let data = vec![7; len];
+            assert_eq!(decompress(&compress(&data)).unwrap(), data, "{}", len);
+            let mut buf = compress_arm9(&data);
+            decompress_in_place(&mut buf).unwrap();
+            assert_eq!(buf, data, "{}", len);
+        }
+    }
+
+    #[test]
+    pub fn test_incompressible() {
+        let mut state = 0x12345678u32;
+        let data: Vec<u8> = (0..1000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        let compressed = compress(&data);
+        assert_eq!(compressed[..data.len()], data);
+        assert_eq!(compressed[data.len()..], [0; 4]);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+}
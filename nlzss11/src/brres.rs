@@ -0,0 +1,145 @@
+//! Helpers for compressed BRRES files (`.brresC`), the model and texture archives that are one
+//! of the main users of nlzss11.
+
+use byteorder::{ByteOrder, BE, LE};
+
+use crate::codec::{self, DynCodec};
+use crate::{CompressError, DecompressError};
+
+/// Padding after the compressed stream is kept up to this alignment.
+const MAX_ALIGN: usize = 0x20;
+
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum BrresError {
+    #[error("decompression failed: {0}")]
+    Decompress(#[from] DecompressError),
+    #[error("invalid BRRES: {0}")]
+    InvalidBrres(&'static str),
+}
+
+/// The header of a BRRES file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrresInfo {
+    pub big_endian: bool,
+    /// The field after the byte order mark, 0 in the files of all known games.
+    pub version: u16,
+    /// File length according to the header.
+    pub file_len: u32,
+    pub root_offset: u16,
+    pub section_count: u16,
+}
+
+impl BrresInfo {
+    pub fn parse(data: &[u8]) -> Result<Self, BrresError> {
+        let header = data
+            .get(..0x10)
+            .ok_or(BrresError::InvalidBrres("too short"))?;
+        if &header[..4] != b"bres" {
+            return Err(BrresError::InvalidBrres("invalid bres magic"));
+        }
+        let big_endian = match &header[4..6] {
+            [0xFE, 0xFF] => true,
+            [0xFF, 0xFE] => false,
+            _ => return Err(BrresError::InvalidBrres("invalid byte order mark")),
+        };
+        let read_u16 = |buf: &[u8]| {
+            if big_endian {
+                BE::read_u16(buf)
+            } else {
+                LE::read_u16(buf)
+            }
+        };
+        let file_len = if big_endian {
+            BE::read_u32(&header[8..])
+        } else {
+            LE::read_u32(&header[8..])
+        };
+        if file_len as usize > data.len() {
+            return Err(BrresError::InvalidBrres("file length beyond the data"));
+        }
+        Ok(BrresInfo {
+            big_endian,
+            version: read_u16(&header[6..]),
+            file_len,
+            root_offset: read_u16(&header[0xC..]),
+            section_count: read_u16(&header[0xE..]),
+        })
+    }
+}
+
+/// A decompressed BRRES file, with the settings it was compressed with.
+#[derive(Debug, Clone)]
+pub struct BrresC {
+    pub data: Vec<u8>,
+    pub info: BrresInfo,
+    codec: &'static dyn DynCodec,
+    /// The compressed file was padded to a multiple of this.
+    align: usize,
+}
+
+impl BrresC {
+    /// Decompresses `compressed` and checks that it contains a BRRES file.
+    pub fn parse(compressed: &[u8]) -> Result<Self, BrresError> {
+        let (codec, identification) =
+            codec::best_match(codec::builtin().iter().copied(), compressed)
+                .ok_or(DecompressError::InvalidMagic)?;
+        let data = codec.decompress(compressed)?;
+        let info = BrresInfo::parse(&data)?;
+        let align = match identification.and_then(|i| i.compressed_len) {
+            Some(len) if len < compressed.len() => {
+                1 << compressed
+                    .len()
+                    .trailing_zeros()
+                    .min(MAX_ALIGN.trailing_zeros())
+            }
+            _ => 1,
+        };
+        Ok(BrresC {
+            data,
+            info,
+            codec,
+            align,
+        })
+    }
+
+    /// Name of the format the file was compressed with.
+    pub fn format(&self) -> &'static str {
+        self.codec.name()
+    }
+
+    /// Compresses `data` with the same format and padding as the original file, `data` is
+    /// usually the modified BRRES. Fails for formats that can only be decompressed.
+    pub fn recompress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        let mut compressed = self.codec.compress(data)?;
+        compressed.resize(compressed.len().next_multiple_of(self.align), 0);
+        Ok(compressed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BrresC, BrresError};
+
+    #[test]
+    pub fn test_brres() {
+        let mut brres = b"bres\xFE\xFF\x00\x00".to_vec();
+        brres.extend_from_slice(&0x40u32.to_be_bytes());
+        brres.extend_from_slice(&[0x00, 0x10, 0x00, 0x02]);
+        brres.resize(0x40, 0x33);
+        let compressed = crate::wszst::compress(&brres);
+
+        let brres_c = BrresC::parse(&compressed).unwrap();
+        assert_eq!(brres_c.data, brres);
+        assert_eq!(brres_c.format(), "nlzss11");
+        assert!(brres_c.info.big_endian);
+        assert_eq!(brres_c.info.file_len, 0x40);
+        assert_eq!(brres_c.info.section_count, 2);
+        assert_eq!(brres_c.recompress(&brres_c.data).unwrap(), compressed);
+
+        assert!(matches!(
+            BrresC::parse(&crate::compress(b"not a brres file")),
+            Err(BrresError::InvalidBrres(_))
+        ));
+    }
+}
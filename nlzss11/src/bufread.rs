@@ -0,0 +1,168 @@
+//! Adapters working on a [`BufRead`] source.
+
+use std::io::{self, BufRead, Cursor, Read};
+
+#[cfg(feature = "compress")]
+use crate::compress;
+#[cfg(feature = "decompress")]
+use crate::Decompressor;
+
+/// Reads the whole source and serves the transformed data afterwards.
+#[cfg(feature = "compress")]
+struct Transformed<B> {
+    inner: B,
+    output: Option<Cursor<Vec<u8>>>,
+}
+
+#[cfg(feature = "compress")]
+impl<B: BufRead> Transformed<B> {
+    fn new(inner: B) -> Self {
+        Transformed {
+            inner,
+            output: None,
+        }
+    }
+
+    fn read_with(
+        &mut self,
+        buf: &mut [u8],
+        transform: impl FnOnce(&[u8]) -> io::Result<Vec<u8>>,
+    ) -> io::Result<usize> {
+        if self.output.is_none() {
+            let mut input = Vec::new();
+            self.inner.read_to_end(&mut input)?;
+            self.output = Some(Cursor::new(transform(&input)?));
+        }
+        self.output.as_mut().unwrap().read(buf)
+    }
+}
+
+/// Reads uncompressed data from a [`BufRead`] and yields it compressed.
+///
+/// The source is read to the end on the first call to `read`.
+#[cfg(feature = "compress")]
+pub struct NlzssEncoder<B> {
+    inner: Transformed<B>,
+}
+
+#[cfg(feature = "compress")]
+impl<B: BufRead> NlzssEncoder<B> {
+    pub fn new(r: B) -> Self {
+        NlzssEncoder {
+            inner: Transformed::new(r),
+        }
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<B> NlzssEncoder<B> {
+    pub fn get_ref(&self) -> &B {
+        &self.inner.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.inner.inner
+    }
+
+    pub fn into_inner(self) -> B {
+        self.inner.inner
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<B: BufRead> Read for NlzssEncoder<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read_with(buf, |input| Ok(compress(input)))
+    }
+}
+
+/// Reads compressed data from a [`BufRead`] and yields it decompressed.
+///
+/// The data is decompressed straight out of the buffer of the source, without copying it first.
+/// Reading stops at the end of the compressed stream, anything after it is left in the source.
+/// Invalid or truncated data results in an [`io::ErrorKind::InvalidData`] error.
+#[cfg(feature = "decompress")]
+pub struct NlzssDecoder<B> {
+    inner: B,
+    decompressor: Decompressor,
+}
+
+#[cfg(feature = "decompress")]
+impl<B: BufRead> NlzssDecoder<B> {
+    pub fn new(r: B) -> Self {
+        NlzssDecoder {
+            inner: r,
+            decompressor: Decompressor::new(),
+        }
+    }
+}
+
+#[cfg(feature = "decompress")]
+impl<B> NlzssDecoder<B> {
+    pub fn get_ref(&self) -> &B {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+#[cfg(feature = "decompress")]
+impl<B: BufRead> Read for NlzssDecoder<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let input = self.inner.fill_buf()?;
+            let eof = input.is_empty();
+            let (consumed, written) = self
+                .decompressor
+                .decompress(input, buf)
+                .map_err(invalid_data)?;
+            self.inner.consume(consumed);
+            if written > 0 || self.decompressor.is_finished() {
+                return Ok(written);
+            }
+            if eof {
+                return self.decompressor.finish().map(|_| 0).map_err(invalid_data);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use std::io::{BufReader, Read};
+
+    use super::NlzssDecoder;
+    use crate::compress;
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_decoder() {
+        let data = sample(30000, 0);
+        let mut compressed = compress(&data);
+        let compressed_len = compressed.len();
+        compressed.extend_from_slice(b"rest");
+
+        let mut decoder = NlzssDecoder::new(BufReader::with_capacity(7, compressed.as_slice()));
+        let mut decompressed = Vec::new();
+        decoder.read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+        // the data after the stream is still there
+        let mut rest = Vec::new();
+        decoder.into_inner().read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"rest");
+
+        let mut decoder = NlzssDecoder::new(&compressed[..compressed_len - 1]);
+        let err = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,626 @@
+//! Object-safe codec interface and a registry to dispatch on the compressed data, for tools that
+//! handle several compression formats without knowing about them.
+//!
+//! [`builtin`] lists the codecs of this crate, [`crate::detect`] and [`crate::decompress_any`]
+//! go through it as well, so a new format only has to be added there.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::detect::{self, Identification};
+use crate::lz10::Lz10 as Lz10Variant;
+use crate::lz40::Lz40 as Lz40Variant;
+use crate::lz60::Lz60 as Lz60Variant;
+#[cfg(feature = "yaz0")]
+use crate::yaz0::Yaz0 as Yaz0Variant;
+#[cfg(feature = "compress")]
+use crate::CompressError;
+use crate::{mio0, yay0, DecompressError, Lz11, LZ77_PREFIX};
+
+/// A compression format, recognized by the start of the compressed data.
+pub trait DynCodec: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Whether `data` starts with the magic of this codec. Doesn't check the rest, so the data
+    /// can still turn out to be invalid when it is decompressed.
+    fn matches(&self, data: &[u8]) -> bool;
+
+    /// Checks the header and walks over the compressed stream without decompressing it, see
+    /// [`crate::detect`]. `None` if the data isn't compressed with this codec or the codec
+    /// can't tell, which is the default.
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        let _ = data;
+        None
+    }
+
+    /// File extensions without the dot, the first one is used for newly compressed files.
+    fn extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Fails with [`CompressError::Unsupported`] for formats that can only be decompressed.
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError>;
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError>;
+}
+
+impl fmt::Debug for dyn DynCodec + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynCodec").field(&self.name()).finish()
+    }
+}
+
+/// Lets the codecs of [`builtin`] be put into a [`Registry`].
+struct Builtin(&'static dyn DynCodec);
+
+impl DynCodec for Builtin {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        self.0.matches(data)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        self.0.identify(data)
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        self.0.extensions()
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        self.0.compress(data)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        self.0.decompress(data)
+    }
+}
+
+/// The nlzss11 (LZ11) format of this crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nlzss11;
+
+impl DynCodec for Nlzss11 {
+    fn name(&self) -> &'static str {
+        "nlzss11"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.strip_prefix(LZ77_PREFIX).unwrap_or(data).first() == Some(&0x11)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_lzss::<Lz11>(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["LZ"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::decompress(data)
+    }
+}
+
+/// The LZ10 format, see [`crate::lz10`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz10;
+
+impl DynCodec for Lz10 {
+    fn name(&self) -> &'static str {
+        "lz10"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x10)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_lzss::<Lz10Variant>(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["LZ"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::lz10::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::lz10::decompress(data)
+    }
+}
+
+/// The LZ40 format, see [`crate::lz40`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz40;
+
+impl DynCodec for Lz40 {
+    fn name(&self) -> &'static str {
+        "lz40"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x40)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_lzss::<Lz40Variant>(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["LZ"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::lz40::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::lz40::decompress(data)
+    }
+}
+
+/// The LH format, see [`crate::lh`]. It shares its type byte with [`Lz40`], which is tried
+/// first when neither can tell.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lh;
+
+impl DynCodec for Lh {
+    fn name(&self) -> &'static str {
+        "lh"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x40)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_lh(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["lh"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, _data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Err(CompressError::Unsupported(self.name()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::lh::decompress(data)
+    }
+}
+
+/// The LZ60 format, see [`crate::lz60`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz60;
+
+impl DynCodec for Lz60 {
+    fn name(&self) -> &'static str {
+        "lz60"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x60)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_lzss::<Lz60Variant>(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["LZ"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::lz60::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::lz60::decompress(data)
+    }
+}
+
+/// The Yaz0 format, see [`crate::yaz0`].
+#[cfg(feature = "yaz0")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yaz0;
+
+#[cfg(feature = "yaz0")]
+impl DynCodec for Yaz0 {
+    fn name(&self) -> &'static str {
+        "yaz0"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(b"Yaz0")
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_lzss::<Yaz0Variant>(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["szs", "yaz0"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::yaz0::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::yaz0::decompress(data)
+    }
+}
+
+/// The Yay0 format, see [`crate::yay0`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yay0;
+
+impl DynCodec for Yay0 {
+    fn name(&self) -> &'static str {
+        "yay0"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(b"Yay0")
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_split(data, &yay0::YAY0, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["szp", "yay0"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::yay0::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::yay0::decompress(data)
+    }
+}
+
+/// The MIO0 format, see [`crate::mio0`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mio0;
+
+impl DynCodec for Mio0 {
+    fn name(&self) -> &'static str {
+        "mio0"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.starts_with(b"MIO0")
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_split(data, &mio0::MIO0, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["mio0"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::mio0::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::mio0::decompress(data)
+    }
+}
+
+/// The RLE format, see [`crate::rle`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rle;
+
+impl DynCodec for Rle {
+    fn name(&self) -> &'static str {
+        "rle"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x30)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_rle(data, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rle"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::rle::compress(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::rle::decompress(data)
+    }
+}
+
+/// The 4 bit Huffman format, see [`crate::huffman`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Huffman4;
+
+impl DynCodec for Huffman4 {
+    fn name(&self) -> &'static str {
+        "huffman4"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x24)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_huffman(data, 0x24, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["huff"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::huffman::compress_4bit(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::huffman::decompress(data)
+    }
+}
+
+/// The 8 bit Huffman format, see [`crate::huffman`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Huffman8;
+
+impl DynCodec for Huffman8 {
+    fn name(&self) -> &'static str {
+        "huffman8"
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        data.first() == Some(&0x28)
+    }
+
+    fn identify(&self, data: &[u8]) -> Option<Identification> {
+        detect::identify_huffman(data, 0x28, self.name())
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["huff"]
+    }
+
+    #[cfg(feature = "compress")]
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+        Ok(crate::huffman::compress_8bit(data))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        crate::huffman::decompress(data)
+    }
+}
+
+/// All codecs of this crate, in the order they are tried.
+static BUILTIN: &[&dyn DynCodec] = &[
+    &Nlzss11,
+    &Lz10,
+    &Lz40,
+    &Lh,
+    &Lz60,
+    #[cfg(feature = "yaz0")]
+    &Yaz0,
+    &Yay0,
+    &Mio0,
+    &Rle,
+    &Huffman4,
+    &Huffman8,
+];
+
+/// All codecs of this crate.
+pub fn builtin() -> &'static [&'static dyn DynCodec] {
+    BUILTIN
+}
+
+/// The codec of this crate named `name`.
+pub fn builtin_by_name(name: &str) -> Option<&'static dyn DynCodec> {
+    BUILTIN.iter().copied().find(|codec| codec.name() == name)
+}
+
+/// The codec of `codecs` that compressed `data` most likely and its [`Identification`].
+///
+/// The codec identifying the data with the highest confidence wins, the first one of equally
+/// good ones. A codec that can't identify the data but whose magic matches is only picked if
+/// no codec identifies it, so broken data still goes to the decoder that reports what is wrong.
+pub(crate) fn best_match<'a>(
+    codecs: impl Iterator<Item = &'a dyn DynCodec>,
+    data: &[u8],
+) -> Option<(&'a dyn DynCodec, Option<Identification>)> {
+    let rank = |identification: &Option<Identification>| {
+        identification
+            .as_ref()
+            .map(|identification| identification.confidence)
+    };
+    let mut best: Option<(&dyn DynCodec, Option<Identification>)> = None;
+    for codec in codecs {
+        let identification = codec.identify(data);
+        if identification.is_none() && !codec.matches(data) {
+            continue;
+        }
+        if best
+            .as_ref()
+            .is_none_or(|(_, best)| rank(&identification) > rank(best))
+        {
+            best = Some((codec, identification));
+        }
+    }
+    best
+}
+
+/// A set of codecs, starting with the ones of this crate or empty.
+#[derive(Default)]
+pub struct Registry {
+    // the most recently registered codec comes first
+    codecs: Vec<Box<dyn DynCodec>>,
+}
+
+impl Registry {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with all codecs of this crate.
+    pub fn with_builtin() -> Self {
+        Registry {
+            codecs: BUILTIN
+                .iter()
+                .map(|&codec| Box::new(Builtin(codec)) as Box<dyn DynCodec>)
+                .collect(),
+        }
+    }
+
+    /// Adds `codec`, returns the codec previously registered with the same name.
+    ///
+    /// Codecs registered later take precedence over ones identifying data equally well.
+    pub fn register(&mut self, codec: Box<dyn DynCodec>) -> Option<Box<dyn DynCodec>> {
+        let previous = self
+            .codecs
+            .iter()
+            .position(|c| c.name() == codec.name())
+            .map(|index| self.codecs.remove(index));
+        self.codecs.insert(0, codec);
+        previous
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&dyn DynCodec> {
+        self.codecs().find(|codec| codec.name() == name)
+    }
+
+    /// Names of all codecs, sorted.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.codecs().map(|codec| codec.name()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Strips the extension of a registered codec from `filename`, `None` if it has none.
+    pub fn strip_extension<'a>(&self, filename: &'a str) -> Option<&'a str> {
+        self.codecs()
+            .flat_map(|codec| codec.extensions())
+            .find_map(|ext| filename.strip_suffix(ext)?.strip_suffix('.'))
+    }
+
+    /// The codec `data` is compressed with, see [`Registry::identify`].
+    pub fn codec_for(&self, data: &[u8]) -> Option<&dyn DynCodec> {
+        best_match(self.codecs(), data).map(|(codec, _)| codec)
+    }
+
+    /// Guesses the format of `data` like [`crate::detect::identify`], but with the codecs of
+    /// this registry.
+    pub fn identify(&self, data: &[u8]) -> Option<Identification> {
+        best_match(self.codecs(), data)?.1
+    }
+
+    pub fn codecs(&self) -> impl Iterator<Item = &dyn DynCodec> {
+        self.codecs.iter().map(|codec| codec.as_ref())
+    }
+
+    /// Decompresses `data` with the codec it is compressed with.
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+        self.codec_for(data)
+            .ok_or(DecompressError::InvalidMagic)?
+            .decompress(data)
+    }
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::{DynCodec, Registry};
+    use crate::{detect, CompressError, DecompressError};
+
+    /// Stores the data uncompressed after a 0xFF byte.
+    struct Stored;
+
+    impl DynCodec for Stored {
+        fn name(&self) -> &'static str {
+            "stored"
+        }
+
+        fn matches(&self, data: &[u8]) -> bool {
+            data.first() == Some(&0xFF)
+        }
+
+        fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CompressError> {
+            Ok([0xFF].iter().chain(data).copied().collect())
+        }
+
+        fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+            Ok(data[1..].to_vec())
+        }
+    }
+
+    #[test]
+    pub fn test_registry() {
+        let mut registry = Registry::with_builtin();
+        assert!(registry.register(Box::new(Stored)).is_none());
+        let data = b"data data data data";
+        for codec in registry.codecs() {
+            let Ok(compressed) = codec.compress(data) else {
+                // decompression only
+                assert_eq!(codec.name(), "lh");
+                continue;
+            };
+            assert_eq!(
+                registry.codec_for(&compressed).unwrap().name(),
+                codec.name()
+            );
+            assert_eq!(registry.decompress(&compressed).unwrap(), data);
+            assert_eq!(
+                registry.identify(&compressed),
+                detect::identify(&compressed)
+            );
+        }
+        // broken data goes to the codec of its magic, which reports what is wrong with it
+        let broken = [0x11, 0x10, 0, 0, 0x80, 0xF0, 0x00];
+        assert_eq!(registry.codec_for(&broken).unwrap().name(), "nlzss11");
+        let prefixed = [b"LZ77".as_slice(), &broken].concat();
+        assert_eq!(registry.codec_for(&prefixed).unwrap().name(), "nlzss11");
+        assert!(matches!(
+            registry.decompress(&broken),
+            Err(DecompressError::InvalidIndex(_))
+        ));
+        assert_eq!(registry.strip_extension("model.szp"), Some("model"));
+        assert_eq!(registry.strip_extension("file.arc.LZ"), Some("file.arc"));
+        assert_eq!(registry.strip_extension("file.bin"), None);
+        assert!(matches!(
+            registry.decompress(&[0x42, 0, 0, 0]),
+            Err(DecompressError::InvalidMagic)
+        ));
+    }
+}
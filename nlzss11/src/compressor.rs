@@ -0,0 +1,180 @@
+use alloc::vec::Vec;
+
+use crate::lzss::{
+    encode_greedy_until, GroupState, GroupWriter, LzssVariant, MatchFinder, MatchLimits,
+    MatchSearcher,
+};
+use crate::Lz11;
+
+/// Backreferences reach at most this far back.
+const WINDOW_SIZE: usize = 0x1000;
+/// Compressed data is only dropped from the buffer once there is this much of it, because the
+/// window has to be inserted into the new hash table every time.
+const DISCARD_SIZE: usize = 1 << 20;
+
+/// Compresses data that arrives in pieces, keeping only the compressed output and the input
+/// matches can still reach around.
+///
+/// The output is the same as [`crate::compress_canonical`]'s for all of the data at once. The
+/// header holds the decompressed size, so nothing is returned before [`Compressor::finish`].
+///
+/// ```
+/// # #[cfg(feature = "decompress")] {
+/// let mut compressor = nlzss11::Compressor::new();
+/// for part in ["fed ", "in ", "parts, ", "fed in parts"] {
+///     compressor.feed(part.as_bytes());
+/// }
+/// let compressed = compressor.finish();
+/// assert_eq!(nlzss11::decompress(&compressed).unwrap(), b"fed in parts, fed in parts");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Compressor {
+    /// The window before `pos` and the data after it that hasn't been compressed yet.
+    buffer: Vec<u8>,
+    pos: usize,
+    /// Input before `buffer`.
+    discarded: usize,
+    searcher: MatchSearcher,
+    groups: Vec<u8>,
+    group_state: GroupState,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compressor {
+    pub fn new() -> Self {
+        let mut groups = Vec::new();
+        let group_state = GroupWriter::<Lz11>::headerless(&mut groups).pause();
+        Compressor {
+            buffer: Vec::new(),
+            pos: 0,
+            discarded: 0,
+            searcher: MatchSearcher::new(),
+            groups,
+            group_state,
+        }
+    }
+
+    /// Total number of bytes fed so far.
+    pub fn total_in(&self) -> usize {
+        self.discarded + self.buffer.len()
+    }
+
+    /// Appends `data` to the input and compresses everything the following data can't change
+    /// anymore.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+        // a match starting here could still get longer
+        let end = self.buffer.len().saturating_sub(Lz11::MAX_LENGTH as usize);
+        if end > self.pos {
+            self.compress_until(end);
+        }
+        if self.pos > DISCARD_SIZE + WINDOW_SIZE {
+            let discard = self.pos - WINDOW_SIZE;
+            self.buffer.drain(..discard);
+            self.discarded += discard;
+            self.pos -= discard;
+            self.searcher.reset();
+            for pos in 0..self.pos {
+                self.searcher.insert(&self.buffer, pos);
+            }
+        }
+    }
+
+    /// Compresses the rest of the input and returns all of the compressed data. Afterwards the
+    /// compressor is empty again and can be used for the next input.
+    ///
+    /// Panics if more than `u32::MAX` bytes have been fed, which the header can't hold.
+    pub fn finish(&mut self) -> Vec<u8> {
+        self.compress_until(self.buffer.len());
+        GroupWriter::<Lz11>::resume(&mut self.groups, self.group_state).finish();
+        let mut out_buf = Vec::with_capacity(8 + self.groups.len());
+        Lz11::write_header(&mut out_buf, self.total_in());
+        out_buf.extend_from_slice(&self.groups);
+        self.reset();
+        out_buf
+    }
+
+    /// Compresses all of `data` at once like [`crate::compress_canonical`], reusing the hash
+    /// table and buffers of the compressor instead of allocating them for every input.
+    ///
+    /// ```
+    /// let mut compressor = nlzss11::Compressor::new();
+    /// for file in [&b"first first first"[..], b"second second second"] {
+    ///     assert_eq!(compressor.compress(file), nlzss11::compress_canonical(file));
+    /// }
+    /// ```
+    pub fn compress(&mut self, data: &[u8]) -> Vec<u8> {
+        self.feed(data);
+        self.finish()
+    }
+
+    /// Drops everything fed so far, keeping the allocations for the next input.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.pos = 0;
+        self.discarded = 0;
+        self.searcher.reset();
+        self.groups.clear();
+        self.group_state = GroupWriter::<Lz11>::headerless(&mut self.groups).pause();
+    }
+
+    fn compress_until(&mut self, end: usize) {
+        let mut writer = GroupWriter::<Lz11>::resume(&mut self.groups, self.group_state);
+        self.pos = encode_greedy_until(
+            &self.buffer,
+            self.pos,
+            end,
+            &mut writer,
+            &mut self.searcher,
+            MatchLimits::of::<Lz11>(),
+            1,
+            false,
+            false,
+        );
+        self.group_state = writer.pause();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compressor;
+    use crate::compress_canonical;
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_fed_in_chunks() {
+        let data = sample(3_000_000, 200_000);
+        let expected = compress_canonical(&data);
+        for chunk_size in [1000, 4096, 100_000, data.len()] {
+            let mut compressor = Compressor::new();
+            for chunk in data.chunks(chunk_size) {
+                compressor.feed(chunk);
+            }
+            assert_eq!(compressor.total_in(), data.len());
+            assert_eq!(compressor.finish(), expected);
+        }
+        assert_eq!(Compressor::new().finish(), compress_canonical(&[]));
+    }
+
+    #[test]
+    pub fn test_reuse() {
+        let mut compressor = Compressor::new();
+        compressor.feed(b"dropped dropped dropped");
+        compressor.reset();
+        assert_eq!(compressor.total_in(), 0);
+        for data in [
+            &b"first first first"[..],
+            b"",
+            &[3; 100_000],
+            b"second second second",
+        ] {
+            assert_eq!(compressor.compress(data), compress_canonical(data));
+        }
+    }
+}
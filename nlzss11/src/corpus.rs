@@ -0,0 +1,196 @@
+//! Pathological but valid nlzss11 streams, for testing other decoders against.
+//!
+//! The streams are written token by token instead of coming from the compressor, so they
+//! contain things the compressor never produces: overlapping matches of the maximum length,
+//! the smallest and biggest value of every code size, extended headers for small sizes and
+//! sizes right at the limit of the short header.
+
+use alloc::vec::Vec;
+
+use crate::LzssCode;
+
+/// A compressed stream and what it decompresses to.
+pub struct CorpusEntry {
+    pub name: &'static str,
+    pub compressed: Vec<u8>,
+    pub decompressed: Vec<u8>,
+}
+
+/// Builds a stream from literals and backreferences, keeping track of the decompressed data.
+struct StreamBuilder {
+    body: Vec<u8>,
+    decompressed: Vec<u8>,
+    group_header_pos: usize,
+    group_header_count: u8,
+}
+
+impl StreamBuilder {
+    fn new() -> Self {
+        StreamBuilder {
+            body: Vec::new(),
+            decompressed: Vec::new(),
+            group_header_pos: 0,
+            group_header_count: 8,
+        }
+    }
+
+    fn next_flag(&mut self, backref: bool) {
+        if self.group_header_count == 8 {
+            self.group_header_pos = self.body.len();
+            self.body.push(0);
+            self.group_header_count = 0;
+        }
+        if backref {
+            self.body[self.group_header_pos] |= 0x80 >> self.group_header_count;
+        }
+        self.group_header_count += 1;
+    }
+
+    fn literal(&mut self, byte: u8) -> &mut Self {
+        self.next_flag(false);
+        self.body.push(byte);
+        self.decompressed.push(byte);
+        self
+    }
+
+    fn literals(&mut self, bytes: &[u8]) -> &mut Self {
+        for &byte in bytes {
+            self.literal(byte);
+        }
+        self
+    }
+
+    /// `distance` has to be within the data decompressed so far.
+    fn backref(&mut self, distance: u32, length: u32) -> &mut Self {
+        self.next_flag(true);
+        LzssCode { distance, length }.write(&mut self.body);
+        let start = self.decompressed.len() - distance as usize;
+        for pos in start..start + length as usize {
+            self.decompressed.push(self.decompressed[pos]);
+        }
+        self
+    }
+
+    fn finish(&self, name: &'static str, extended_header: bool) -> CorpusEntry {
+        let mut compressed = Vec::with_capacity(self.body.len() + 8);
+        let len = self.decompressed.len() as u32;
+        if extended_header || len > 0xFFFFFF {
+            compressed.extend([0x11, 0, 0, 0]);
+            compressed.extend(len.to_le_bytes());
+        } else {
+            compressed.push(0x11);
+            compressed.extend(&len.to_le_bytes()[..3]);
+        }
+        compressed.extend_from_slice(&self.body);
+        CorpusEntry {
+            name,
+            compressed,
+            decompressed: self.decompressed.clone(),
+        }
+    }
+}
+
+/// Repeats backreferences to the previous byte until the decompressed data has `len` bytes.
+fn fill_to(builder: &mut StreamBuilder, len: usize) {
+    while builder.decompressed.len() < len {
+        let length = (len - builder.decompressed.len()).min(0x10110);
+        if length < 3 {
+            builder.literal(0);
+        } else {
+            builder.backref(1, length as u32);
+        }
+    }
+}
+
+/// All streams of the corpus, they add up to about 32 MiB decompressed.
+pub fn generate() -> Vec<CorpusEntry> {
+    let mut entries = Vec::new();
+
+    entries.push(
+        StreamBuilder::new()
+            .literal(0x11)
+            .finish("single literal", false),
+    );
+
+    // literals only, also as the last group with only one of its flags used
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    let mut builder = StreamBuilder::new();
+    builder.literals(&all_bytes).literal(0);
+    entries.push(builder.finish("all literals", false));
+
+    // the length and distance limits of every code size
+    let mut builder = StreamBuilder::new();
+    builder.literals(&all_bytes);
+    // the distance 1 codes come first so there is enough data for the longer distances
+    for (length, distance) in [
+        (3, 1),
+        (16, 1),
+        (0x11, 1),
+        (0x110, 1),
+        (0x111, 1),
+        (0x10110, 1),
+        (3, 256),
+        (16, 256),
+        (0x11, 0x1000),
+        (0x110, 0x1000),
+        (0x111, 0x1000),
+        (0x10110, 0x1000),
+    ] {
+        builder.backref(distance, length);
+    }
+    entries.push(builder.finish("code size boundaries", false));
+
+    // every backreference overlaps the data it produces
+    let mut builder = StreamBuilder::new();
+    builder.literal(0xAA).literal(0x55);
+    for distance in [1, 2, 1, 2] {
+        builder.backref(distance, 0x10110);
+    }
+    entries.push(builder.finish("overlapping maximum length", false));
+
+    // the last backreference ends exactly at the end of the data
+    let mut builder = StreamBuilder::new();
+    builder.literals(b"end").backref(3, 0x10110);
+    entries.push(builder.finish("backreference to the end", false));
+
+    let mut builder = StreamBuilder::new();
+    builder.literals(b"small but extended");
+    entries.push(builder.finish("extended header", true));
+
+    // the biggest size in the short header and the smallest one needing the extended header
+    for (name, len) in [
+        ("max short header", 0xFFFFFF),
+        ("min extended header", 0x1000000),
+    ] {
+        let mut builder = StreamBuilder::new();
+        builder.literals(&all_bytes);
+        fill_to(&mut builder, len);
+        entries.push(builder.finish(name, false));
+    }
+
+    entries
+}
+
+#[cfg(all(test, feature = "std", feature = "compress", feature = "decompress"))]
+mod test {
+    use super::generate;
+    use crate::{decompress, reference};
+
+    #[test]
+    pub fn test_corpus() {
+        for entry in generate() {
+            assert_eq!(
+                decompress(&entry.compressed).unwrap(),
+                entry.decompressed,
+                "{}",
+                entry.name
+            );
+            assert_eq!(
+                reference::decompress(&entry.compressed).unwrap(),
+                entry.decompressed,
+                "{}",
+                entry.name
+            );
+        }
+    }
+}
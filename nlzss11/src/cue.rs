@@ -0,0 +1,137 @@
+//! Encoders producing the same output as CUE's `lzss` (LZ10) and `lzx` (LZ11) tools, for
+//! patches and build systems that compare compressed files byte for byte.
+//!
+//! They search the whole window for every byte, so they are a lot slower than the normal
+//! compressors.
+
+use alloc::vec::Vec;
+
+use crate::lz10::Lz10;
+use crate::lzss::{match_length, LzssVariant};
+use crate::{Lz11, LzssCode};
+
+const WINDOW_SIZE: usize = 0x1000;
+/// Matches have to be longer than this.
+const THRESHOLD: usize = 2;
+
+/// The mode of the CUE tools, `-ewn`, `-evn`, `-ewo` or `-evo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CueMode {
+    /// Never copies from the previous byte, so the data can be decompressed to VRAM.
+    pub vram: bool,
+    /// Looks one match ahead to decide whether a literal is better.
+    pub optimal: bool,
+}
+
+/// Compresses like `lzss -e?? file`.
+pub fn compress_lz10(data: &[u8], mode: CueMode) -> Vec<u8> {
+    compress::<Lz10>(data, mode)
+}
+
+/// Compresses like `lzx -e?? file`.
+pub fn compress_lz11(data: &[u8], mode: CueMode) -> Vec<u8> {
+    compress::<Lz11>(data, mode)
+}
+
+/// The longest match at `pos`, the closest one if there are several.
+///
+/// Matches never overlap the data they are copying.
+fn search(data: &[u8], pos: usize, max_length: usize, vram: bool) -> (usize, usize) {
+    let mut best = (0, THRESHOLD);
+    let max_distance = pos.min(WINDOW_SIZE);
+    let rest = &data[pos..];
+    for distance in (vram as usize + 1)..=max_distance {
+        let length = match_length(
+            &rest[..rest.len().min(max_length).min(distance)],
+            &data[pos - distance..],
+        );
+        if length > best.1 {
+            best = (distance, length);
+            if length == max_length {
+                break;
+            }
+        }
+    }
+    best
+}
+
+fn compress<V: LzssVariant>(data: &[u8], mode: CueMode) -> Vec<u8> {
+    let max_length = V::MAX_LENGTH as usize;
+    let mut out_buf = Vec::with_capacity(data.len() + data.len().div_ceil(8) + 4);
+    V::write_header(&mut out_buf, data.len());
+
+    let mut flag_pos = 0;
+    let mut flag_count = 8;
+    let mut pos = 0;
+    while pos < data.len() {
+        if flag_count == 8 {
+            flag_pos = out_buf.len();
+            out_buf.push(0);
+            flag_count = 0;
+        }
+        let (distance, mut length) = search(data, pos, max_length, mode.vram);
+        if mode.optimal && length > THRESHOLD && pos + length < data.len() {
+            // a literal is better if the match after it covers more than both matches
+            let next = search(data, pos + length, max_length, mode.vram).1;
+            let post = search(data, pos + 1, max_length, mode.vram).1;
+            let next = if next > THRESHOLD { next } else { 1 };
+            let post = if post > THRESHOLD { post } else { 1 };
+            if length + next <= 1 + post {
+                length = 1;
+            }
+        }
+        if length > THRESHOLD {
+            out_buf[flag_pos] |= V::flag(flag_count);
+            V::write_code(
+                &LzssCode {
+                    distance: distance as u32,
+                    length: length as u32,
+                },
+                &mut out_buf,
+            );
+            pos += length;
+        } else {
+            out_buf.push(data[pos]);
+            pos += 1;
+        }
+        flag_count += 1;
+    }
+    out_buf
+}
+
+#[cfg(all(test, feature = "std", feature = "decompress"))]
+mod test {
+    use super::{compress_lz10, compress_lz11, CueMode};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_compress() {
+        let mode = CueMode::default();
+        assert_eq!(
+            compress_lz10(b"aaaaaaaa", mode),
+            [0x10, 8, 0, 0, 0x10, b'a', b'a', b'a', 0x00, 0x02, b'a', b'a']
+        );
+        let vram = CueMode {
+            vram: true,
+            optimal: false,
+        };
+        assert_eq!(
+            compress_lz11(b"abababab", vram),
+            [0x11, 8, 0, 0, 0x08, b'a', b'b', b'a', b'b', 0x30, 0x03]
+        );
+
+        let data = sample(8000, 0);
+        for vram in [false, true] {
+            for optimal in [false, true] {
+                let mode = CueMode { vram, optimal };
+                let lz10 = compress_lz10(&data, mode);
+                assert_eq!(crate::lz10::decompress(&lz10).unwrap(), data);
+                let lz11 = compress_lz11(&data, mode);
+                assert_eq!(crate::decompress(&lz11).unwrap(), data);
+                if vram {
+                    crate::bios::check(&lz10, true).unwrap();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Cheap identification of compressed data, for labeling unknown blobs in archive browsers.
+//!
+//! Identifying never decompresses the data or allocates based on sizes from headers, it only
+//! walks over the compressed stream to check that it is consistent.
+
+use byteorder::{ByteOrder, BE};
+
+use crate::codec;
+use crate::lzss::{self, read_header, Counter, LzssVariant};
+use crate::yay0::{self, SplitFormat};
+use crate::{huffman, lh, rle, DecompressError, LzssCode, LZ77_PREFIX};
+
+/// Trailing zeros up to this length are treated as padding after the compressed stream.
+const MAX_PADDING: usize = 0x20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Confidence {
+    /// The header is valid, but the stream is truncated or empty.
+    Low,
+    /// The stream is valid, but followed by other data.
+    Medium,
+    /// The stream is valid and covers all of the data, except for padding.
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identification {
+    /// Name of the codec in [`crate::codec`].
+    pub format: &'static str,
+    /// Length of the compressed stream, `None` if the data ends before the stream does.
+    pub compressed_len: Option<usize>,
+    pub decompressed_len: usize,
+    pub confidence: Confidence,
+}
+
+/// Guesses the format of `data` with the codecs of [`codec::builtin`], `None` if it doesn't look
+/// like any of them.
+pub fn identify(data: &[u8]) -> Option<Identification> {
+    codec::best_match(codec::builtin().iter().copied(), data)?.1
+}
+
+/// Finds complete compressed streams embedded in `data`, checking every `align` bytes.
+///
+/// Returns the offset of each stream, the search continues after the end of a found stream.
+///
+/// Offsets without the magic of a codec or with a decompressed size the rest of `data` can't
+/// expand to are skipped on the header alone. The others are walked up to the end of their
+/// stream or the first inconsistency, so data full of bytes that look like headers takes up to
+/// `data.len() / align` walks over the rest of it; use the alignment of the container, usually
+/// 4 or more, rather than 1.
+pub fn scan(data: &[u8], align: usize) -> impl Iterator<Item = (usize, Identification)> + '_ {
+    let align = align.max(1);
+    let mut offset = 0;
+    core::iter::from_fn(move || {
+        while offset < data.len() {
+            let start = offset;
+            offset += align;
+            if !plausible_header(&data[start..]) {
+                continue;
+            }
+            let Some(identification) = identify(&data[start..]) else {
+                continue;
+            };
+            let Some(compressed_len) = identification.compressed_len else {
+                continue;
+            };
+            if identification.confidence < Confidence::Medium
+                || identification.decompressed_len == 0
+            {
+                continue;
+            }
+            offset = (start + compressed_len).next_multiple_of(align);
+            return Some((start, identification));
+        }
+        None
+    })
+}
+
+/// Whether `data` starts with the magic of a codec of [`codec::builtin`] and a decompressed size
+/// that the rest of `data` could decompress to, see [`lzss::max_output`].
+fn plausible_header(data: &[u8]) -> bool {
+    if !codec::builtin().iter().any(|codec| codec.matches(data)) {
+        return false;
+    }
+    let data = data.strip_prefix(LZ77_PREFIX).unwrap_or(data);
+    let header = match data {
+        [magic @ (0x10 | 0x11 | 0x24 | 0x28 | 0x30 | 0x40 | 0x60), ..] => {
+            read_header(data, *magic).ok()
+        }
+        // Yaz0, Yay0 and MIO0 all have the size after the magic and a 16 byte header
+        [b'Y' | b'M', ..] => (data.len() >= 16).then(|| (BE::read_u32(&data[4..]) as usize, 16)),
+        // a codec this doesn't know the header of, identify it the slow way
+        _ => return true,
+    };
+    header
+        .is_some_and(|(out_size, header_len)| out_size <= lzss::max_output(data.len() - header_len))
+}
+
+fn confidence_for(data: &[u8], compressed_len: usize) -> Confidence {
+    let rest = &data[compressed_len..];
+    if rest.len() < MAX_PADDING && rest.iter().all(|&b| b == 0) {
+        Confidence::High
+    } else {
+        Confidence::Medium
+    }
+}
+
+pub(crate) fn identify_lzss<V: LzssVariant>(
+    data: &[u8],
+    format: &'static str,
+) -> Option<Identification> {
+    let (out_size, mut pos) = V::read_header(data).ok()?;
+    let mut identification = Identification {
+        format,
+        compressed_len: None,
+        decompressed_len: out_size,
+        confidence: Confidence::Low,
+    };
+    if out_size == 0 {
+        return Some(identification);
+    }
+
+    let mut out_pos = 0;
+    let mut group_header = 0;
+    let mut remaining_chunks = 0;
+    while out_pos < out_size {
+        if remaining_chunks == 0 {
+            let Some(&byte) = data.get(pos) else {
+                return Some(identification);
+            };
+            group_header = byte;
+            pos += 1;
+            remaining_chunks = 8;
+        }
+        if !V::is_backref(group_header, 8 - remaining_chunks) {
+            if pos >= data.len() {
+                return Some(identification);
+            }
+            pos += 1;
+            out_pos += 1;
+        } else {
+            let Some((LzssCode { distance, length }, advance)) = V::read_code(&data[pos..]) else {
+                return Some(identification);
+            };
+            if distance == 0 || distance as usize > out_pos {
+                return None;
+            }
+            pos += advance;
+            out_pos += length as usize;
+        }
+        remaining_chunks -= 1;
+    }
+    identification.compressed_len = Some(pos);
+    identification.confidence = confidence_for(data, pos);
+    Some(identification)
+}
+
+pub(crate) fn identify_split(
+    data: &[u8],
+    split_format: &SplitFormat,
+    format: &'static str,
+) -> Option<Identification> {
+    let decompressed_len = yay0::read_header(data, split_format).ok()?;
+    let (compressed_len, confidence) = match yay0::decode(data, split_format, &mut Counter(0)) {
+        Ok(len) => (Some(len), confidence_for(data, len)),
+        // the tables can be anywhere, so there is no telling invalid and truncated data apart
+        Err(_) => (None, Confidence::Low),
+    };
+    Some(Identification {
+        format,
+        compressed_len,
+        decompressed_len,
+        confidence,
+    })
+}
+
+pub(crate) fn identify_rle(data: &[u8], format: &'static str) -> Option<Identification> {
+    if data.first() != Some(&0x30) {
+        return None;
+    }
+    let (decompressed_len, _) = read_header(data, 0x30).ok()?;
+    let (compressed_len, confidence) = match rle::decode(data, &mut Counter(0)) {
+        Ok(len) => (Some(len), confidence_for(data, len)),
+        // every byte is a valid flag, so the only way to fail is running out of data
+        Err(_) => (None, Confidence::Low),
+    };
+    Some(Identification {
+        format,
+        compressed_len,
+        decompressed_len,
+        confidence,
+    })
+}
+
+pub(crate) fn identify_huffman(
+    data: &[u8],
+    magic: u8,
+    format: &'static str,
+) -> Option<Identification> {
+    let (decompressed_len, _) = read_header(data, magic).ok()?;
+    let (compressed_len, confidence) = match huffman::decode(data, &mut Counter(0)) {
+        Ok(len) => (Some(len), confidence_for(data, len)),
+        Err(_) => (None, Confidence::Low),
+    };
+    Some(Identification {
+        format,
+        compressed_len,
+        decompressed_len,
+        confidence,
+    })
+}
+
+pub(crate) fn identify_lh(data: &[u8], format: &'static str) -> Option<Identification> {
+    let (decompressed_len, _) = read_header(data, 0x40).ok()?;
+    let (compressed_len, confidence) = match lh::decode(data, &mut Counter(0)) {
+        Ok(len) => (Some(len), confidence_for(data, len)),
+        Err(DecompressError::InvalidIndex(pos)) if pos >= data.len() => (None, Confidence::Low),
+        // broken trees or a match before the start, which is likely LZ40 data instead
+        Err(_) => return None,
+    };
+    Some(Identification {
+        format,
+        compressed_len,
+        decompressed_len,
+        confidence,
+    })
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::{identify, plausible_header, scan, Confidence, Identification};
+    use crate::compress;
+    use crate::test_data::{lh_sample, sample};
+
+    #[test]
+    pub fn test_identify() {
+        let data = sample(5000, 0);
+        let mut compressed = compress(&data);
+        let len = compressed.len();
+        assert_eq!(
+            identify(&compressed),
+            Some(Identification {
+                format: "nlzss11",
+                compressed_len: Some(len),
+                decompressed_len: data.len(),
+                confidence: Confidence::High,
+            })
+        );
+        assert_eq!(
+            identify(&compressed[..len - 1]).map(|i| (i.compressed_len, i.confidence)),
+            Some((None, Confidence::Low))
+        );
+        compressed.extend_from_slice(b"trailing data");
+        assert_eq!(
+            identify(&compressed).map(|i| (i.compressed_len, i.confidence)),
+            Some((Some(len), Confidence::Medium))
+        );
+        assert_eq!(
+            identify(&crate::lz10::compress(&data)).map(|i| (i.format, i.confidence)),
+            Some(("lz10", Confidence::High))
+        );
+        // both have the type byte 0x40
+        assert_eq!(
+            identify(&crate::lz40::compress(&data)).map(|i| (i.format, i.confidence)),
+            Some(("lz40", Confidence::High))
+        );
+        assert_eq!(
+            identify(&lh_sample().0).map(|i| (i.format, i.confidence)),
+            Some(("lh", Confidence::High))
+        );
+        assert_eq!(
+            identify(&crate::yay0::compress(&data)).map(|i| (i.format, i.confidence)),
+            Some(("yay0", Confidence::High))
+        );
+        assert_eq!(
+            identify(&crate::mio0::compress(&data)).map(|i| (i.format, i.confidence)),
+            Some(("mio0", Confidence::High))
+        );
+        assert_eq!(
+            identify(&crate::rle::compress(&data)).map(|i| (i.format, i.confidence)),
+            Some(("rle", Confidence::High))
+        );
+        assert_eq!(
+            identify(&crate::huffman::compress_4bit(&data)).map(|i| (i.format, i.confidence)),
+            Some(("huffman4", Confidence::High))
+        );
+        assert_eq!(identify(b"just some text"), None);
+        // backreference before the start of the data
+        assert_eq!(identify(&[0x11, 0x10, 0, 0, 0x80, 0xF0, 0x00]), None);
+    }
+
+    #[test]
+    pub fn test_scan() {
+        let data = sample(5000, 0);
+        let mut blob = vec![0xAB; 6];
+        blob.extend_from_slice(&compress(&data));
+        let second = blob.len().next_multiple_of(4) + 8;
+        blob.resize(second, 0xCD);
+        blob.extend_from_slice(&crate::lz10::compress(&data));
+        blob.extend_from_slice(b"end");
+        let found: Vec<_> = scan(&blob, 2)
+            .map(|(offset, i)| (offset, i.format))
+            .collect();
+        assert_eq!(found, [(6, "nlzss11"), (second, "lz10")]);
+
+        assert!(plausible_header(&blob[6..]));
+        assert!(plausible_header(&blob[second..]));
+        assert!(!plausible_header(b"just some text"));
+        // more than 5 bytes can decompress to
+        assert!(!plausible_header(&[0x10, 0xFF, 0xFF, 0xFF, 0x00]));
+        assert!(!plausible_header(b"Yaz0\xFF\xFF\xFF\xFF\0\0\0\0\0\0\0\0\0"));
+        assert!(!plausible_header(&[0x10, 0x01]));
+    }
+}
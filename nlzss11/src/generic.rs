@@ -0,0 +1,248 @@
+//! A configurable LZSS engine for nonstandard variants without a dedicated module.
+//!
+//! The data has no header, every group of 8 items is preceded by a byte of flags and a
+//! backreference is a single value of `distance_bits + length_bits` bits, with the distance
+//! counted from 1.
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "compress")]
+use crate::lzss::MatchSearcher;
+use crate::DecompressError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzssParams {
+    /// The window is `1 << distance_bits` bytes.
+    pub distance_bits: u8,
+    pub length_bits: u8,
+    /// Length of the shortest backreference, stored as 0.
+    pub min_match: usize,
+    /// Whether a set flag marks a literal instead of a backreference.
+    pub flag_marks_literal: bool,
+    /// Whether the flags are used from the least significant bit on.
+    pub lsb_first: bool,
+    /// Byte order of the backreferences.
+    pub little_endian: bool,
+    /// Whether the length is stored in the upper bits of a backreference.
+    pub length_high: bool,
+}
+
+impl LzssParams {
+    /// The LZ10 stream after its header.
+    pub const LZ10: LzssParams = LzssParams {
+        distance_bits: 12,
+        length_bits: 4,
+        min_match: 3,
+        flag_marks_literal: false,
+        lsb_first: false,
+        little_endian: false,
+        length_high: true,
+    };
+
+    /// Errors if backreferences aren't a whole number of bytes or longer than 4 bytes.
+    pub fn validate(&self) -> Result<(), DecompressError> {
+        let bits = self.distance_bits as usize + self.length_bits as usize;
+        if bits == 0 || !bits.is_multiple_of(8) || bits > 32 || self.distance_bits == 0 {
+            return Err(DecompressError::LibraryError("invalid LZSS parameters"));
+        }
+        Ok(())
+    }
+
+    fn code_len(&self) -> usize {
+        (self.distance_bits as usize + self.length_bits as usize) / 8
+    }
+
+    fn max_distance(&self) -> usize {
+        1 << self.distance_bits
+    }
+
+    fn max_length(&self) -> usize {
+        self.min_match + (1 << self.length_bits) - 1
+    }
+
+    fn flag(&self, index: usize) -> u8 {
+        if self.lsb_first {
+            1 << index
+        } else {
+            0x80 >> index
+        }
+    }
+
+    fn read_code(&self, buf: &[u8]) -> (usize, usize) {
+        let value = if self.little_endian {
+            buf.iter().rev().fold(0, |v, &b| v << 8 | b as u32)
+        } else {
+            buf.iter().fold(0, |v, &b| v << 8 | b as u32)
+        };
+        let (length, distance) = if self.length_high {
+            (
+                value >> self.distance_bits,
+                value & ((1 << self.distance_bits) - 1),
+            )
+        } else {
+            (
+                value & ((1 << self.length_bits) - 1),
+                value >> self.length_bits,
+            )
+        };
+        (distance as usize + 1, length as usize + self.min_match)
+    }
+
+    fn write_code(&self, distance: usize, length: usize, out_buf: &mut Vec<u8>) {
+        let distance = (distance - 1) as u32;
+        let length = (length - self.min_match) as u32;
+        let value = if self.length_high {
+            length << self.distance_bits | distance
+        } else {
+            distance << self.length_bits | length
+        };
+        let bytes = &value.to_be_bytes()[4 - self.code_len()..];
+        if self.little_endian {
+            out_buf.extend(bytes.iter().rev());
+        } else {
+            out_buf.extend_from_slice(bytes);
+        }
+    }
+}
+
+/// Decompresses until `out_size` bytes are produced or, without a size, until the data ends.
+#[cfg(feature = "decompress")]
+pub fn decompress(
+    data: &[u8],
+    params: &LzssParams,
+    out_size: Option<usize>,
+) -> Result<Vec<u8>, DecompressError> {
+    params.validate()?;
+    let code_len = params.code_len();
+    let out_size = out_size.unwrap_or(usize::MAX);
+    let mut out_buf = Vec::new();
+    let mut pos = 0;
+    'groups: while out_buf.len() < out_size && pos < data.len() {
+        let flags = data[pos];
+        pos += 1;
+        for index in 0..8 {
+            if out_buf.len() >= out_size || pos >= data.len() {
+                break 'groups;
+            }
+            let is_backref = (flags & params.flag(index) != 0) != params.flag_marks_literal;
+            if !is_backref {
+                out_buf.push(data[pos]);
+                pos += 1;
+                continue;
+            }
+            let code = data
+                .get(pos..pos + code_len)
+                .ok_or(DecompressError::InvalidIndex(pos))?;
+            let (distance, length) = params.read_code(code);
+            if distance > out_buf.len() {
+                return Err(DecompressError::InvalidIndex(pos));
+            }
+            pos += code_len;
+            let start = out_buf.len() - distance;
+            for cpy_pos in start..start + length.min(out_size - out_buf.len()) {
+                out_buf.push(out_buf[cpy_pos]);
+            }
+        }
+    }
+    if out_size != usize::MAX && out_buf.len() < out_size {
+        return Err(DecompressError::InvalidIndex(pos));
+    }
+    Ok(out_buf)
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8], params: &LzssParams) -> Result<Vec<u8>, DecompressError> {
+    params.validate()?;
+    let max_distance = params.max_distance() as u32;
+    let max_length = params.max_length() as u32;
+    let mut searcher = MatchSearcher::new();
+    let mut out_buf = Vec::with_capacity(data.len() + data.len() / 8 + 1);
+    let mut flags_pos = 0;
+    let mut flag_count = 8;
+    let mut pos = 0;
+    while pos < data.len() {
+        if flag_count == 8 {
+            flags_pos = out_buf.len();
+            out_buf.push(if params.flag_marks_literal { 0xFF } else { 0 });
+            flag_count = 0;
+        }
+        let code = searcher
+            .get_lz_code(data, pos as u32, max_distance, max_length)
+            .filter(|&(_, length)| length as usize >= params.min_match);
+        if let Some((distance, length)) = code {
+            out_buf[flags_pos] ^= params.flag(flag_count);
+            params.write_code(distance as usize, length as usize, &mut out_buf);
+            for p in pos..pos + length as usize {
+                searcher.submit_val(data, p as u32);
+            }
+            pos += length as usize;
+        } else {
+            out_buf.push(data[pos]);
+            searcher.submit_val(data, pos as u32);
+            pos += 1;
+        }
+        flag_count += 1;
+    }
+    Ok(out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress, LzssParams};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_lz10() {
+        let data = sample(5000, 0);
+        let lz10 = crate::lz10::compress(&data);
+        assert_eq!(
+            decompress(&lz10[4..], &LzssParams::LZ10, Some(data.len())).unwrap(),
+            data
+        );
+        assert_eq!(compress(&data, &LzssParams::LZ10).unwrap(), lz10[4..]);
+    }
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 0);
+        let variants = [
+            LzssParams {
+                distance_bits: 12,
+                length_bits: 4,
+                min_match: 3,
+                flag_marks_literal: true,
+                lsb_first: true,
+                little_endian: true,
+                length_high: false,
+            },
+            LzssParams {
+                distance_bits: 11,
+                length_bits: 5,
+                min_match: 2,
+                flag_marks_literal: false,
+                lsb_first: true,
+                little_endian: false,
+                length_high: true,
+            },
+            LzssParams {
+                distance_bits: 16,
+                length_bits: 8,
+                min_match: 4,
+                flag_marks_literal: true,
+                lsb_first: false,
+                little_endian: true,
+                length_high: true,
+            },
+        ];
+        for params in variants {
+            let compressed = compress(&data, &params).unwrap();
+            assert!(compressed.len() < data.len() / 2);
+            assert_eq!(decompress(&compressed, &params, None).unwrap(), data);
+        }
+        let invalid = LzssParams {
+            length_bits: 5,
+            ..LzssParams::LZ10
+        };
+        assert!(compress(&data, &invalid).is_err());
+    }
+}
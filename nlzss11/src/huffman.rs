@@ -0,0 +1,295 @@
+//! The Huffman formats (types 0x24 and 0x28) of the GBA/DS BIOS, encoding 4 or 8 bit symbols.
+//!
+//! The tree is stored as table of node pairs where every node can only point up to 64 pairs
+//! ahead, the compressed bits follow it as little endian words read from the top bit down.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use byteorder::{ByteOrder, LE};
+
+use crate::lzss::{read_header, write_header, Sink};
+use crate::DecompressError;
+
+/// How far ahead (in pairs) a node can point to its children.
+const MAX_CHILD_OFFSET: usize = 64;
+
+enum Node {
+    Leaf(u8),
+    Internal(usize, usize),
+}
+
+/// Builds a huffman tree, the root is the last node.
+fn build_tree(freqs: &[usize]) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for (symbol, &freq) in freqs.iter().enumerate() {
+        if freq > 0 {
+            heap.push(Reverse((freq, nodes.len())));
+            nodes.push(Node::Leaf(symbol as u8));
+        }
+    }
+    // the tree needs at least 2 leaves
+    for symbol in 0..2 {
+        if heap.len() < 2
+            && !nodes
+                .iter()
+                .any(|n| matches!(n, Node::Leaf(s) if *s == symbol))
+        {
+            heap.push(Reverse((0, nodes.len())));
+            nodes.push(Node::Leaf(symbol));
+        }
+    }
+    while heap.len() > 1 {
+        let Reverse((freq0, node0)) = heap.pop().unwrap();
+        let Reverse((freq1, node1)) = heap.pop().unwrap();
+        heap.push(Reverse((freq0 + freq1, nodes.len())));
+        nodes.push(Node::Internal(node0, node1));
+    }
+    nodes
+}
+
+/// Builds a tree where every internal node has a leaf child, the most frequent symbols get
+/// the shortest codes. It can always be stored, but is usually worse than a huffman tree.
+fn build_chain(freqs: &[usize]) -> Vec<Node> {
+    let mut symbols: Vec<u8> = (0..freqs.len() as u16).map(|s| s as u8).collect();
+    symbols.sort_by_key(|&s| Reverse(freqs[s as usize]));
+    let mut nodes: Vec<Node> = symbols.iter().rev().map(|&s| Node::Leaf(s)).collect();
+    let mut last = 0;
+    for leaf in 1..symbols.len() {
+        nodes.push(Node::Internal(leaf, last));
+        last = nodes.len() - 1;
+    }
+    nodes
+}
+
+/// Assigns every internal node the pair its children are stored in, `None` if some node can't
+/// reach its children.
+///
+/// Pairs are handed out depth first to keep the number of nodes waiting for their children low,
+/// unless that would make a waiting node miss its deadline.
+fn layout(nodes: &[Node]) -> Option<Vec<usize>> {
+    let root = nodes.len() - 1;
+    let mut child_pairs = vec![0; nodes.len()];
+    // (deadline, pair of the node, node)
+    let mut pending = vec![(MAX_CHILD_OFFSET, 0, root)];
+    let mut next_pair = 1;
+    while !pending.is_empty() {
+        let latest = (0..pending.len()).max_by_key(|&i| pending[i].1).unwrap();
+        let mut deadlines: Vec<usize> = pending
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != latest)
+            .map(|(_, p)| p.0)
+            .collect();
+        deadlines.sort_unstable();
+        let latest_fits = deadlines
+            .iter()
+            .enumerate()
+            .all(|(i, &deadline)| deadline > next_pair + i);
+        let index = if latest_fits {
+            latest
+        } else {
+            (0..pending.len()).min_by_key(|&i| pending[i].0).unwrap()
+        };
+        let (deadline, _, node) = pending.swap_remove(index);
+        if deadline < next_pair {
+            return None;
+        }
+        child_pairs[node] = next_pair;
+        if let Node::Internal(child0, child1) = nodes[node] {
+            for child in [child0, child1] {
+                if matches!(nodes[child], Node::Internal(..)) {
+                    pending.push((next_pair + MAX_CHILD_OFFSET, next_pair, child));
+                }
+            }
+        }
+        next_pair += 1;
+    }
+    Some(child_pairs)
+}
+
+/// Writes the tree table including the size byte and collects the code of every symbol.
+fn write_tree(nodes: &[Node], child_pairs: &[usize], codes: &mut [Vec<bool>]) -> Vec<u8> {
+    let pair_count = child_pairs.iter().max().unwrap() + 1;
+    // the bitstream after the tree has to be word aligned
+    let mut table = vec![0; (pair_count * 2).next_multiple_of(4)];
+    table[0] = (table.len() / 2 - 1) as u8;
+    // (node, byte in the table, code)
+    let mut stack = vec![(nodes.len() - 1, 1, Vec::new())];
+    while let Some((node, pos, code)) = stack.pop() {
+        match nodes[node] {
+            Node::Leaf(symbol) => {
+                table[pos] = symbol;
+                codes[symbol as usize] = code;
+            }
+            Node::Internal(child0, child1) => {
+                let pair = child_pairs[node];
+                let mut byte = (pair - pos / 2 - 1) as u8;
+                for (bit, child, flag) in [(false, child0, 0x80), (true, child1, 0x40)] {
+                    if matches!(nodes[child], Node::Leaf(_)) {
+                        byte |= flag;
+                    }
+                    let mut child_code = code.clone();
+                    child_code.push(bit);
+                    stack.push((child, pair * 2 + bit as usize, child_code));
+                }
+                table[pos] = byte;
+            }
+        }
+    }
+    table
+}
+
+fn compress_symbols(data: &[u8], symbol_bits: u8) -> Vec<u8> {
+    let symbols: Vec<u8> = if symbol_bits == 4 {
+        data.iter().flat_map(|&b| [b & 0xF, b >> 4]).collect()
+    } else {
+        data.to_vec()
+    };
+    let mut freqs = vec![0; 1 << symbol_bits];
+    for &symbol in &symbols {
+        freqs[symbol as usize] += 1;
+    }
+    let mut nodes = build_tree(&freqs);
+    let child_pairs = match layout(&nodes) {
+        Some(child_pairs) => child_pairs,
+        None => {
+            nodes = build_chain(&freqs);
+            layout(&nodes).expect("chains always fit")
+        }
+    };
+    let mut codes = vec![Vec::new(); 1 << symbol_bits];
+
+    let mut out_buf = Vec::with_capacity(data.len() + 0x200);
+    write_header(&mut out_buf, 0x20 | symbol_bits, data.len());
+    out_buf.extend_from_slice(&write_tree(&nodes, &child_pairs, &mut codes));
+
+    let mut word: u32 = 0;
+    let mut word_bits = 0;
+    for &symbol in &symbols {
+        for &bit in &codes[symbol as usize] {
+            word |= (bit as u32) << (31 - word_bits);
+            word_bits += 1;
+            if word_bits == 32 {
+                out_buf.extend_from_slice(&word.to_le_bytes());
+                word = 0;
+                word_bits = 0;
+            }
+        }
+    }
+    if word_bits != 0 {
+        out_buf.extend_from_slice(&word.to_le_bytes());
+    }
+    out_buf
+}
+
+/// Compresses `data` as 4 bit symbols (type 0x24).
+#[cfg(feature = "compress")]
+pub fn compress_4bit(data: &[u8]) -> Vec<u8> {
+    compress_symbols(data, 4)
+}
+
+/// Compresses `data` as 8 bit symbols (type 0x28).
+#[cfg(feature = "compress")]
+pub fn compress_8bit(data: &[u8]) -> Vec<u8> {
+    compress_symbols(data, 8)
+}
+
+/// Decodes `data` into `sink`, returns the length of the compressed data that was used.
+pub(crate) fn decode(data: &[u8], sink: &mut impl Sink) -> Result<usize, DecompressError> {
+    let symbol_bits = match data.first() {
+        Some(0x24) => 4,
+        Some(0x28) => 8,
+        _ => return Err(DecompressError::InvalidMagic),
+    };
+    let (out_size, header_len) = read_header(data, data[0])?;
+    let tree_len = (*data
+        .get(header_len)
+        .ok_or(DecompressError::InvalidIndex(header_len))? as usize
+        + 1)
+        * 2;
+    let tree = data
+        .get(header_len..header_len + tree_len)
+        .ok_or(DecompressError::InvalidIndex(data.len()))?;
+    let mut pos = header_len + tree_len;
+
+    // position in the tree and whether it is a leaf
+    let mut node = (1, false);
+    let mut half_byte = None;
+    let mut word = 0;
+    let mut word_bits = 0;
+    while sink.len() < out_size {
+        if word_bits == 0 {
+            word = LE::read_u32(
+                data.get(pos..pos + 4)
+                    .ok_or(DecompressError::InvalidIndex(pos))?,
+            );
+            pos += 4;
+            word_bits = 32;
+        }
+        let bit = (word >> 31) as usize;
+        word <<= 1;
+        word_bits -= 1;
+
+        let byte = tree[node.0];
+        let child = (node.0 & !1) + (byte & 0x3F) as usize * 2 + 2 + bit;
+        if child >= tree.len() {
+            return Err(DecompressError::InvalidIndex(header_len + child));
+        }
+        node = (child, byte & (0x80 >> bit) != 0);
+        if !node.1 {
+            continue;
+        }
+        let symbol = tree[node.0];
+        node = (1, false);
+        if symbol_bits == 8 {
+            sink.push(symbol);
+        } else if let Some(low) = half_byte.take() {
+            sink.push(low | (symbol & 0xF) << 4);
+        } else {
+            half_byte = Some(symbol & 0xF);
+        }
+    }
+    Ok(pos)
+}
+
+/// Decompresses both the 4 and the 8 bit variant.
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let mut out_buf = Vec::new();
+    decode(data, &mut out_buf)?;
+    Ok(out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress_4bit, compress_8bit, decompress};
+
+    #[test]
+    pub fn test_roundtrip() {
+        let text: Vec<u8> = b"this is some text with a skewed distribution of bytes"
+            .iter()
+            .copied()
+            .cycle()
+            .take(5000)
+            .collect();
+        // every byte equally often, gives a balanced tree with 256 leaves
+        let all: Vec<u8> = (0..5000u32).map(|i| (i * 7) as u8).collect();
+        for data in [text.as_slice(), &all, b"x"] {
+            for compressed in [compress_4bit(data), compress_8bit(data)] {
+                assert_eq!(decompress(&compressed).unwrap(), data);
+            }
+        }
+        assert!(compress_8bit(&text).len() < text.len() * 2 / 3);
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        // root with leaves 'a' (0) and 'b' (1), bits 0110 1...
+        let compressed = [0x28, 0x05, 0, 0, 0x01, 0xC0, b'a', b'b', 0, 0, 0, 0x68];
+        assert_eq!(decompress(&compressed).unwrap(), b"abbab");
+    }
+}
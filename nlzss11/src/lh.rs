@@ -0,0 +1,187 @@
+//! The LH format (type 0x40) of the Wii SDK, LZ77 where the literals, match lengths and distances
+//! are huffman coded.
+//!
+//! After the header come two trees, one for 9 bit symbols (literals and lengths) and one for 5
+//! bit symbols (the bit length of distances), stored as tables of nodes packed from the top bit
+//! down. The compressed bits follow, also read from the top bit of every byte down.
+//!
+//! It has the same type byte as [`crate::lz40`], so it can't be told apart by the magic, only
+//! by checking the trees and the stream like [`crate::detect`] does.
+
+use alloc::vec::Vec;
+
+use crate::lzss::{self, read_header, Sink};
+use crate::DecompressError;
+
+const MAGIC: u8 = 0x40;
+/// Symbols below 0x100 are literals, the others lengths.
+const LENGTH_BITS: u32 = 9;
+/// Symbols are the number of bits in the distance.
+const DISTANCE_BITS: u32 = 5;
+const MIN_MATCH: usize = 3;
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bits: u64,
+    bit_count: u32,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, count: u32) -> Result<u32, DecompressError> {
+        while self.bit_count < count {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or(DecompressError::InvalidIndex(self.pos))?;
+            self.bits = self.bits << 8 | byte as u64;
+            self.bit_count += 8;
+            self.pos += 1;
+        }
+        self.bit_count -= count;
+        let value = (self.bits >> self.bit_count) as u32 & ((1 << count) - 1);
+        self.bits &= (1 << self.bit_count) - 1;
+        Ok(value)
+    }
+}
+
+/// A tree of `bits` bit nodes: the lowest `bits - 2` bits point to the pair of children, the
+/// top two bits mark the first and second child as leaves. The root is node 1.
+struct Tree {
+    nodes: Vec<u16>,
+    bits: u32,
+}
+
+impl Tree {
+    /// Reads the table at the start of `data`, returns the tree and the length of the table.
+    fn read(data: &[u8], bits: u32) -> Result<(Self, usize), DecompressError> {
+        let (size, pos) = if bits > 8 {
+            let size = data.get(..2).ok_or(DecompressError::InvalidIndex(0))?;
+            (u16::from_le_bytes([size[0], size[1]]) as usize, 2)
+        } else {
+            (
+                *data.first().ok_or(DecompressError::InvalidIndex(0))? as usize,
+                1,
+            )
+        };
+        let table_len = (size + 1) * 4;
+        let table = data
+            .get(..table_len)
+            .ok_or(DecompressError::InvalidIndex(data.len()))?;
+        // node 0 isn't used
+        let mut nodes = Vec::from([0]);
+        let mut reader = BitReader {
+            data: table,
+            pos,
+            bits: 0,
+            bit_count: 0,
+        };
+        // the bits left at the end don't make a full node
+        while (table_len - reader.pos) * 8 + reader.bit_count as usize >= bits as usize {
+            nodes.push(reader.read(bits)? as u16);
+        }
+        Ok((Tree { nodes, bits }, table_len))
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, DecompressError> {
+        let offset_mask = (1 << (self.bits - 2)) - 1;
+        let mut index = 1;
+        loop {
+            let node = *self
+                .nodes
+                .get(index)
+                .ok_or(DecompressError::LibraryError("Invalid tree"))?;
+            let bit = reader.read(1)? as usize;
+            let child = (index & !1) + ((node & offset_mask) as usize + 1) * 2 + bit;
+            let child_node = *self
+                .nodes
+                .get(child)
+                .ok_or(DecompressError::LibraryError("Invalid tree"))?;
+            if node & (1 << (self.bits - 1)) >> bit != 0 {
+                return Ok(child_node);
+            }
+            index = child;
+        }
+    }
+}
+
+/// Decodes `data` into `sink`, returns the length of the compressed data that was used.
+pub(crate) fn decode(data: &[u8], sink: &mut impl Sink) -> Result<usize, DecompressError> {
+    let (out_size, mut pos) = read_header(data, MAGIC)?;
+    let (length_tree, table_len) = Tree::read(&data[pos..], LENGTH_BITS)?;
+    pos += table_len;
+    let (distance_tree, table_len) = Tree::read(&data[pos..], DISTANCE_BITS)?;
+    pos += table_len;
+
+    let mut reader = BitReader {
+        data,
+        pos,
+        bits: 0,
+        bit_count: 0,
+    };
+    while sink.len() < out_size {
+        let symbol = length_tree.decode(&mut reader)?;
+        if symbol < 0x100 {
+            sink.push(symbol as u8);
+            continue;
+        }
+        let length = (symbol as usize & 0xFF) + MIN_MATCH;
+        let distance_bits = distance_tree.decode(&mut reader)? as u32;
+        let distance = if distance_bits == 0 {
+            1
+        } else {
+            ((1 << (distance_bits - 1)) | reader.read(distance_bits - 1)? as usize) + 1
+        };
+        if distance > sink.len() {
+            return Err(DecompressError::InvalidIndex(reader.pos));
+        }
+        sink.copy(distance, length.min(out_size - sink.len()));
+    }
+    Ok(reader.pos)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let (out_size, header_len) = read_header(data, MAGIC)?;
+    let mut out_buf = Vec::with_capacity(out_size.min(lzss::max_output(data.len() - header_len)));
+    decode(data, &mut out_buf)?;
+    Ok(out_buf)
+}
+
+#[cfg(all(test, feature = "decompress"))]
+mod test {
+    use super::decompress;
+    use crate::test_data::lh_sample;
+
+    #[test]
+    pub fn test_decompress() {
+        let header = [0x40, 6, 0, 0];
+        // root with the leaves 'a' and length 4
+        let length_tree = [0x01, 0x00, 0xC0, 0x18, 0x60, 0x20, 0x00, 0x00];
+        // root with the leaves 1 and 0 distance bits
+        let distance_tree = [0x00, 0xC0, 0x40, 0x00];
+        let mut compressed = [&header[..], &length_tree, &distance_tree].concat();
+
+        // 'a', 'a', length 4 from 1 back
+        compressed.push(0b0011_0000);
+        assert_eq!(decompress(&compressed).unwrap(), b"aaaaaa");
+
+        // 'a', 'a', length 4 from 2 back
+        *compressed.last_mut().unwrap() = 0b0010_0000;
+        assert_eq!(decompress(&compressed).unwrap(), b"aaaaaa");
+
+        // length 4 from 2 back, without any data
+        *compressed.last_mut().unwrap() = 0b1000_0000;
+        assert!(decompress(&compressed).is_err());
+        // truncated
+        compressed.pop();
+        assert!(decompress(&compressed).is_err());
+    }
+
+    #[test]
+    pub fn test_decompress_nested_trees() {
+        let (compressed, decompressed) = lh_sample();
+        assert_eq!(decompress(&compressed).unwrap(), decompressed);
+        assert!(decompress(&compressed[..compressed.len() - 1]).is_err());
+    }
+}
@@ -1,15 +1,181 @@
-use byteorder::{ByteOrder, LE};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// the halves not built leave helpers shared by both unused
+#![cfg_attr(
+    not(all(feature = "compress", feature = "decompress")),
+    allow(dead_code, unused_imports)
+)]
 
-#[derive(thiserror::Error, Debug)]
+extern crate alloc;
+
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "binrw")]
+pub mod binrw;
+#[cfg(all(feature = "std", feature = "decompress"))]
+pub mod bios;
+pub mod blz;
+#[cfg(all(feature = "std", feature = "compress", feature = "decompress"))]
+pub mod brres;
+#[cfg(feature = "std")]
+pub mod bufread;
+#[cfg(feature = "decompress")]
+pub mod codec;
+#[cfg(feature = "compress")]
+mod compressor;
+pub mod corpus;
+#[cfg(feature = "compress")]
+pub mod cue;
+#[cfg(feature = "decompress")]
+pub mod detect;
+pub mod generic;
+pub mod huffman;
+pub mod lh;
+pub mod lz10;
+pub mod lz40;
+pub mod lz60;
+mod lzss;
+pub mod mio0;
+#[cfg(feature = "compress")]
+mod optimal;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod read;
+#[cfg(all(feature = "std", feature = "compress", feature = "decompress"))]
+pub mod reference;
+pub mod rle;
+#[cfg(feature = "decompress")]
+pub mod salvage;
+#[cfg(feature = "compress")]
+mod search;
+#[cfg(all(feature = "std", feature = "decompress"))]
+pub mod seekable;
+#[cfg(feature = "decompress")]
+pub mod stats;
+#[cfg(feature = "decompress")]
+mod stream;
+#[cfg(test)]
+mod test_data;
+pub mod tokens;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+#[cfg(feature = "std")]
+pub mod write;
+#[cfg(feature = "compress")]
+pub mod wszst;
+pub mod yay0;
+#[cfg(feature = "yaz0")]
+pub mod yaz0;
+
+#[cfg(feature = "compress")]
+pub use compressor::Compressor;
+#[cfg(feature = "decompress")]
+pub use stream::{Decompressor, Status};
+
+#[cfg(feature = "compress")]
+use lzss::MatchLimits;
+#[cfg(feature = "compress")]
+use search::{BinaryTree, HashChain, ShortMatches};
+
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::fmt;
+
+// implemented by hand instead of with thiserror, which needs std
+#[derive(Debug)]
 #[non_exhaustive]
 pub enum DecompressError {
-    #[error("invalid magic")]
     InvalidMagic,
-    #[error("invalid index: {0}")]
     InvalidIndex(usize),
     // TODO make better
-    #[error("other error: {0}")]
     LibraryError(&'static str),
+    AllocationFailed(TryReserveError),
+    /// The decompressed size from the header is bigger than the limit given to
+    /// [`decompress_with_limit`].
+    OutputTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    /// [`DecompressMode::Strict`] rejected the compressed data at this index.
+    NotStrict(usize),
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecompressError::InvalidMagic => write!(f, "invalid magic"),
+            DecompressError::InvalidIndex(index) => write!(f, "invalid index: {}", index),
+            DecompressError::LibraryError(message) => write!(f, "other error: {}", message),
+            DecompressError::AllocationFailed(e) => write!(f, "allocation failed: {}", e),
+            DecompressError::OutputTooLarge { size, limit } => write!(
+                f,
+                "decompressed size {} is bigger than the limit of {}",
+                size, limit
+            ),
+            DecompressError::NotStrict(index) => {
+                write!(f, "unexpected compressed data at index {}", index)
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecompressError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            DecompressError::AllocationFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TryReserveError> for DecompressError {
+    fn from(e: TryReserveError) -> Self {
+        DecompressError::AllocationFailed(e)
+    }
+}
+
+#[cfg(feature = "compress")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CompressError {
+    /// The header can't hold more than `u32::MAX` decompressed bytes.
+    InputTooLarge(usize),
+    AllocationFailed(TryReserveError),
+    /// The token at this index can't be encoded by [`tokens::encode_tokens`].
+    InvalidToken(usize),
+    /// The format with this name can only be decompressed.
+    Unsupported(&'static str),
+}
+
+#[cfg(feature = "compress")]
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressError::InputTooLarge(len) => {
+                write!(f, "input of {} bytes is too large for the header", len)
+            }
+            CompressError::AllocationFailed(e) => write!(f, "allocation failed: {}", e),
+            CompressError::InvalidToken(index) => write!(f, "invalid token at index {}", index),
+            CompressError::Unsupported(format) => write!(f, "{} can't be compressed", format),
+        }
+    }
+}
+
+#[cfg(feature = "compress")]
+impl core::error::Error for CompressError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            CompressError::AllocationFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "compress")]
+impl From<TryReserveError> for CompressError {
+    fn from(e: TryReserveError) -> Self {
+        CompressError::AllocationFailed(e)
+    }
 }
 
 struct LzssCode {
@@ -19,21 +185,21 @@ struct LzssCode {
 
 impl LzssCode {
     fn read(buf: &[u8]) -> Option<(LzssCode, usize)> {
-        let pair = u16::from_be_bytes(buf[..2].try_into().ok()?) as u32;
+        let pair = u16::from_be_bytes(buf.get(..2)?.try_into().ok()?) as u32;
         Some(match pair & 0xF000 {
             0 => {
                 // 0000LLLL LLLLDDDD DDDDDDDD
                 // L + 0x11, D + 1
                 // 255 + 17 >= length >= 17
                 let length = (pair >> 4) + 0x11;
-                let distance = (((pair & 0xF) << 8) as u32 | *buf.get(2)? as u32) + 1;
+                let distance = (((pair & 0xF) << 8) | *buf.get(2)? as u32) + 1;
                 (LzssCode { distance, length }, 3)
             }
             0x1000 => {
                 // 0001LLLL LLLLLLLL LLLLDDDD DDDDDDDD
                 // L + 0x111, D + 1
                 // 2^16 + 255 + 17 >= length >= 256 + 17
-                let ext_pair = u16::from_be_bytes(buf[2..4].try_into().ok()?) as u32;
+                let ext_pair = u16::from_be_bytes(buf.get(2..4)?.try_into().ok()?) as u32;
                 let length = ((pair & 0xFFF) << 4 | ext_pair >> 12) + 0x111;
                 let distance = (ext_pair & 0xFFF) + 1;
                 (LzssCode { distance, length }, 4)
@@ -70,124 +236,236 @@ impl LzssCode {
     }
 }
 
-#[inline(always)]
-fn get_or_oob_err(data: &[u8], pos: usize) -> Result<u8, DecompressError> {
-    data.get(pos)
-        .copied()
-        .ok_or(DecompressError::InvalidIndex(pos))
-}
+/// The nlzss11 (LZ11) variant.
+struct Lz11;
 
-pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
-    if data.len() < 4 {
-        return Err(DecompressError::LibraryError("Too short"));
+/// ASCII magic DS download play and some SDK tools put in front of the header.
+const LZ77_PREFIX: &[u8; 4] = b"LZ77";
+
+impl lzss::LzssVariant for Lz11 {
+    const MAGIC: u8 = 0x11;
+    const MAX_DISTANCE: u32 = TOTAL_BACKREF_POS;
+    const MAX_LENGTH: u32 = TOTAL_BACKREF_LEN;
+
+    fn read_code(buf: &[u8]) -> Option<(LzssCode, usize)> {
+        LzssCode::read(buf)
     }
-    if data[0] != 0x11 {
-        return Err(DecompressError::InvalidMagic);
+
+    fn write_code(code: &LzssCode, out_buf: &mut Vec<u8>) {
+        code.write(out_buf)
     }
-    let mut pos = 4;
-    let mut out_size: usize = LE::read_u24(&data[1..]) as usize;
-    if out_size == 0 {
-        if data.len() < 8 {
-            return Err(DecompressError::LibraryError("Too short"));
+
+    fn code_size(length: u32) -> usize {
+        match length {
+            0x111.. => 4,
+            0x11.. => 3,
+            _ => 2,
         }
-        out_size = LE::read_u32(&data[4..]) as usize;
     }
-    let mut out_buf = Vec::with_capacity(out_size);
 
-    let mut group_header = 0;
-    let mut remaining_chunks = 0;
-    while out_buf.len() < out_buf.capacity() {
-        // one byte indicates if the next 8 blocks are literals or backreferences
-        if remaining_chunks == 0 {
-            group_header = get_or_oob_err(data, pos)?;
-            pos += 1;
-            remaining_chunks = 8;
-        }
-        if (group_header & 0x80) == 0 {
-            out_buf.push(get_or_oob_err(data, pos)?);
-            pos += 1;
-        } else {
-            let (LzssCode { distance, length }, advance) =
-                LzssCode::read(&data[pos..]).ok_or(DecompressError::InvalidIndex(data.len()))?;
-
-            pos += advance;
-
-            let cpy_start = out_buf
-                .len()
-                .checked_sub(distance as usize)
-                .ok_or(DecompressError::InvalidIndex(0))?;
-            if distance > length {
-                // region to copy doesn't overlap the region it's copied to
-                out_buf.extend_from_within(cpy_start..cpy_start + length as usize);
-            } else {
-                for cpy_pos in cpy_start..cpy_start + length as usize {
-                    // it shouldn't be possible to end up in the default of unwrap_or
-                    out_buf.push(out_buf.get(cpy_pos).copied().unwrap_or(0));
-                }
-            }
+    /// Also skips the [`LZ77_PREFIX`], which is counted as part of the header.
+    fn read_header(data: &[u8]) -> Result<(usize, usize), DecompressError> {
+        match data.strip_prefix(LZ77_PREFIX) {
+            Some(rest) => lzss::read_header(rest, Self::MAGIC)
+                .map(|(out_size, header_len)| (out_size, LZ77_PREFIX.len() + header_len)),
+            None => lzss::read_header(data, Self::MAGIC),
         }
-
-        group_header <<= 1;
-        remaining_chunks -= 1;
     }
-    Ok(out_buf)
 }
 
-// https://github.com/PSeitz/lz4_flex/blob/c17d3b110325211f9e63c897add5fad09ddd8ef1/src/block/hashtable.rs#L16
-#[inline]
-fn make_hash(sequence: [u8; 4]) -> u32 {
-    (u32::from_ne_bytes(sequence).wrapping_mul(2654435761_u32)) >> 16
+/// Reads the decompressed size from the header of `data`, including the extended form for big
+/// sizes, without decompressing anything.
+pub fn decompressed_size(data: &[u8]) -> Result<u64, DecompressError> {
+    let (out_size, _) = <Lz11 as lzss::LzssVariant>::read_header(data)?;
+    Ok(out_size as u64)
 }
 
-const HASH_COUNT: usize = 4096 * 16; // has to be power of 2
+/// Decompresses nlzss11 data.
+///
+/// This and the other decompressors of nlzss11 data never panic, whatever `data` is, so they can
+/// be used on untrusted input. Broken data gives an error and no more memory is reserved up front
+/// than `data` could decompress to, whatever size the header claims. Use
+/// [`decompress_with_limit`] to also limit the size of valid output.
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    decompress_with_consumed(data).map(|(out_buf, _)| out_buf)
+}
 
-struct MatchSearcher {
-    search_dict: [u32; HASH_COUNT],
+/// How picky decompression is about data the compressor wouldn't have written.
+#[cfg(feature = "decompress")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DecompressMode {
+    /// Fails with [`DecompressError::NotStrict`] if anything follows the stream, the last
+    /// backreference reaches past the decompressed size or an unused flag of the last group is
+    /// set. Streams padded to an alignment are rejected as well.
+    Strict,
+    /// Stops after the decompressed size from the header and ignores everything after it, like
+    /// the decompressors of the games, which real files sometimes rely on.
+    #[default]
+    Lenient,
 }
 
-impl MatchSearcher {
-    pub fn new() -> Self {
-        MatchSearcher {
-            search_dict: [u32::MAX; HASH_COUNT],
+/// Decompresses streams stored back to back until the end of `data`. Zeros between and after
+/// them are skipped, so streams padded to an alignment work as well.
+///
+/// ```
+/// # #[cfg(feature = "compress")] {
+/// let mut data = nlzss11::compress(b"first first first");
+/// data.extend_from_slice(&nlzss11::compress(b"second second second"));
+/// let streams = nlzss11::decompress_concatenated(&data)?;
+/// assert_eq!(streams, [&b"first first first"[..], b"second second second"]);
+/// # }
+/// # Ok::<(), nlzss11::DecompressError>(())
+/// ```
+#[cfg(feature = "decompress")]
+pub fn decompress_concatenated(data: &[u8]) -> Result<Vec<Vec<u8>>, DecompressError> {
+    let mut streams = Vec::new();
+    let mut pos = 0;
+    loop {
+        // the magic is never 0
+        pos += data[pos..].iter().take_while(|&&byte| byte == 0).count();
+        if pos == data.len() {
+            return Ok(streams);
         }
+        let (decompressed, consumed) = decompress_with_consumed(&data[pos..])?;
+        streams.push(decompressed);
+        pos += consumed;
     }
-    pub fn submit_val(&mut self, data: &[u8], cur_pos: u32) {
-        let rest = &data[cur_pos as usize..];
-        if rest.len() < 4 {
-            return;
-        }
-        let hash = make_hash(rest[..4].try_into().unwrap()) % HASH_COUNT as u32;
-        self.search_dict[hash as usize] = cur_pos;
-    }
+}
 
-    pub fn get_lz_code(&self, data: &[u8], cur_pos: u32) -> Option<(u32, u32)> {
-        let rest = &data[cur_pos as usize..];
-        if rest.len() < 4 {
-            return None;
-        }
-        let hash = make_hash(rest[..4].try_into().unwrap()) % HASH_COUNT as u32;
-        let prev = self.search_dict[hash as usize];
-        if prev == u32::MAX {
-            return None;
-        }
-        let match_backref = cur_pos.wrapping_sub(prev);
-        if match_backref > TOTAL_BACKREF_POS {
-            return None;
-        }
-        let match_len = data[cur_pos as usize..]
-            .iter()
-            .zip(data[prev as usize..].iter())
-            .take_while(|&(a, b)| a == b)
-            .count();
-        if match_len < 4 {
-            return None;
-        }
-        Some((match_backref, (match_len as u32).min(TOTAL_BACKREF_LEN)))
-        // None
+/// Like [`decompress`], which is [`DecompressMode::Lenient`], but in the given `mode`.
+///
+/// ```
+/// # #[cfg(feature = "compress")] {
+/// use nlzss11::{compress, decompress_with_mode, DecompressMode};
+///
+/// let mut compressed = compress(b"strict strict strict");
+/// assert!(decompress_with_mode(&compressed, DecompressMode::Strict).is_ok());
+/// compressed.push(0);
+/// assert!(decompress_with_mode(&compressed, DecompressMode::Strict).is_err());
+/// assert!(decompress_with_mode(&compressed, DecompressMode::Lenient).is_ok());
+/// # }
+/// ```
+#[cfg(feature = "decompress")]
+pub fn decompress_with_mode(data: &[u8], mode: DecompressMode) -> Result<Vec<u8>, DecompressError> {
+    let (out_size, pos) = <Lz11 as lzss::LzssVariant>::read_header(data)?;
+    let mut out_buf = Vec::with_capacity(out_size.min(lzss::max_output(data.len() - pos)));
+    let strict = mode == DecompressMode::Strict;
+    lzss::decompress_groups::<Lz11>(data, pos, out_size, &mut out_buf, strict, |_, _| {})?;
+    Ok(out_buf)
+}
+
+/// Decompresses `data` and also returns how many bytes of it belong to the compressed stream,
+/// for streams embedded in bigger files whose end isn't stored anywhere else.
+///
+/// ```
+/// # #[cfg(feature = "compress")] {
+/// let mut file = nlzss11::compress(b"embedded embedded embedded");
+/// let end = file.len();
+/// file.extend_from_slice(b"whatever comes next");
+/// let (decompressed, consumed) = nlzss11::decompress_with_consumed(&file)?;
+/// assert_eq!(decompressed, b"embedded embedded embedded");
+/// assert_eq!(consumed, end);
+/// # }
+/// # Ok::<(), nlzss11::DecompressError>(())
+/// ```
+#[cfg(feature = "decompress")]
+pub fn decompress_with_consumed(data: &[u8]) -> Result<(Vec<u8>, usize), DecompressError> {
+    lzss::decompress_with_consumed::<Lz11>(data)
+}
+
+/// Like [`decompress`], but returns an error instead of aborting if the output can't be
+/// allocated, for services handling untrusted data.
+#[cfg(feature = "decompress")]
+pub fn try_decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let mut out_buf = Vec::new();
+    lzss::decompress_into_vec::<Lz11>(data, &mut out_buf, |out_buf, out_size| {
+        Ok(out_buf.try_reserve_exact(out_size)?)
+    })?;
+    Ok(out_buf)
+}
+
+/// Like [`decompress`], but fails with [`DecompressError::OutputTooLarge`] before allocating
+/// anything if the header declares more than `max_out` bytes, for untrusted data where a few
+/// bytes could otherwise claim gigabytes of output.
+#[cfg(feature = "decompress")]
+pub fn decompress_with_limit(data: &[u8], max_out: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut out_buf = Vec::new();
+    let (out_size, _) = <Lz11 as lzss::LzssVariant>::read_header(data)?;
+    if out_size > max_out {
+        return Err(DecompressError::OutputTooLarge {
+            size: out_size,
+            limit: max_out,
+        });
     }
+    lzss::decompress_into_vec::<Lz11>(data, &mut out_buf, |out_buf, out_size| {
+        out_buf.reserve_exact(out_size);
+        Ok(())
+    })?;
+    Ok(out_buf)
+}
+
+/// Like [`decompress`], but calls `progress` with the number of decompressed bytes so far and
+/// the decompressed size about every MiB and once at the end.
+#[cfg(feature = "decompress")]
+pub fn decompress_with_progress(
+    data: &[u8],
+    progress: impl FnMut(usize, usize),
+) -> Result<Vec<u8>, DecompressError> {
+    let mut out_buf = Vec::new();
+    lzss::decompress_into_vec_with_progress::<Lz11>(
+        data,
+        &mut out_buf,
+        |out_buf, out_size| {
+            out_buf.reserve_exact(out_size);
+            Ok(())
+        },
+        progress,
+    )?;
+    Ok(out_buf)
+}
+
+/// Like [`decompress`], but writes into `out`, which is cleared first. Reusing `out` for many
+/// inputs saves allocating the output every time.
+#[cfg(feature = "decompress")]
+pub fn decompress_into(data: &[u8], out: &mut Vec<u8>) -> Result<(), DecompressError> {
+    out.clear();
+    lzss::decompress_into_vec::<Lz11>(data, out, |out, out_size| {
+        out.reserve_exact(out_size);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Decompresses `data` into the start of `out` without allocating and returns the decompressed
+/// size. Fails if `out` is smaller than the decompressed size from the header.
+#[cfg(feature = "decompress")]
+pub fn decompress_to_slice(data: &[u8], out: &mut [u8]) -> Result<usize, DecompressError> {
+    lzss::decompress_to_slice::<Lz11>(data, out)
+}
+
+/// Decompresses the groups of a stream without the header, for containers that store the
+/// decompressed size themselves. The counterpart of [`compress_raw`].
+#[cfg(feature = "decompress")]
+pub fn decompress_raw(data: &[u8], out_size: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut out_buf = Vec::with_capacity(out_size.min(lzss::max_output(data.len())));
+    lzss::decompress_groups::<Lz11>(data, 0, out_size, &mut out_buf, false, |_, _| {})?;
+    Ok(out_buf)
+}
+
+/// Decompresses data in any of the formats of [`codec::builtin`], recognized like
+/// [`detect::identify`] does, and returns the name of the format.
+#[doc(alias = "decompress_auto")]
+#[cfg(feature = "decompress")]
+pub fn decompress_any(data: &[u8]) -> Result<(&'static str, Vec<u8>), DecompressError> {
+    let (codec, _) = codec::best_match(codec::builtin().iter().copied(), data)
+        .ok_or(DecompressError::InvalidMagic)?;
+    Ok((codec.name(), codec.decompress(data)?))
 }
 
 const TOTAL_BACKREF_LEN: u32 = 0x10110;
+/// Shortest backreference the codes can hold.
+const MIN_LENGTH: u32 = 3;
 const TOTAL_BACKREF_POS: u32 = 0xFFF;
 
 #[cfg(feature = "zlib")]
@@ -199,72 +477,458 @@ pub fn compress(data: &[u8]) -> Vec<u8> {
     out
 }
 
-#[cfg(not(feature = "zlib"))]
+#[cfg(all(feature = "compress", not(feature = "zlib")))]
 pub fn compress(data: &[u8]) -> Vec<u8> {
-    let mut searcher = MatchSearcher::new();
-
-    let mut out_buf: Vec<u8> = Vec::with_capacity(data.len());
-    // write magic
-    out_buf.push(0x11);
-    // very big archives
-    // little endian data length
-    if data.len() < 0xFFFFFF {
-        let mut len_buf = [0; 3];
-        LE::write_u24(&mut len_buf, data.len() as u32);
-        out_buf.extend_from_slice(&len_buf);
-    } else if data.len() < 0xFFFFFFFF {
-        out_buf.extend([0, 0, 0]);
-        out_buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
-    }
-
-    let mut group_header_pos = out_buf.len();
-    out_buf.push(0);
-    let mut group_header = 0;
-    let mut group_header_count = 0;
-
-    // go through the input in 3 byte chunks
-    let mut pos: usize = 0;
-
-    while pos < data.len() {
-        if group_header_count == 8 {
-            out_buf[group_header_pos] = group_header;
-            group_header_pos = out_buf.len();
-            out_buf.push(0);
-            group_header = 0;
-            group_header_count = 0;
-        }
-        if let Some((backref_dist, backref_len)) = searcher.get_lz_code(data, pos as u32) {
-            group_header <<= 1;
-            group_header += 1;
-            group_header_count += 1;
-            LzssCode {
-                length: backref_len,
-                distance: backref_dist,
-            }
-            .write(&mut out_buf);
-            for p in pos..(pos + backref_len as usize) {
-                searcher.submit_val(data, p as u32);
-            }
-            pos += backref_len as usize;
-            // TODO: submit vals?
-        } else {
-            group_header <<= 1;
-            group_header_count += 1;
-            out_buf.push(data[pos]);
-            searcher.submit_val(data, pos as u32);
-            pos += 1;
+    compress_canonical(data)
+}
+
+/// The biggest size the compressed data of `len` bytes can have, with every byte stored as a
+/// literal. [`compress_into`] doesn't reallocate if `out` has at least this capacity.
+#[cfg(feature = "compress")]
+pub fn compress_bound(len: usize) -> usize {
+    lzss::compress_bound(len)
+}
+
+/// Like [`compress`], but writes into `out`, which is cleared first. Reusing `out` for many
+/// inputs saves allocating the output every time.
+#[cfg(feature = "compress")]
+pub fn compress_into(data: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    #[cfg(feature = "zlib")]
+    compress_with_zlib_into(data, out, 7);
+    #[cfg(not(feature = "zlib"))]
+    {
+        *out = lzss::compress_into_vec::<Lz11>(data, core::mem::take(out));
+    }
+}
+
+/// Number of entries the scratch memory of [`compress_with_scratch`] needs, 256 KiB.
+#[cfg(feature = "compress")]
+pub const SCRATCH_LEN: usize = lzss::HASH_COUNT;
+
+/// Like [`compress_canonical`], but keeps the hash table in `scratch` instead of allocating it
+/// and writes into `out`, which is cleared first. Nothing is allocated at all if `out` has a
+/// capacity of at least [`compress_bound`], for targets where every allocation counts.
+///
+/// Panics if `scratch` has less than [`SCRATCH_LEN`] entries.
+///
+/// ```
+/// let mut scratch = vec![0; nlzss11::SCRATCH_LEN];
+/// let mut out = Vec::with_capacity(nlzss11::compress_bound(1000));
+/// nlzss11::compress_with_scratch(&[7; 1000], &mut out, &mut scratch);
+/// assert_eq!(out, nlzss11::compress_canonical(&[7; 1000]));
+/// ```
+#[cfg(feature = "compress")]
+pub fn compress_with_scratch(data: &[u8], out: &mut Vec<u8>, scratch: &mut [u32]) {
+    out.clear();
+    let mut searcher = lzss::MatchSearcher::with_table(scratch);
+    *out = lzss::compress_with_searcher::<Lz11, _>(data, core::mem::take(out), &mut searcher);
+}
+
+/// How much effort [`compress_with_level`] puts into finding matches, from 0 to 9 like in zlib.
+///
+/// 0 stores the data as literals without looking for matches, for data that is known not to
+/// compress, like textures that are already compressed. Up to 3 only the first position of
+/// every match goes into the hash table, 1 also skips more and more positions while no match is
+/// found, which is a lot faster on incompressible data. 4 to 6 are the same as
+/// [`compress_canonical`], 7 and 8 search hash chains 16 and 128 positions deep and skip a
+/// match if the next position has a longer one (lazy matching) and 9 finds the longest match
+/// with binary trees and picks the literals and matches with the smallest total size (optimal
+/// parsing), which is a lot slower. From 7 on, matches of 3 bytes are used as well.
+#[cfg(feature = "compress")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Level(u8);
+
+#[cfg(feature = "compress")]
+impl Level {
+    pub const STORE: Level = Level(0);
+    pub const FAST: Level = Level(1);
+    pub const DEFAULT: Level = Level(6);
+    pub const BEST: Level = Level(9);
+
+    /// Levels above 9 are treated as 9.
+    pub fn new(level: u8) -> Self {
+        Level(level.min(9))
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+#[cfg(feature = "compress")]
+impl Default for Level {
+    fn default() -> Self {
+        Level::DEFAULT
+    }
+}
+
+/// Compresses with the built-in compressor at the given level, also with the `zlib` feature.
+#[cfg(feature = "compress")]
+pub fn compress_with_level(data: &[u8], level: Level) -> Vec<u8> {
+    compress_with_options(data, &CompressionOptions::new().level(level))
+}
+
+#[cfg(feature = "compress")]
+fn compress_level(data: &[u8], out_buf: Vec<u8>, options: &CompressionOptions) -> Vec<u8> {
+    let limits = options.limits();
+    let short = limits.min_length < lzss::MIN_MATCH;
+    let hash_bits = options.hash_bits;
+    match options.level.get() {
+        0 => lzss::compress_stored::<Lz11>(data, out_buf),
+        level @ 1..=6 => lzss::compress_greedy::<Lz11, _>(
+            data,
+            out_buf,
+            &mut ShortMatches::new(lzss::MatchSearcher::with_bits(hash_bits), short),
+            limits,
+            options
+                .insert_step
+                .unwrap_or(if level >= 4 { 1 } else { 0 }),
+            false,
+            level <= 1,
+        ),
+        level @ 7..=8 => {
+            let depth = if level == 7 { 16 } else { 128 };
+            let mut chain = ShortMatches::new(HashChain::new(depth, hash_bits), short);
+            let insert_step = options.insert_step.unwrap_or(1);
+            lzss::compress_greedy::<Lz11, _>(
+                data,
+                out_buf,
+                &mut chain,
+                limits,
+                insert_step,
+                true,
+                false,
+            )
+        }
+        _ => {
+            let mut tree = ShortMatches::new(BinaryTree::new(hash_bits), short);
+            optimal::compress::<Lz11, _>(data, out_buf, &mut tree, limits)
+        }
+    }
+}
+
+/// Settings for [`compress_with_options`], starting from the same output as
+/// [`compress_canonical`].
+///
+/// ```
+/// # #[cfg(feature = "decompress")] {
+/// use nlzss11::{compress_with_options, decompress, CompressionOptions, Level};
+///
+/// let data = b"options options options";
+/// let options = CompressionOptions::new()
+///     .level(Level::BEST)
+///     .max_match(0x110)
+///     .align(true);
+/// let compressed = compress_with_options(data, &options);
+/// assert_eq!(compressed.len() % 4, 0);
+/// assert_eq!(decompress(&compressed).unwrap(), data);
+/// # }
+/// ```
+#[cfg(feature = "compress")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionOptions {
+    level: Level,
+    chain_depth: Option<usize>,
+    min_match: Option<u32>,
+    max_match: u32,
+    window_size: u32,
+    cue: Option<cue::CueMode>,
+    /// The compressed size is padded to a multiple of this with `fill`.
+    alignment: usize,
+    fill: u8,
+    extended_header: bool,
+    lz77_prefix: bool,
+    vram_safe: bool,
+    hash_bits: u32,
+    insert_step: Option<usize>,
+}
+
+#[cfg(feature = "compress")]
+impl Default for CompressionOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "compress")]
+impl CompressionOptions {
+    pub fn new() -> Self {
+        CompressionOptions {
+            level: Level::DEFAULT,
+            chain_depth: None,
+            min_match: None,
+            max_match: TOTAL_BACKREF_LEN,
+            window_size: TOTAL_BACKREF_POS,
+            cue: None,
+            alignment: 1,
+            fill: 0,
+            extended_header: false,
+            lz77_prefix: false,
+            vram_safe: false,
+            hash_bits: lzss::HASH_BITS,
+            insert_step: None,
+        }
+    }
+
+    /// How much effort goes into finding matches, see [`Level`].
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Uses a hash chain match finder and lazy matching instead of the one of the level,
+    /// following the chains for `depth` positions. Deeper searches find better matches and are
+    /// slower.
+    pub fn chain_depth(mut self, depth: usize) -> Self {
+        self.chain_depth = Some(depth);
+        self
+    }
+
+    /// Length of the shortest match that is used, from 3 to 0x10110. The default is 3 from
+    /// level 7 on and 4 below, matches of 3 bytes need another hash table and only save a byte.
+    pub fn min_match(mut self, length: u32) -> Self {
+        self.min_match = Some(length.clamp(MIN_LENGTH, TOTAL_BACKREF_LEN));
+        self
+    }
+
+    /// Length of the longest match that is used, from 4 to 0x10110 (the default). Up to 0x110
+    /// leaves out the 4 byte backreferences.
+    pub fn max_match(mut self, length: u32) -> Self {
+        self.max_match = length.clamp(lzss::MIN_MATCH, TOTAL_BACKREF_LEN);
+        self
+    }
+
+    /// How far back matches can reach, from 1 to 0xFFF (the default), for decoders that keep
+    /// less of the output around.
+    pub fn window_size(mut self, size: u32) -> Self {
+        self.window_size = size.clamp(1, TOTAL_BACKREF_POS);
+        self
+    }
+
+    /// Chooses the same matches as CUE's `lzx` tool in `mode` for output that is identical
+    /// byte for byte, see [`cue`]. The other settings except for the alignment are ignored.
+    pub fn cue(mut self, mode: cue::CueMode) -> Self {
+        self.cue = Some(mode);
+        self
+    }
+
+    /// Pads the compressed stream with zeros to a multiple of 4 bytes, some games need that.
+    /// Shorthand for an [`CompressionOptions::alignment`] of 4 or 1.
+    pub fn align(mut self, align: bool) -> Self {
+        self.alignment = if align { 4 } else { 1 };
+        self
+    }
+
+    /// Pads the compressed stream to a multiple of `alignment` bytes, like the entries of many
+    /// archives. The default of 1 doesn't pad, 0 is treated as 1.
+    pub fn alignment(mut self, alignment: usize) -> Self {
+        self.alignment = alignment.max(1);
+        self
+    }
+
+    /// The byte the padding of [`CompressionOptions::alignment`] is filled with, 0 by default.
+    pub fn fill(mut self, fill: u8) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Always writes the 8 byte header with a size of 0 followed by the 32 bit size, which is
+    /// otherwise only used from 16 MiB on, for tools that expect it for every file.
+    pub fn extended_header(mut self, extended_header: bool) -> Self {
+        self.extended_header = extended_header;
+        self
+    }
+
+    /// Puts the ASCII magic "LZ77" in front of the header like DS download play and some SDK
+    /// tools, the decompressors skip it.
+    pub fn lz77_prefix(mut self, lz77_prefix: bool) -> Self {
+        self.lz77_prefix = lz77_prefix;
+        self
+    }
+
+    /// Never copies from the previous byte, so the data can be decompressed straight to the
+    /// VRAM of the GBA and DS, which is only written 2 bytes at a time.
+    pub fn vram_safe(mut self, vram_safe: bool) -> Self {
+        self.vram_safe = vram_safe;
+        self
+    }
+
+    /// Size of the hash tables of the match finders as a power of 2, from 8 to 22 entries. The
+    /// default of 16 takes 256 KiB, smaller tables need less memory and miss more matches,
+    /// bigger ones help the hash chains and trees of level 7 and up on large inputs. With
+    /// [`CompressionOptions::cue`] it is ignored.
+    pub fn hash_bits(mut self, bits: u32) -> Self {
+        self.hash_bits = bits.clamp(*lzss::HASH_BITS_RANGE.start(), *lzss::HASH_BITS_RANGE.end());
+        self
+    }
+
+    /// Inserts only every `step`th position inside a match into the hash tables, 0 inserts none
+    /// but the first. Up to level 3 the default is 0, from level 4 on 1, and longer steps trade
+    /// ratio for speed on data with many long matches. Level 0 doesn't have a hash table and
+    /// level 9 always inserts every position.
+    pub fn insert_step(mut self, step: usize) -> Self {
+        self.insert_step = Some(step);
+        self
+    }
+
+    fn limits(&self) -> MatchLimits {
+        MatchLimits {
+            min_length: self
+                .min_match
+                .unwrap_or(if self.level.get() >= 7 {
+                    MIN_LENGTH
+                } else {
+                    lzss::MIN_MATCH
+                })
+                .min(self.max_match),
+            max_length: self.max_match,
+            max_distance: self.window_size,
+            vram_safe: self.vram_safe,
+        }
+    }
+}
+
+#[cfg(all(feature = "compress", feature = "decompress"))]
+impl CompressionOptions {
+    /// Guesses the alignment `compressed` was compressed with, `None` if it isn't nlzss11. The
+    /// other settings can't be recovered and are left at their defaults.
+    pub fn detect(compressed: &[u8]) -> Option<Self> {
+        let identification = detect::identify(compressed)?;
+        if identification.format != codec::DynCodec::name(&codec::Nlzss11) {
+            return None;
         }
+        // a stream that ends aligned can't be told apart
+        let align = identification.compressed_len.is_some_and(|len| {
+            len % 4 != 0
+                && compressed.len() == len.next_multiple_of(4)
+                && compressed[len..].iter().all(|&b| b == 0)
+        });
+        Some(CompressionOptions::new().align(align))
     }
-    if group_header_count != 0 {
-        group_header <<= 8 - group_header_count;
-        out_buf[group_header_pos] = group_header;
+}
+
+#[cfg(feature = "compress")]
+pub fn compress_with_options(data: &[u8], options: &CompressionOptions) -> Vec<u8> {
+    let out_buf = Vec::with_capacity(data.len());
+    let limits = options.limits();
+    let mut out_buf = match (options.cue, options.chain_depth) {
+        (Some(mode), _) => cue::compress_lz11(data, mode),
+        (None, Some(depth)) => lzss::compress_greedy::<Lz11, _>(
+            data,
+            out_buf,
+            &mut ShortMatches::new(
+                HashChain::new(depth, options.hash_bits),
+                limits.min_length < lzss::MIN_MATCH,
+            ),
+            limits,
+            options.insert_step.unwrap_or(1),
+            true,
+            false,
+        ),
+        (None, None) => compress_level(data, out_buf, options),
+    };
+    // bigger data has the extended header anyway
+    if options.extended_header && data.len() < 0xFFFFFF {
+        let len = (data.len() as u32).to_le_bytes();
+        out_buf.splice(1..4, [0, 0, 0, len[0], len[1], len[2], len[3]]);
+    }
+    if options.lz77_prefix {
+        out_buf.splice(0..0, *LZ77_PREFIX);
     }
+    out_buf.resize(
+        out_buf.len().next_multiple_of(options.alignment),
+        options.fill,
+    );
     out_buf
 }
 
-#[cfg(test)]
+/// Compresses with the canonical configuration, the same as [`compress`] without the `zlib`
+/// feature.
+///
+/// Its output doesn't depend on features and only changes with a new major version, so it can
+/// be used where compressed files have to be reproducible, like content addressed storage.
+///
+/// Panics if `data` is longer than `u32::MAX` bytes, which the header can't hold,
+/// [`try_compress`] returns an error instead.
+#[cfg(feature = "compress")]
+pub fn compress_canonical(data: &[u8]) -> Vec<u8> {
+    lzss::compress::<Lz11>(data)
+}
+
+/// Like [`compress_canonical`], but without the header holding the magic and the decompressed
+/// size, for containers that store the size themselves.
+#[cfg(feature = "compress")]
+pub fn compress_raw(data: &[u8]) -> Vec<u8> {
+    let mut out_buf = Vec::with_capacity(data.len());
+    let mut writer = lzss::GroupWriter::<Lz11>::headerless(&mut out_buf);
+    lzss::encode_greedy(
+        data,
+        0,
+        &mut writer,
+        &mut lzss::MatchSearcher::new(),
+        MatchLimits::of::<Lz11>(),
+        1,
+        false,
+        false,
+    );
+    writer.finish();
+    out_buf
+}
+
+/// Like [`compress_canonical`], but calls `progress` with the number of compressed input bytes
+/// so far and the length of `data` about every MiB and once at the end.
+#[cfg(feature = "compress")]
+pub fn compress_with_progress(data: &[u8], mut progress: impl FnMut(usize, usize)) -> Vec<u8> {
+    let mut out_buf = Vec::with_capacity(data.len());
+    let mut writer = lzss::GroupWriter::<Lz11>::new(&mut out_buf, data.len());
+    let mut searcher = lzss::MatchSearcher::new();
+    let mut pos = 0;
+    loop {
+        pos = lzss::encode_greedy_until(
+            data,
+            pos,
+            (pos + lzss::PROGRESS_INTERVAL).min(data.len()),
+            &mut writer,
+            &mut searcher,
+            MatchLimits::of::<Lz11>(),
+            1,
+            false,
+            false,
+        );
+        progress(pos, data.len());
+        if pos == data.len() {
+            break;
+        }
+    }
+    writer.finish();
+    out_buf
+}
+
+/// Like [`compress_canonical`], but returns an error instead of panicking if `data` is too long
+/// for the header or aborting if the output can't be allocated. All memory is reserved up front,
+/// so this fails before doing any work.
+#[cfg(feature = "compress")]
+pub fn try_compress(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    if data.len() as u64 > lzss::MAX_HEADER_LEN {
+        return Err(CompressError::InputTooLarge(data.len()));
+    }
+    let mut out_buf = Vec::new();
+    out_buf.try_reserve_exact(lzss::compress_bound(data.len()))?;
+    Ok(lzss::compress_into_vec::<Lz11>(data, out_buf))
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
 mod test {
-    use super::LzssCode;
+    use super::{
+        compress, compress_bound, compress_canonical, compress_into, compress_raw,
+        compress_with_level, compress_with_options, compress_with_progress, compress_with_scratch,
+        cue, decompress, decompress_any, decompress_concatenated, decompress_into, decompress_raw,
+        decompress_to_slice, decompress_with_consumed, decompress_with_limit, decompress_with_mode,
+        decompress_with_progress, decompressed_size, tokens, try_compress, try_decompress,
+        CompressionOptions, DecompressError, DecompressMode, Level, LzssCode, SCRATCH_LEN,
+    };
+    use crate::test_data::sample;
 
     #[test]
     pub fn test_roundtrip() {
@@ -289,4 +953,642 @@ mod test {
             }
         }
     }
+
+    #[test]
+    pub fn test_decompress_any() {
+        let data = b"any any any any any";
+        let (format, decompressed) = decompress_any(&crate::lz10::compress(data)).unwrap();
+        assert_eq!(format, "lz10");
+        assert_eq!(decompressed, data);
+        assert!(decompress_any(b"plain").is_err());
+    }
+
+    /// FNV-1a, to keep the expected outputs short.
+    fn hash(data: &[u8]) -> u64 {
+        data.iter().fold(0xcbf29ce484222325, |hash, &b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    /// Inputs covering literals, all code sizes, the window size and the extended header.
+    fn golden_corpus() -> Vec<(&'static str, Vec<u8>)> {
+        let mut state = 0x2545F491u32;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        let text = b"The Legend of Zelda: Skyward Sword ".repeat(300);
+        vec![
+            ("empty", Vec::new()),
+            ("byte", vec![0x42]),
+            ("zeros", vec![0; 0x20000]),
+            ("text", text),
+            ("random", (0..5000).map(|_| random() as u8).collect()),
+            (
+                "small alphabet",
+                (0..70000).map(|_| b"abcd"[random() as usize % 4]).collect(),
+            ),
+            (
+                "far matches",
+                (0..300000u32)
+                    .map(|i| (i % 4093) as u8 ^ (i / 5000) as u8)
+                    .collect(),
+            ),
+            ("big", (0..0x1000010u32).map(|i| (i >> 10) as u8).collect()),
+        ]
+    }
+
+    #[test]
+    pub fn test_canonical_output() {
+        assert_eq!(
+            compress_canonical(b"abcabcabcabc"),
+            [0x11, 0x0C, 0x00, 0x00, 0x10, b'a', b'b', b'c', 0x80, 0x02]
+        );
+        let hashes: Vec<_> = golden_corpus()
+            .iter()
+            .map(|(name, data)| {
+                let compressed = compress_canonical(data);
+                if !data.is_empty() {
+                    assert_eq!(&decompress(&compressed).unwrap(), data, "{}", name);
+                }
+                (*name, hash(&compressed))
+            })
+            .collect();
+        assert_eq!(
+            hashes,
+            [
+                ("empty", 0xa2fb9f567cd3251c),
+                ("byte", 0xd16401209817e91d),
+                ("zeros", 0x70bc150d5e1e52f0),
+                ("text", 0x4b06110995862621),
+                ("random", 0xdb2d2cb54d014784),
+                ("small alphabet", 0xdd80b7570ce2eb34),
+                ("far matches", 0x558e61127b88491b),
+                ("big", 0x28d6aeff24e97ba7),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_extended_header() {
+        let options = CompressionOptions::new().extended_header(true);
+        for data in [&b""[..], b"a", b"extended extended extended"] {
+            let compressed = compress_with_options(data, &options);
+            assert_eq!(compressed[..4], [0x11, 0, 0, 0]);
+            assert_eq!(compressed[4..8], (data.len() as u32).to_le_bytes());
+            assert_eq!(compressed[8..], compress_canonical(data)[4..]);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+            assert_eq!(decompressed_size(&compressed).unwrap(), data.len() as u64);
+        }
+    }
+
+    #[test]
+    pub fn test_lz77_prefix() {
+        let data = b"prefixed prefixed prefixed";
+        for options in [
+            CompressionOptions::new(),
+            CompressionOptions::new().extended_header(true),
+        ] {
+            let compressed = compress_with_options(data, &options.lz77_prefix(true));
+            assert_eq!(compressed[..4], *b"LZ77");
+            assert_eq!(compressed[4..], compress_with_options(data, &options));
+            assert_eq!(decompress(&compressed).unwrap(), data);
+            assert_eq!(decompressed_size(&compressed).unwrap(), data.len() as u64);
+            assert_eq!(
+                decompress_with_consumed(&compressed).unwrap().1,
+                compressed.len()
+            );
+            assert_eq!(decompress_any(&compressed).unwrap().0, "nlzss11");
+        }
+        assert!(matches!(
+            decompress(b"LZ77\x10\x04\x00\x00abcd"),
+            Err(DecompressError::InvalidMagic)
+        ));
+    }
+
+    #[test]
+    pub fn test_align() {
+        let options = CompressionOptions::new().align(true);
+        for len in [5, 6, 7, 8, 100] {
+            let data = vec![3; len];
+            let compressed = compress_with_options(&data, &options);
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+        // 4 bytes header, 1 group header and 2 literals
+        let compressed = compress_with_options(b"ab", &options);
+        assert_eq!(compressed.len(), 8);
+        assert_eq!(CompressionOptions::detect(&compressed), Some(options));
+        let compressed = compress_with_options(b"ab", &CompressionOptions::default());
+        assert_eq!(
+            CompressionOptions::detect(&compressed),
+            Some(CompressionOptions::default())
+        );
+        let options = CompressionOptions::new().alignment(0x20).fill(0xFF);
+        for len in [0, 1, 100, 1000] {
+            let data = vec![3; len];
+            let compressed = compress_with_options(&data, &options);
+            let unpadded = compress_canonical(&data);
+            assert_eq!(compressed.len(), unpadded.len().next_multiple_of(0x20));
+            assert_eq!(compressed[..unpadded.len()], unpadded);
+            assert!(compressed[unpadded.len()..]
+                .iter()
+                .all(|&byte| byte == 0xFF));
+        }
+        assert_eq!(
+            CompressionOptions::new().alignment(0),
+            CompressionOptions::new()
+        );
+    }
+
+    #[test]
+    pub fn test_try() {
+        for data in [&b""[..], b"a", b"try try try try", &[0x55; 1000]] {
+            let compressed = try_compress(data).unwrap();
+            assert_eq!(compressed, compress_canonical(data));
+            // the reserved memory was enough
+            assert_eq!(compressed.capacity(), compress_bound(data.len()));
+            if !data.is_empty() {
+                assert_eq!(try_decompress(&compressed).unwrap(), data);
+            }
+        }
+        // claims a decompressed size of 4 GiB
+        assert!(try_decompress(&[0x11, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+    }
+
+    #[test]
+    pub fn test_compress_into() {
+        let mut out = Vec::new();
+        compress_into(&[7; 5000], &mut out);
+        assert_eq!(out, super::compress(&[7; 5000]));
+        let ptr = out.as_ptr();
+        compress_into(b"shorter", &mut out);
+        assert_eq!(out, super::compress(b"shorter"));
+        // the buffer was big enough to be reused
+        assert_eq!(out.as_ptr(), ptr);
+    }
+
+    #[test]
+    pub fn test_compress_with_scratch() {
+        let data = sample(50000, 0);
+        // leftovers from an earlier input don't matter
+        let mut scratch = vec![123; SCRATCH_LEN + 10];
+        let mut out = Vec::with_capacity(compress_bound(data.len()));
+        let ptr = out.as_ptr();
+        for data in [&data[..], b"", b"scratch scratch"] {
+            compress_with_scratch(data, &mut out, &mut scratch);
+            assert_eq!(out, compress_canonical(data));
+            assert_eq!(out.as_ptr(), ptr);
+        }
+    }
+
+    #[test]
+    pub fn test_compress_bound() {
+        let mut state = 0x12345678u32;
+        let random: Vec<u8> = (0..10000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        for len in [0, 1, 8, 9, 10000] {
+            let mut out = Vec::with_capacity(compress_bound(len));
+            let ptr = out.as_ptr();
+            compress_into(&random[..len], &mut out);
+            assert!(out.len() <= compress_bound(len));
+            assert_eq!(out.as_ptr(), ptr, "reallocated for {} bytes", len);
+        }
+    }
+
+    #[test]
+    pub fn test_decompress_into() {
+        let mut out = Vec::new();
+        decompress_into(&super::compress(&[7; 5000]), &mut out).unwrap();
+        assert_eq!(out, [7; 5000]);
+        let ptr = out.as_ptr();
+        decompress_into(&super::compress(b"shorter"), &mut out).unwrap();
+        assert_eq!(out, b"shorter");
+        assert_eq!(out.as_ptr(), ptr);
+        assert!(decompress_into(b"invalid", &mut out).is_err());
+    }
+
+    #[test]
+    pub fn test_decompress_to_slice() {
+        let data = b"slice slice slice slice slice";
+        let compressed = super::compress(data);
+        let mut out = [0; 40];
+        assert_eq!(
+            decompress_to_slice(&compressed, &mut out).unwrap(),
+            data.len()
+        );
+        assert_eq!(out[..data.len()], data[..]);
+        assert!(decompress_to_slice(&compressed, &mut out[..data.len() - 1]).is_err());
+        let data = vec![9; 100000];
+        let mut out = vec![0; data.len()];
+        decompress_to_slice(&super::compress(&data), &mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    pub fn test_levels() {
+        let text = b"The Legend of Zelda: Skyward Sword, The Legend of Zelda: Twilight Princess. "
+            .repeat(50);
+        let mut state = 0x2545F491u32;
+        let small_alphabet: Vec<u8> = (0..20000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                b"abcd"[state as usize % 4]
+            })
+            .collect();
+        // skipped ahead in the random part, still has to find the text after it
+        let random_then_text: Vec<u8> = (0..20000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .chain(text.iter().copied())
+            .collect();
+        for data in [&b""[..], b"a", &text, &small_alphabet, &random_then_text] {
+            for level in 0..=9 {
+                let compressed = compress_with_level(data, Level::new(level));
+                if !data.is_empty() {
+                    assert_eq!(decompress(&compressed).unwrap(), data, "{}", level);
+                }
+            }
+            let stored = compress_with_level(data, Level::STORE);
+            assert_eq!(stored.len(), 4 + data.len() + data.len().div_ceil(8).max(1));
+            if !data.is_empty() {
+                assert_eq!(decompress(&stored).unwrap(), data);
+            }
+            let sizes: Vec<_> = [Level::FAST, Level::DEFAULT, Level::BEST]
+                .into_iter()
+                .map(|level| {
+                    let compressed = compress_with_level(data, level);
+                    if !data.is_empty() {
+                        assert_eq!(decompress(&compressed).unwrap(), data, "{:?}", level);
+                    }
+                    compressed.len()
+                })
+                .collect();
+            assert!(sizes[0] >= sizes[1] && sizes[1] >= sizes[2], "{:?}", sizes);
+        }
+        assert_eq!(
+            compress_with_level(&text, Level::DEFAULT),
+            compress_canonical(&text)
+        );
+        assert_eq!(Level::new(20), Level::BEST);
+    }
+
+    #[test]
+    pub fn test_chain_depth() {
+        let data = b"abcd----abcdefgh----abcdefghijkl----abcdefgh".repeat(20);
+        let sizes: Vec<_> = [1, 4, 64]
+            .into_iter()
+            .map(|depth| {
+                let options = CompressionOptions::new().chain_depth(depth);
+                let compressed = compress_with_options(&data, &options);
+                assert_eq!(decompress(&compressed).unwrap(), data);
+                compressed.len()
+            })
+            .collect();
+        assert!(sizes[0] > sizes[1] && sizes[1] >= sizes[2], "{:?}", sizes);
+    }
+
+    #[test]
+    pub fn test_decompressed_size() {
+        assert_eq!(decompressed_size(&compress(&[1; 100])).unwrap(), 100);
+        assert_eq!(
+            decompressed_size(&[0x11, 0, 0, 0, 0x78, 0x56, 0x34, 0x12]).unwrap(),
+            0x12345678
+        );
+        assert!(matches!(
+            decompressed_size(&[0x10, 5, 0, 0]),
+            Err(DecompressError::InvalidMagic)
+        ));
+        assert!(decompressed_size(&[0x11, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    pub fn test_no_panic() {
+        let data = b"The Legend of Zelda: Skyward Sword, The Legend of Zelda: Twilight Princess. "
+            .repeat(100);
+        let compressed = compress_with_level(&data, Level::BEST);
+        let mut state = 0x9E3779B9u32;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize
+        };
+        let mut out = vec![0; data.len()];
+        for _ in 0..2000 {
+            let mut broken = compressed.clone();
+            for _ in 0..random() % 4 + 1 {
+                let pos = random() % broken.len();
+                broken[pos] = random() as u8;
+            }
+            broken.truncate(random() % broken.len() + 1);
+            let _ = decompress(&broken);
+            let _ = decompress_with_mode(&broken, DecompressMode::Strict);
+            let _ = decompress_to_slice(&broken, &mut out);
+            let _ = decompress_raw(broken.get(4..).unwrap_or_default(), data.len());
+            let _ = tokens::parse_tokens(&broken);
+            let mut decompressor = crate::Decompressor::new();
+            let _ = decompressor.push(&broken, &mut Vec::new());
+        }
+        // 4 GiB claimed by a few bytes
+        let mut out = Vec::new();
+        let bomb = b"\x11\x00\x00\x00\xFF\xFF\xFF\xFF\x00abcdefgh";
+        assert!(decompress_into(bomb, &mut out).is_err());
+        assert!(out.capacity() < 1 << 20, "{}", out.capacity());
+        assert!(try_decompress(bomb).is_err());
+    }
+
+    #[test]
+    pub fn test_consumed() {
+        let data = b"consumed, consumed and consumed again".repeat(20);
+        for level in [Level::STORE, Level::FAST, Level::BEST] {
+            let options = CompressionOptions::new().level(level);
+            let compressed = compress_with_options(&data, &options);
+            let mut embedded = compressed.clone();
+            embedded.extend_from_slice(&compress(b"the next stream"));
+            let (decompressed, consumed) = decompress_with_consumed(&embedded).unwrap();
+            assert_eq!(decompressed, data);
+            assert_eq!(consumed, compressed.len(), "{:?}", level);
+            // the padding doesn't belong to the stream
+            let aligned = compress_with_options(&data, &options.align(true));
+            assert_eq!(decompress_with_consumed(&aligned).unwrap().1, consumed);
+        }
+    }
+
+    #[test]
+    pub fn test_concatenated() {
+        let files: [&[u8]; 4] = [b"first first first", &[0; 5000], b"abc", b"last last"];
+        let mut data = Vec::new();
+        for file in files {
+            data.extend_from_slice(&compress(file));
+        }
+        assert_eq!(decompress_concatenated(&data).unwrap(), files);
+        let mut aligned = Vec::new();
+        for file in files {
+            let options = CompressionOptions::new().align(true);
+            aligned.extend_from_slice(&compress_with_options(file, &options));
+            aligned.resize(aligned.len().next_multiple_of(0x20), 0);
+        }
+        assert_eq!(decompress_concatenated(&aligned).unwrap(), files);
+        assert!(decompress_concatenated(&[]).unwrap().is_empty());
+        assert!(decompress_concatenated(&data[..data.len() - 1]).is_err());
+        data.push(0x10);
+        assert!(decompress_concatenated(&data).is_err());
+    }
+
+    #[test]
+    pub fn test_decompress_mode() {
+        let strict = |data: &[u8]| decompress_with_mode(data, DecompressMode::Strict);
+        let lenient = |data: &[u8]| decompress_with_mode(data, DecompressMode::Lenient);
+        let data = b"The Legend of Zelda: Skyward Sword, The Legend of Zelda: Twilight Princess. "
+            .repeat(50);
+        for level in [Level::STORE, Level::FAST, Level::DEFAULT, Level::BEST] {
+            let compressed = compress_with_level(&data, level);
+            assert_eq!(strict(&compressed).unwrap(), data, "{:?}", level);
+            assert_eq!(lenient(&compressed).unwrap(), data, "{:?}", level);
+            assert!(strict(&compressed[..compressed.len() - 1]).is_err());
+            assert!(lenient(&compressed[..compressed.len() - 1]).is_err());
+        }
+        let mut padded = compress(b"abc");
+        padded.push(0);
+        assert!(matches!(
+            strict(&padded),
+            Err(DecompressError::NotStrict(8))
+        ));
+        assert_eq!(lenient(&padded).unwrap(), b"abc");
+        // the flag of the 4th entry of the only group
+        let mut unused_flag = compress(b"abc");
+        unused_flag[4] |= 0x10;
+        assert!(matches!(
+            strict(&unused_flag),
+            Err(DecompressError::NotStrict(4))
+        ));
+        assert_eq!(lenient(&unused_flag).unwrap(), b"abc");
+        let tokens = [
+            tokens::Token::Literal(1),
+            tokens::Token::Match {
+                distance: 1,
+                length: 10,
+            },
+        ];
+        let overlong = tokens::encode_tokens(&tokens, 5).unwrap();
+        assert!(matches!(
+            strict(&overlong),
+            Err(DecompressError::NotStrict(6))
+        ));
+        assert_eq!(lenient(&overlong).unwrap(), [1; 5]);
+    }
+
+    #[test]
+    pub fn test_raw() {
+        for data in [&b""[..], b"raw", &[7; 10000], b"raw raw raw raw raw raw"] {
+            let compressed = compress_canonical(data);
+            let raw = compress_raw(data);
+            assert!(compressed.ends_with(&raw));
+            assert_eq!(compressed.len() - raw.len(), 4);
+            assert_eq!(decompress_raw(&raw, data.len()).unwrap(), data);
+        }
+        let raw = compress_raw(b"raw raw raw raw raw raw");
+        assert!(decompress_raw(&raw[..raw.len() - 1], 23).is_err());
+    }
+
+    #[test]
+    pub fn test_progress() {
+        let data = sample(3_000_000, 0);
+        let mut calls = Vec::new();
+        let compressed = compress_with_progress(&data, |done, total| calls.push((done, total)));
+        assert_eq!(compressed, compress_canonical(&data));
+        assert!(calls.len() >= 3);
+        assert!(calls.windows(2).all(|calls| calls[0].0 < calls[1].0));
+        assert!(calls.iter().all(|&(_, total)| total == data.len()));
+        assert_eq!(calls.last(), Some(&(data.len(), data.len())));
+
+        calls.clear();
+        let decompressed =
+            decompress_with_progress(&compressed, |done, total| calls.push((done, total))).unwrap();
+        assert_eq!(decompressed, data);
+        assert!(calls.len() >= 3);
+        assert!(calls.windows(2).all(|calls| calls[0].0 < calls[1].0));
+        assert_eq!(calls.last(), Some(&(data.len(), data.len())));
+
+        calls.clear();
+        compress_with_progress(&[], |done, total| calls.push((done, total)));
+        assert_eq!(calls, [(0, 0)]);
+    }
+
+    #[test]
+    pub fn test_limit() {
+        let compressed = compress(&[7; 1000]);
+        assert_eq!(decompress_with_limit(&compressed, 1000).unwrap(), [7; 1000]);
+        assert!(matches!(
+            decompress_with_limit(&compressed, 999),
+            Err(DecompressError::OutputTooLarge {
+                size: 1000,
+                limit: 999
+            })
+        ));
+        // 4 GiB declared in 12 bytes
+        let bomb = [0x11, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xF0, 0, 0xFF, 0xF0];
+        assert!(matches!(
+            decompress_with_limit(&bomb, 1 << 20),
+            Err(DecompressError::OutputTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    pub fn test_header_sizes() {
+        for (len, header) in [
+            (0xFFFFFE, &[0x11, 0xFE, 0xFF, 0xFF][..]),
+            (0xFFFFFF, &[0x11, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0][..]),
+        ] {
+            let data = vec![0; len];
+            let compressed = try_compress(&data).unwrap();
+            assert_eq!(&compressed[..header.len()], header);
+            assert_eq!(decompressed_size(&compressed).unwrap(), len as u64);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    /// The backreferences in an nlzss11 stream.
+    fn codes(compressed: &[u8]) -> Vec<LzssCode> {
+        let (out_size, mut pos) = super::lzss::read_header(compressed, 0x11).unwrap();
+        let mut codes = Vec::new();
+        let mut out_len = 0;
+        while out_len < out_size {
+            let group_header = compressed[pos];
+            pos += 1;
+            for index in 0..8 {
+                if out_len >= out_size {
+                    break;
+                }
+                if group_header & (0x80 >> index) == 0 {
+                    pos += 1;
+                    out_len += 1;
+                } else {
+                    let (code, advance) = LzssCode::read(&compressed[pos..]).unwrap();
+                    pos += advance;
+                    out_len += code.length as usize;
+                    codes.push(code);
+                }
+            }
+        }
+        codes
+    }
+
+    #[test]
+    pub fn test_options() {
+        let data: Vec<u8> = (0..20000u32)
+            .flat_map(|i| format!("{} {} ", i % 1000, i % 7).into_bytes())
+            .chain([0; 5000])
+            .collect();
+        for level in [Level::FAST, Level::DEFAULT, Level::new(7), Level::BEST] {
+            let options = CompressionOptions::new()
+                .level(level)
+                .min_match(6)
+                .max_match(0x40)
+                .window_size(0x100);
+            let compressed = compress_with_options(&data, &options);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+            let codes = codes(&compressed);
+            assert!(!codes.is_empty());
+            for code in codes {
+                assert!((6..=0x40).contains(&code.length), "{:?}", level);
+                assert!(code.distance <= 0x100, "{:?}", level);
+            }
+        }
+        let mode = cue::CueMode {
+            vram: true,
+            optimal: true,
+        };
+        let options = CompressionOptions::new().cue(mode).max_match(0x10);
+        assert_eq!(
+            compress_with_options(&data[..3000], &options),
+            cue::compress_lz11(&data[..3000], mode)
+        );
+        for level in [Level::FAST, Level::DEFAULT, Level::new(7), Level::BEST] {
+            let options = CompressionOptions::new().level(level).vram_safe(true);
+            let compressed = compress_with_options(&data, &options);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+            let codes = codes(&compressed);
+            assert!(codes.iter().all(|code| code.distance >= 2), "{:?}", level);
+            // the zeros at the end still compress
+            assert!(codes.iter().any(|code| code.length > 0x1000), "{:?}", level);
+        }
+        // matches of 3 bytes from level 7 on or when asked for
+        for (options, short) in [
+            (CompressionOptions::new(), false),
+            (CompressionOptions::new().min_match(3), true),
+            (CompressionOptions::new().level(Level::new(7)), true),
+            (CompressionOptions::new().level(Level::BEST), true),
+            (
+                CompressionOptions::new().level(Level::BEST).min_match(4),
+                false,
+            ),
+        ] {
+            let compressed = compress_with_options(&data, &options);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+            let has_short = codes(&compressed).iter().any(|code| code.length == 3);
+            assert_eq!(has_short, short, "{:?}", options);
+        }
+        // a longer minimum than maximum uses the maximum
+        let options = CompressionOptions::new().min_match(0x20).max_match(0x10);
+        let compressed = compress_with_options(&data, &options);
+        assert!(codes(&compressed).iter().all(|code| code.length == 0x10));
+        // the default hash size gives the same output, out of range sizes are clamped
+        for level in [Level::FAST, Level::DEFAULT, Level::new(7), Level::BEST] {
+            let options = CompressionOptions::new().level(level);
+            let expected = compress_with_options(&data, &options);
+            let compressed = compress_with_options(&data, &options.hash_bits(16));
+            assert_eq!(compressed, expected, "{:?}", level);
+            for bits in [0, 8, 12, 22, 40] {
+                let compressed = compress_with_options(&data, &options.hash_bits(bits));
+                assert_eq!(
+                    decompress(&compressed).unwrap(),
+                    data,
+                    "{:?} {}",
+                    level,
+                    bits
+                );
+            }
+        }
+        // every position inside matches is inserted by default from level 4 on
+        for level in [Level::FAST, Level::DEFAULT, Level::new(7), Level::new(8)] {
+            let options = CompressionOptions::new().level(level);
+            let expected = compress_with_options(&data, &options);
+            let step = if level < Level::new(4) { 0 } else { 1 };
+            let compressed = compress_with_options(&data, &options.insert_step(step));
+            assert_eq!(compressed, expected, "{:?}", level);
+            for step in [0, 2, 7] {
+                let compressed = compress_with_options(&data, &options.insert_step(step));
+                assert_eq!(
+                    decompress(&compressed).unwrap(),
+                    data,
+                    "{:?} {}",
+                    level,
+                    step
+                );
+            }
+        }
+        let options = CompressionOptions::new().chain_depth(4).insert_step(3);
+        assert_eq!(
+            decompress(&compress_with_options(&data, &options)).unwrap(),
+            data
+        );
+        let small = compress_with_options(&data, &CompressionOptions::new().hash_bits(8));
+        assert!(small.len() > compress_with_options(&data, &CompressionOptions::new()).len());
+    }
 }
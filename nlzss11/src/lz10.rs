@@ -0,0 +1,91 @@
+//! The LZ10 (type 0x10) format used by the GBA/DS BIOS, the predecessor of nlzss11 with
+//! backreferences of at most 18 bytes.
+
+use alloc::vec::Vec;
+
+use crate::lzss::{self, LzssVariant};
+use crate::{DecompressError, LzssCode};
+
+pub(crate) struct Lz10;
+
+impl LzssVariant for Lz10 {
+    const MAGIC: u8 = 0x10;
+    const MAX_DISTANCE: u32 = 0x1000;
+    const MAX_LENGTH: u32 = 18;
+
+    fn read_code(buf: &[u8]) -> Option<(LzssCode, usize)> {
+        // LLLLDDDD DDDDDDDD
+        // L + 3, D + 1
+        let pair = u16::from_be_bytes(buf.get(..2)?.try_into().ok()?) as u32;
+        let length = (pair >> 12) + 3;
+        let distance = (pair & 0xFFF) + 1;
+        Some((LzssCode { distance, length }, 2))
+    }
+
+    fn write_code(code: &LzssCode, out_buf: &mut Vec<u8>) {
+        let adj_dist = code.distance - 1;
+        out_buf.push((((code.length - 3) << 4) + (adj_dist >> 8)) as u8);
+        out_buf.push((adj_dist & 0xFF) as u8);
+    }
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    lzss::compress::<Lz10>(data)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    lzss::decompress_with_consumed::<Lz10>(data).map(|(out_buf, _)| out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 300);
+        let compressed = compress(&data);
+        assert_eq!(compressed[0], 0x10);
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_longest_match() {
+        // a literal and a backreference of length 18, the longest there is
+        assert_eq!(
+            compress(&[b'a'; 19]),
+            [0x10, 19, 0, 0, 0x40, b'a', 0xF0, 0x00]
+        );
+        // so 19 bytes need a second one
+        let compressed = compress(&[b'a'; 20]);
+        assert!(compressed.len() > 8);
+        assert_eq!(decompress(&compressed).unwrap(), [b'a'; 20]);
+    }
+
+    #[test]
+    pub fn test_farthest_match() {
+        // "abcd" only repeats 0x1000 bytes later, the farthest LZ10 can reach
+        let mut data = b"abcd".to_vec();
+        data.resize(0x1000, b'x');
+        data.extend_from_slice(b"abcd");
+        let compressed = compress(&data);
+        assert!(compressed.ends_with(&[0x1F, 0xFF]));
+        assert_eq!(decompress(&compressed).unwrap(), data);
+        // one byte farther it has to be literals
+        data.insert(4, b'x');
+        let compressed = compress(&data);
+        assert!(compressed.ends_with(b"abcd"));
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        // "abc" followed by a backreference of length 6 and distance 3
+        let compressed = [0x10, 0x09, 0x00, 0x00, 0x10, b'a', b'b', b'c', 0x30, 0x02];
+        assert_eq!(decompress(&compressed).unwrap(), b"abcabcabc");
+    }
+}
@@ -0,0 +1,111 @@
+//! The LZ40 (type 0x40) format from DSi-era games. It uses the group header flags starting
+//! from the least significant bit and stores backreferences little endian.
+
+use alloc::vec::Vec;
+
+use crate::lzss::{self, LzssVariant};
+use crate::{DecompressError, LzssCode};
+
+pub(crate) struct Lz40;
+
+impl LzssVariant for Lz40 {
+    const MAGIC: u8 = 0x40;
+    const MAX_DISTANCE: u32 = 0xFFF;
+    const MAX_LENGTH: u32 = 0xFFFF + 0x110;
+    const LSB_FIRST: bool = true;
+
+    fn read_code(buf: &[u8]) -> Option<(LzssCode, usize)> {
+        read_lz40_code(buf)
+    }
+
+    fn write_code(code: &LzssCode, out_buf: &mut Vec<u8>) {
+        write_lz40_code(code, out_buf)
+    }
+}
+
+/// Shared with LZ60, which only has a different magic.
+pub(crate) fn read_lz40_code(buf: &[u8]) -> Option<(LzssCode, usize)> {
+    // DDDDLLLL DDDDDDDD, little endian
+    let pair = u16::from_le_bytes(buf.get(..2)?.try_into().ok()?) as u32;
+    let distance = pair >> 4;
+    Some(match pair & 0xF {
+        0 => {
+            // L = next byte + 0x10
+            let length = *buf.get(2)? as u32 + 0x10;
+            (LzssCode { distance, length }, 3)
+        }
+        1 => {
+            // L = next 2 bytes + 0x110
+            let length = u16::from_le_bytes(buf.get(2..4)?.try_into().ok()?) as u32 + 0x110;
+            (LzssCode { distance, length }, 4)
+        }
+        length => (LzssCode { distance, length }, 2),
+    })
+}
+
+pub(crate) fn write_lz40_code(code: &LzssCode, out_buf: &mut Vec<u8>) {
+    let (nibble, rest) = if code.length >= 0x110 {
+        (1, Some(code.length - 0x110))
+    } else if code.length >= 0x10 {
+        (0, Some(code.length - 0x10))
+    } else {
+        (code.length, None)
+    };
+    out_buf.extend_from_slice(&((code.distance << 4 | nibble) as u16).to_le_bytes());
+    match rest {
+        Some(rest) if nibble == 1 => out_buf.extend_from_slice(&(rest as u16).to_le_bytes()),
+        Some(rest) => out_buf.push(rest as u8),
+        None => {}
+    }
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    lzss::compress::<Lz40>(data)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    lzss::decompress_with_consumed::<Lz40>(data).map(|(out_buf, _)| out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 70000);
+        let compressed = compress(&data);
+        assert_eq!(compressed[0], 0x40);
+        assert!(compressed.len() < data.len() / 10);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_lengths() {
+        // a literal and one backreference, which takes 2, 3 or 4 bytes depending on the length
+        for (length, code_len) in [
+            (0xF, 2),
+            (0x10, 3),
+            (0x10F, 3),
+            (0x110, 4),
+            (0xFFFF + 0x110, 4),
+        ] {
+            let data = vec![b'a'; length + 1];
+            let compressed = compress(&data);
+            assert_eq!(compressed.len(), 4 + 1 + 1 + code_len, "{:#x}", length);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        // "ab", a backreference of length 20 and distance 2, then "c"
+        let compressed = [
+            0x40, 0x17, 0x00, 0x00, 0x04, b'a', b'b', 0x20, 0x00, 0x04, b'c',
+        ];
+        assert_eq!(decompress(&compressed).unwrap(), b"abababababababababababc");
+    }
+}
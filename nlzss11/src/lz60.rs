@@ -0,0 +1,50 @@
+//! The LZ60 (type 0x60) format used by some DSi system software, LZ40 with a different magic.
+
+use alloc::vec::Vec;
+
+use crate::lz40::{read_lz40_code, write_lz40_code};
+use crate::lzss::{self, LzssVariant};
+use crate::{DecompressError, LzssCode};
+
+pub(crate) struct Lz60;
+
+impl LzssVariant for Lz60 {
+    const MAGIC: u8 = 0x60;
+    const MAX_DISTANCE: u32 = 0xFFF;
+    const MAX_LENGTH: u32 = 0xFFFF + 0x110;
+    const LSB_FIRST: bool = true;
+
+    fn read_code(buf: &[u8]) -> Option<(LzssCode, usize)> {
+        read_lz40_code(buf)
+    }
+
+    fn write_code(code: &LzssCode, out_buf: &mut Vec<u8>) {
+        write_lz40_code(code, out_buf)
+    }
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    lzss::compress::<Lz60>(data)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    lzss::decompress_with_consumed::<Lz60>(data).map(|(out_buf, _)| out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(5000, 300);
+        let compressed = compress(&data);
+        assert_eq!(compressed[0], 0x60);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+        // same stream as LZ40 apart from the magic
+        assert_eq!(compressed[1..], crate::lz40::compress(&data)[1..]);
+    }
+}
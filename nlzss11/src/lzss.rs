@@ -0,0 +1,871 @@
+//! The parts shared by the LZSS variants: header, group headers, match finder and the
+//! (de)compression loops. The variants only differ in how backreferences are encoded.
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::ops::DerefMut;
+
+use byteorder::{ByteOrder, LE};
+
+use crate::{DecompressError, LzssCode};
+
+pub(crate) trait LzssVariant {
+    /// First byte of the header.
+    const MAGIC: u8;
+    const MAX_DISTANCE: u32;
+    const MAX_LENGTH: u32;
+    /// Whether the flags of a group header are used from the least significant bit on.
+    const LSB_FIRST: bool = false;
+    /// Whether a set flag marks a literal instead of a backreference.
+    const FLAG_MARKS_LITERAL: bool = false;
+
+    fn read_code(buf: &[u8]) -> Option<(LzssCode, usize)>;
+
+    fn write_code(code: &LzssCode, out_buf: &mut Vec<u8>);
+
+    /// Bytes [`LzssVariant::write_code`] writes for a backreference of `length`.
+    fn code_size(length: u32) -> usize {
+        let mut buf = Vec::with_capacity(4);
+        Self::write_code(
+            &LzssCode {
+                distance: 1,
+                length,
+            },
+            &mut buf,
+        );
+        buf.len()
+    }
+
+    /// Returns the decompressed size and the length of the header.
+    fn read_header(data: &[u8]) -> Result<(usize, usize), DecompressError> {
+        read_header(data, Self::MAGIC)
+    }
+
+    fn write_header(out_buf: &mut Vec<u8>, len: usize) {
+        write_header(out_buf, Self::MAGIC, len)
+    }
+
+    #[inline(always)]
+    fn is_backref(group_header: u8, index: u8) -> bool {
+        (group_header & Self::flag(index) != 0) != Self::FLAG_MARKS_LITERAL
+    }
+
+    #[inline(always)]
+    fn flag(index: u8) -> u8 {
+        if Self::LSB_FIRST {
+            1 << index
+        } else {
+            0x80 >> index
+        }
+    }
+}
+
+/// Where the decoded data goes, so detection can walk the data without producing output.
+pub(crate) trait Sink {
+    fn len(&self) -> usize;
+
+    fn push(&mut self, byte: u8);
+
+    /// Copies `length` bytes from `distance` bytes back, which has to be within the output.
+    fn copy(&mut self, distance: usize, length: usize);
+}
+
+impl Sink for Vec<u8> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn push(&mut self, byte: u8) {
+        Vec::push(self, byte)
+    }
+
+    fn copy(&mut self, distance: usize, length: usize) {
+        let start = self.len() - distance;
+        for pos in start..start + length {
+            Vec::push(self, self[pos]);
+        }
+    }
+}
+
+/// Only counts the output.
+pub(crate) struct Counter(pub usize);
+
+impl Sink for Counter {
+    fn len(&self) -> usize {
+        self.0
+    }
+
+    fn push(&mut self, _byte: u8) {
+        self.0 += 1;
+    }
+
+    fn copy(&mut self, _distance: usize, length: usize) {
+        self.0 += length;
+    }
+}
+
+#[inline(always)]
+fn get_or_oob_err(data: &[u8], pos: usize) -> Result<u8, DecompressError> {
+    data.get(pos)
+        .copied()
+        .ok_or(DecompressError::InvalidIndex(pos))
+}
+
+/// Reads the magic and size, returns the decompressed size and the length of the header.
+pub(crate) fn read_header(data: &[u8], magic: u8) -> Result<(usize, usize), DecompressError> {
+    if data.len() < 4 {
+        return Err(DecompressError::LibraryError("Too short"));
+    }
+    if data[0] != magic {
+        return Err(DecompressError::InvalidMagic);
+    }
+    let out_size = LE::read_u24(&data[1..]) as usize;
+    if out_size != 0 {
+        return Ok((out_size, 4));
+    }
+    if data.len() < 8 {
+        return Err(DecompressError::LibraryError("Too short"));
+    }
+    Ok((LE::read_u32(&data[4..]) as usize, 8))
+}
+
+/// Biggest decompressed size the extended header can hold.
+pub(crate) const MAX_HEADER_LEN: u64 = u32::MAX as u64;
+
+/// Panics if `len` is bigger than [`MAX_HEADER_LEN`].
+pub(crate) fn write_header(out_buf: &mut Vec<u8>, magic: u8, len: usize) {
+    assert!(
+        len as u64 <= MAX_HEADER_LEN,
+        "{} bytes don't fit into the header",
+        len
+    );
+    out_buf.push(magic);
+    // very big archives
+    // little endian data length
+    if len < 0xFFFFFF {
+        let mut len_buf = [0; 3];
+        LE::write_u24(&mut len_buf, len as u32);
+        out_buf.extend_from_slice(&len_buf);
+    } else {
+        out_buf.extend([0, 0, 0]);
+        out_buf.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+}
+
+/// How often the functions taking a progress callback call it, in bytes.
+#[cfg(any(feature = "compress", feature = "decompress"))]
+pub(crate) const PROGRESS_INTERVAL: usize = 1 << 20;
+
+/// Decompresses `data` and also returns how many bytes of it belong to the compressed stream.
+#[cfg(feature = "decompress")]
+pub(crate) fn decompress_with_consumed<V: LzssVariant>(
+    data: &[u8],
+) -> Result<(Vec<u8>, usize), DecompressError> {
+    let mut out_buf = Vec::new();
+    let consumed = decompress_into_vec::<V>(data, &mut out_buf, |out_buf, out_size| {
+        out_buf.reserve_exact(out_size);
+        Ok(())
+    })?;
+    Ok((out_buf, consumed))
+}
+
+/// Decompresses `data` into the empty `out_buf` and returns how many bytes of `data` belong to
+/// the compressed stream. `reserve` gets called with the size the output can reach before
+/// decoding, the decompressed size unless [`max_output`] is smaller, `out_buf` doesn't grow past
+/// the capacity it reserves.
+#[cfg(feature = "decompress")]
+pub(crate) fn decompress_into_vec<V: LzssVariant>(
+    data: &[u8],
+    out_buf: &mut Vec<u8>,
+    reserve: impl FnOnce(&mut Vec<u8>, usize) -> Result<(), DecompressError>,
+) -> Result<usize, DecompressError> {
+    decompress_into_vec_with_progress::<V>(data, out_buf, reserve, |_, _| {})
+}
+
+/// [`decompress_into_vec`], which also calls `progress` with the decompressed bytes so far and
+/// the decompressed size about every [`PROGRESS_INTERVAL`] bytes and at the end.
+#[cfg(feature = "decompress")]
+pub(crate) fn decompress_into_vec_with_progress<V: LzssVariant>(
+    data: &[u8],
+    out_buf: &mut Vec<u8>,
+    reserve: impl FnOnce(&mut Vec<u8>, usize) -> Result<(), DecompressError>,
+    progress: impl FnMut(usize, usize),
+) -> Result<usize, DecompressError> {
+    let (out_size, pos) = V::read_header(data)?;
+    reserve(out_buf, out_size.min(max_output(data.len() - pos)))?;
+    decompress_groups::<V>(data, pos, out_size, out_buf, false, progress)
+}
+
+/// The most output a byte of compressed data can produce, a group of 8 backreferences of
+/// 0x10110 bytes takes up 33 bytes.
+#[cfg(feature = "decompress")]
+const MAX_EXPANSION: usize = 0x4000;
+
+/// The most output `len` bytes of groups can decompress to, so a header declaring gigabytes in
+/// a few bytes of untrusted data doesn't make the decompressors allocate all of it up front.
+#[cfg(feature = "decompress")]
+pub(crate) fn max_output(len: usize) -> usize {
+    len.saturating_mul(MAX_EXPANSION)
+}
+
+/// Decodes the groups starting at `pos` in `data` until `out_buf` holds `out_size` bytes,
+/// returns the position after the last one.
+///
+/// With `strict` set, fails with [`DecompressError::NotStrict`] if the last backreference is
+/// longer than the rest of the output, an unused flag of the last group is set or `data`
+/// doesn't end after the last group.
+#[cfg(feature = "decompress")]
+pub(crate) fn decompress_groups<V: LzssVariant>(
+    data: &[u8],
+    mut pos: usize,
+    out_size: usize,
+    out_buf: &mut Vec<u8>,
+    strict: bool,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<usize, DecompressError> {
+    let mut group_header_pos = pos;
+    let mut group_header = 0;
+    let mut remaining_chunks = 0;
+    let mut next_progress = PROGRESS_INTERVAL;
+    while out_buf.len() < out_size {
+        // one byte indicates if the next 8 blocks are literals or backreferences
+        if remaining_chunks == 0 {
+            if out_buf.len() >= next_progress {
+                progress(out_buf.len(), out_size);
+                next_progress = out_buf.len() + PROGRESS_INTERVAL;
+            }
+            group_header_pos = pos;
+            group_header = get_or_oob_err(data, pos)?;
+            pos += 1;
+            remaining_chunks = 8;
+        }
+        if !V::is_backref(group_header, 8 - remaining_chunks) {
+            out_buf.push(get_or_oob_err(data, pos)?);
+            pos += 1;
+        } else {
+            let (LzssCode { distance, length }, advance) =
+                V::read_code(&data[pos..]).ok_or(DecompressError::InvalidIndex(data.len()))?;
+
+            if strict && length as usize > out_size - out_buf.len() {
+                return Err(DecompressError::NotStrict(pos));
+            }
+            pos += advance;
+
+            let cpy_start = out_buf
+                .len()
+                .checked_sub(distance as usize)
+                .filter(|_| distance != 0)
+                .ok_or(DecompressError::InvalidIndex(0))?;
+            // the last backreference might be longer than the remaining output
+            let length = (length as usize).min(out_size - out_buf.len());
+            if distance as usize > length {
+                // region to copy doesn't overlap the region it's copied to
+                out_buf.extend_from_within(cpy_start..cpy_start + length);
+            } else {
+                for cpy_pos in cpy_start..cpy_start + length {
+                    // it shouldn't be possible to end up in the default of unwrap_or
+                    out_buf.push(out_buf.get(cpy_pos).copied().unwrap_or(0));
+                }
+            }
+        }
+
+        remaining_chunks -= 1;
+    }
+    if strict {
+        let unused_set = (8 - remaining_chunks..8).any(|index| V::is_backref(group_header, index));
+        if remaining_chunks != 0 && unused_set {
+            return Err(DecompressError::NotStrict(group_header_pos));
+        }
+        if pos != data.len() {
+            return Err(DecompressError::NotStrict(pos));
+        }
+    }
+    progress(out_size, out_size);
+    Ok(pos)
+}
+
+/// Decompresses `data` into the start of `out` without allocating, returns the decompressed
+/// size.
+#[cfg(feature = "decompress")]
+pub(crate) fn decompress_to_slice<V: LzssVariant>(
+    data: &[u8],
+    out: &mut [u8],
+) -> Result<usize, DecompressError> {
+    let (out_size, mut pos) = V::read_header(data)?;
+    let out = out
+        .get_mut(..out_size)
+        .ok_or(DecompressError::LibraryError("Output buffer too small"))?;
+
+    let mut out_pos = 0;
+    let mut group_header = 0;
+    let mut remaining_chunks = 0;
+    while out_pos < out_size {
+        // one byte indicates if the next 8 blocks are literals or backreferences
+        if remaining_chunks == 0 {
+            group_header = get_or_oob_err(data, pos)?;
+            pos += 1;
+            remaining_chunks = 8;
+        }
+        if !V::is_backref(group_header, 8 - remaining_chunks) {
+            out[out_pos] = get_or_oob_err(data, pos)?;
+            out_pos += 1;
+            pos += 1;
+        } else {
+            let (LzssCode { distance, length }, advance) =
+                V::read_code(&data[pos..]).ok_or(DecompressError::InvalidIndex(data.len()))?;
+
+            pos += advance;
+
+            let cpy_start = out_pos
+                .checked_sub(distance as usize)
+                .filter(|_| distance != 0)
+                .ok_or(DecompressError::InvalidIndex(0))?;
+            // the last backreference might be longer than the remaining output
+            let length = (length as usize).min(out_size - out_pos);
+            if distance as usize > length {
+                out.copy_within(cpy_start..cpy_start + length, out_pos);
+            } else {
+                for cpy_pos in cpy_start..cpy_start + length {
+                    out[out_pos + cpy_pos - cpy_start] = out[cpy_pos];
+                }
+            }
+            out_pos += length;
+        }
+
+        remaining_chunks -= 1;
+    }
+    Ok(out_size)
+}
+
+// https://github.com/PSeitz/lz4_flex/blob/c17d3b110325/src/block/hashtable.rs#L16
+/// Hash of `sequence` with `bits` bits, from 1 to 32.
+#[cfg(feature = "compress")]
+#[inline]
+pub(crate) fn make_hash(sequence: [u8; 4], bits: u32) -> usize {
+    ((u32::from_ne_bytes(sequence).wrapping_mul(2654435761_u32)) >> (32 - bits)) as usize
+}
+
+/// How many bytes at the start of `a` and `b` are the same.
+///
+/// Compares 16 bytes at a time with SSE2, which every x86_64 cpu has, and 8 bytes at a time
+/// everywhere else.
+#[cfg(feature = "compress")]
+#[inline]
+pub(crate) fn match_length(a: &[u8], b: &[u8]) -> usize {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[..len], &b[..len]);
+    let mut pos = 0;
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    while pos + 16 <= len {
+        use core::arch::x86_64::{__m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8};
+        // SAFETY: both slices have at least 16 bytes from `pos` on, sse2 is enabled and
+        // `_mm_loadu_si128` doesn't need any alignment
+        let equal = unsafe {
+            let a = _mm_loadu_si128(a.as_ptr().add(pos).cast::<__m128i>());
+            let b = _mm_loadu_si128(b.as_ptr().add(pos).cast::<__m128i>());
+            _mm_movemask_epi8(_mm_cmpeq_epi8(a, b)) as u32
+        };
+        if equal != 0xFFFF {
+            return pos + equal.trailing_ones() as usize;
+        }
+        pos += 16;
+    }
+    while pos + 8 <= len {
+        let diff = u64::from_le_bytes(a[pos..pos + 8].try_into().unwrap())
+            ^ u64::from_le_bytes(b[pos..pos + 8].try_into().unwrap());
+        if diff != 0 {
+            return pos + diff.trailing_zeros() as usize / 8;
+        }
+        pos += 8;
+    }
+    pos + a[pos..]
+        .iter()
+        .zip(&b[pos..])
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+#[cfg(feature = "compress")]
+pub(crate) const HASH_BITS: u32 = 16;
+#[cfg(feature = "compress")]
+pub(crate) const HASH_COUNT: usize = 1 << HASH_BITS;
+/// Range of the hash sizes that can be chosen, from 1 KiB to 16 MiB tables.
+#[cfg(feature = "compress")]
+pub(crate) const HASH_BITS_RANGE: core::ops::RangeInclusive<u32> = 8..=22;
+
+/// The last position by the hash of the 4 bytes starting there, in a table of `1 << bits`
+/// entries that is either owned or provided by the caller.
+#[cfg(feature = "compress")]
+#[derive(Clone)]
+pub(crate) struct MatchSearcher<T = Box<[u32]>> {
+    // on the heap, the table is too big for the stack of small threads
+    search_dict: T,
+    bits: u32,
+}
+
+#[cfg(feature = "compress")]
+impl MatchSearcher {
+    pub fn new() -> Self {
+        Self::with_bits(HASH_BITS)
+    }
+
+    /// A searcher with a table of `1 << bits` entries.
+    pub fn with_bits(bits: u32) -> Self {
+        MatchSearcher {
+            search_dict: vec![u32::MAX; 1 << bits].into_boxed_slice(),
+            bits,
+        }
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<'a> MatchSearcher<&'a mut [u32]> {
+    /// Uses the first [`HASH_COUNT`] entries of `table`, panics if it is shorter.
+    pub fn with_table(table: &'a mut [u32]) -> Self {
+        let mut searcher = MatchSearcher {
+            search_dict: &mut table[..HASH_COUNT],
+            bits: HASH_BITS,
+        };
+        searcher.reset();
+        searcher
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<T: DerefMut<Target = [u32]>> MatchSearcher<T> {
+    /// Forgets all positions, without allocating a new table.
+    pub fn reset(&mut self) {
+        self.search_dict.fill(u32::MAX);
+    }
+
+    pub fn submit_val(&mut self, data: &[u8], cur_pos: u32) {
+        let rest = &data[cur_pos as usize..];
+        if rest.len() < 4 {
+            return;
+        }
+        let hash = make_hash(rest[..4].try_into().unwrap(), self.bits);
+        self.search_dict[hash] = cur_pos;
+    }
+
+    pub fn get_lz_code(
+        &self,
+        data: &[u8],
+        cur_pos: u32,
+        max_distance: u32,
+        max_length: u32,
+    ) -> Option<(u32, u32)> {
+        let rest = &data[cur_pos as usize..];
+        if rest.len() < 4 {
+            return None;
+        }
+        let hash = make_hash(rest[..4].try_into().unwrap(), self.bits);
+        let prev = self.search_dict[hash];
+        if prev == u32::MAX {
+            return None;
+        }
+        let match_backref = cur_pos.wrapping_sub(prev);
+        if match_backref > max_distance {
+            return None;
+        }
+        let match_len = match_length(&data[cur_pos as usize..], &data[prev as usize..]);
+        if match_len < MIN_MATCH as usize {
+            return None;
+        }
+        Some((match_backref, (match_len as u32).min(max_length)))
+    }
+}
+
+/// The size of the compressed data in the worst case: the header and every byte as a literal.
+#[cfg(feature = "compress")]
+pub(crate) fn compress_bound(len: usize) -> usize {
+    8 + len + len / 8 + 1
+}
+
+#[cfg(feature = "compress")]
+pub(crate) fn compress<V: LzssVariant>(data: &[u8]) -> Vec<u8> {
+    compress_into_vec::<V>(data, Vec::with_capacity(data.len()))
+}
+
+/// Compresses `data` and appends it to `out_buf`, which doesn't grow anymore if it has
+/// [`compress_bound`] bytes of spare capacity.
+#[cfg(feature = "compress")]
+pub(crate) fn compress_into_vec<V: LzssVariant>(data: &[u8], out_buf: Vec<u8>) -> Vec<u8> {
+    compress_with_searcher::<V, _>(data, out_buf, &mut MatchSearcher::new())
+}
+
+/// Like [`compress_into_vec`], with a searcher that may already have a table.
+#[cfg(feature = "compress")]
+pub(crate) fn compress_with_searcher<V: LzssVariant, T: DerefMut<Target = [u32]>>(
+    data: &[u8],
+    out_buf: Vec<u8>,
+    searcher: &mut MatchSearcher<T>,
+) -> Vec<u8> {
+    let limits = MatchLimits::of::<V>();
+    compress_greedy::<V, _>(data, out_buf, searcher, limits, 1, false, false)
+}
+
+/// Writes `data` as literals only, like the compressor does for data without any matches.
+#[cfg(feature = "compress")]
+pub(crate) fn compress_stored<V: LzssVariant>(data: &[u8], mut out_buf: Vec<u8>) -> Vec<u8> {
+    out_buf.reserve(compress_bound(data.len()));
+    V::write_header(&mut out_buf, data.len());
+    if data.is_empty() {
+        out_buf.push(0);
+    }
+    for group in data.chunks(8) {
+        let group_header = if V::FLAG_MARKS_LITERAL {
+            (0..group.len() as u8).fold(0, |header, index| header | V::flag(index))
+        } else {
+            0
+        };
+        out_buf.push(group_header);
+        out_buf.extend_from_slice(group);
+    }
+    out_buf
+}
+
+/// Shortest match the match finders return.
+#[cfg(feature = "compress")]
+pub(crate) const MIN_MATCH: u32 = 4;
+
+/// The matches the compressor may use.
+#[cfg(feature = "compress")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MatchLimits {
+    pub min_length: u32,
+    pub max_length: u32,
+    pub max_distance: u32,
+    /// Never copies from the previous byte, VRAM can only be written 2 bytes at a time.
+    pub vram_safe: bool,
+}
+
+#[cfg(feature = "compress")]
+impl MatchLimits {
+    /// Everything the format can store.
+    pub fn of<V: LzssVariant>() -> Self {
+        MatchLimits {
+            min_length: MIN_MATCH,
+            max_length: V::MAX_LENGTH,
+            max_distance: V::MAX_DISTANCE,
+            vram_safe: false,
+        }
+    }
+
+    /// Asks `finder` for a match at `pos` and drops it if it is too short.
+    #[inline(always)]
+    pub fn find<M: MatchFinder>(
+        &self,
+        finder: &mut M,
+        data: &[u8],
+        pos: usize,
+    ) -> Option<(u32, u32)> {
+        let found = finder.find(data, pos, self.max_distance, self.max_length);
+        let found = match found {
+            Some((1, _)) if self.vram_safe => {
+                if pos < 2 || self.max_distance < 2 {
+                    return None;
+                }
+                // a run of the same byte also repeats every 2 bytes
+                let rest = &data[pos..data.len().min(pos + self.max_length as usize)];
+                Some((2, match_length(rest, &data[pos - 2..]) as u32))
+            }
+            found => found,
+        };
+        found.filter(|&(_, length)| length >= self.min_length)
+    }
+}
+
+/// Finds backreferences for the compressor.
+#[cfg(feature = "compress")]
+pub(crate) trait MatchFinder {
+    /// Returns distance and length of a match for the data at `pos`, if there is one.
+    fn find(
+        &mut self,
+        data: &[u8],
+        pos: usize,
+        max_distance: u32,
+        max_length: u32,
+    ) -> Option<(u32, u32)>;
+
+    /// Makes the data at `pos` available for later matches.
+    fn insert(&mut self, data: &[u8], pos: usize);
+}
+
+#[cfg(feature = "compress")]
+impl<T: DerefMut<Target = [u32]>> MatchFinder for MatchSearcher<T> {
+    fn find(
+        &mut self,
+        data: &[u8],
+        pos: usize,
+        max_distance: u32,
+        max_length: u32,
+    ) -> Option<(u32, u32)> {
+        self.get_lz_code(data, pos as u32, max_distance, max_length)
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        self.submit_val(data, pos as u32)
+    }
+}
+
+/// Where a [`GroupWriter`] stopped, to continue the same groups with a new writer later.
+#[cfg(feature = "compress")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GroupState {
+    group_header_pos: usize,
+    group_header: u8,
+    group_header_count: u8,
+}
+
+/// Writes the header, literals and backreferences, taking care of the group headers.
+#[cfg(feature = "compress")]
+pub(crate) struct GroupWriter<'a, V> {
+    out_buf: &'a mut Vec<u8>,
+    group_header_pos: usize,
+    group_header: u8,
+    group_header_count: u8,
+    variant: PhantomData<V>,
+}
+
+#[cfg(feature = "compress")]
+impl<'a, V: LzssVariant> GroupWriter<'a, V> {
+    /// Writes the header for `len` decompressed bytes and the first group header.
+    pub fn new(out_buf: &'a mut Vec<u8>, len: usize) -> Self {
+        V::write_header(out_buf, len);
+        Self::headerless(out_buf)
+    }
+
+    /// Only writes the first group header.
+    pub fn headerless(out_buf: &'a mut Vec<u8>) -> Self {
+        let group_header_pos = out_buf.len();
+        out_buf.push(0);
+        GroupWriter {
+            out_buf,
+            group_header_pos,
+            group_header: 0,
+            group_header_count: 0,
+            variant: PhantomData,
+        }
+    }
+
+    /// Continues the groups of the writer `state` was taken from, `out_buf` has to be the same
+    /// buffer.
+    pub fn resume(out_buf: &'a mut Vec<u8>, state: GroupState) -> Self {
+        GroupWriter {
+            out_buf,
+            group_header_pos: state.group_header_pos,
+            group_header: state.group_header,
+            group_header_count: state.group_header_count,
+            variant: PhantomData,
+        }
+    }
+
+    /// Stops writing without finishing the last group, see [`GroupWriter::resume`].
+    pub fn pause(self) -> GroupState {
+        GroupState {
+            group_header_pos: self.group_header_pos,
+            group_header: self.group_header,
+            group_header_count: self.group_header_count,
+        }
+    }
+
+    #[inline(always)]
+    fn next_flag(&mut self, backref: bool) {
+        if self.group_header_count == 8 {
+            self.out_buf[self.group_header_pos] = self.group_header;
+            self.group_header_pos = self.out_buf.len();
+            self.out_buf.push(0);
+            self.group_header = 0;
+            self.group_header_count = 0;
+        }
+        if backref != V::FLAG_MARKS_LITERAL {
+            self.group_header |= V::flag(self.group_header_count);
+        }
+        self.group_header_count += 1;
+    }
+
+    #[inline(always)]
+    pub fn literal(&mut self, byte: u8) {
+        self.next_flag(false);
+        self.out_buf.push(byte);
+    }
+
+    #[inline(always)]
+    pub fn backref(&mut self, distance: u32, length: u32) {
+        self.next_flag(true);
+        V::write_code(&LzssCode { distance, length }, self.out_buf);
+    }
+
+    /// Writes the items of `stream`, the groups of another headerless writer that make up `len`
+    /// decompressed bytes.
+    #[cfg(feature = "parallel")]
+    pub fn append(&mut self, stream: &[u8], len: usize) {
+        let mut pos = 0;
+        let mut produced = 0;
+        while produced < len {
+            let group_header = stream[pos];
+            pos += 1;
+            for index in 0..8 {
+                if produced >= len {
+                    break;
+                }
+                if V::is_backref(group_header, index) {
+                    let (code, size) = V::read_code(&stream[pos..]).unwrap();
+                    self.backref(code.distance, code.length);
+                    pos += size;
+                    produced += code.length as usize;
+                } else {
+                    self.literal(stream[pos]);
+                    pos += 1;
+                    produced += 1;
+                }
+            }
+        }
+    }
+
+    /// Writes the last group header.
+    pub fn finish(self) {
+        if self.group_header_count != 0 {
+            self.out_buf[self.group_header_pos] = self.group_header;
+        }
+    }
+}
+
+/// Takes the match `finder` returns at every position. Of the positions inside a match, every
+/// `insert_step`th one is inserted into `finder`, with 0 only the first one, which is faster but
+/// finds less matches. With `lazy` set, a match is only taken if the next position doesn't have
+/// a longer one. With `skip_ahead` set, positions get skipped the longer no match has been
+/// found, like in lz4, which makes incompressible data a lot faster.
+#[cfg(feature = "compress")]
+pub(crate) fn compress_greedy<V: LzssVariant, M: MatchFinder>(
+    data: &[u8],
+    mut out_buf: Vec<u8>,
+    finder: &mut M,
+    limits: MatchLimits,
+    insert_step: usize,
+    lazy: bool,
+    skip_ahead: bool,
+) -> Vec<u8> {
+    let mut writer = GroupWriter::<V>::new(&mut out_buf, data.len());
+    encode_greedy(
+        data,
+        0,
+        &mut writer,
+        finder,
+        limits,
+        insert_step,
+        lazy,
+        skip_ahead,
+    );
+    writer.finish();
+    out_buf
+}
+
+/// The loop of [`compress_greedy`] for the data from `start` on, the window before it is
+/// inserted into `finder` first.
+#[cfg(feature = "compress")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encode_greedy<V: LzssVariant, M: MatchFinder>(
+    data: &[u8],
+    start: usize,
+    writer: &mut GroupWriter<V>,
+    finder: &mut M,
+    limits: MatchLimits,
+    insert_step: usize,
+    lazy: bool,
+    skip_ahead: bool,
+) {
+    for pos in start.saturating_sub(limits.max_distance as usize)..start {
+        finder.insert(data, pos);
+    }
+    encode_greedy_until(
+        data,
+        start,
+        data.len(),
+        writer,
+        finder,
+        limits,
+        insert_step,
+        lazy,
+        skip_ahead,
+    );
+}
+
+/// Like [`encode_greedy`], but expects everything before `start` to be inserted into `finder`
+/// already and stops at `end`, or after the match crossing it. Returns the position it stopped
+/// at.
+#[cfg(feature = "compress")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn encode_greedy_until<V: LzssVariant, M: MatchFinder>(
+    data: &[u8],
+    start: usize,
+    end: usize,
+    writer: &mut GroupWriter<V>,
+    finder: &mut M,
+    limits: MatchLimits,
+    insert_step: usize,
+    lazy: bool,
+    skip_ahead: bool,
+) -> usize {
+    let mut pos = start;
+    // the match at `pos`, which isn't inserted yet
+    let mut found = limits.find(finder, data, pos);
+    let mut misses = 0;
+    while pos < end {
+        finder.insert(data, pos);
+        if let Some((distance, length)) = found {
+            if lazy && pos + 1 < data.len() {
+                let following = limits.find(finder, data, pos + 1);
+                if following.is_some_and(|(_, following_length)| following_length > length) {
+                    writer.literal(data[pos]);
+                    pos += 1;
+                    found = following;
+                    continue;
+                }
+            }
+            writer.backref(distance, length);
+            misses = 0;
+            let end = pos + length as usize;
+            if insert_step != 0 {
+                for p in (pos + 1..end).step_by(insert_step) {
+                    finder.insert(data, p);
+                }
+            }
+            pos = end;
+        } else if skip_ahead {
+            // one more position every 64 misses in a row
+            let step = 1 + (misses >> 6);
+            misses += 1;
+            let next = (pos + step).min(data.len());
+            for &byte in &data[pos..next] {
+                writer.literal(byte);
+            }
+            pos = next;
+        } else {
+            writer.literal(data[pos]);
+            pos += 1;
+        }
+        found = limits.find(finder, data, pos);
+    }
+    pos
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::match_length;
+
+    #[test]
+    pub fn test_match_length() {
+        let a: Vec<u8> = (0..100).collect();
+        for len in 0..a.len() {
+            let mut b = a.clone();
+            b[len] ^= 1;
+            assert_eq!(match_length(&a, &b), len);
+            assert_eq!(match_length(&a[..len], &b), len);
+            assert_eq!(match_length(&a, &b[..len]), len);
+        }
+        assert_eq!(match_length(&a, &a), a.len());
+    }
+}
@@ -0,0 +1,61 @@
+//! MIO0, used by N64 games. Same layout as [`crate::yay0`], but backreferences are at most
+//! 18 bytes long.
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "compress")]
+use crate::yay0::compress_split;
+#[cfg(feature = "decompress")]
+use crate::yay0::decompress_split;
+use crate::yay0::SplitFormat;
+use crate::DecompressError;
+
+pub(crate) const MIO0: SplitFormat = SplitFormat {
+    magic: b"MIO0",
+    long_lengths: false,
+};
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    compress_split(data, &MIO0)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    decompress_split(data, &MIO0)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 1000);
+        let compressed = compress(&data);
+        assert_eq!(&compressed[..4], b"MIO0");
+        assert!(compressed.len() < data.len() / 3);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_longest_match() {
+        // backreferences end at 18 bytes, so 36 bytes need two links where Yay0 has one
+        let data = [b'a'; 37];
+        let compressed = compress(&data);
+        assert_eq!(compressed[8..16], [0, 0, 0, 0x14, 0, 0, 0, 0x18]);
+        assert_eq!(compressed.len(), 0x18 + 1);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+        assert_eq!(crate::yay0::compress(&data).len(), 0x18 + 2);
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        // "abc" and a backreference of length 5 and distance 3
+        let mut compressed = b"MIO0\0\0\0\x08\0\0\0\x14\0\0\0\x18".to_vec();
+        compressed.extend_from_slice(&[0xE0, 0, 0, 0, 0x20, 0x02, 0x00, 0x00]);
+        compressed.extend_from_slice(b"abc");
+        assert_eq!(decompress(&compressed).unwrap(), b"abcabcab");
+    }
+}
@@ -0,0 +1,126 @@
+//! Optimal parsing: picks the literals and backreferences with the smallest total size instead
+//! of taking every match the match finder returns.
+//!
+//! The data is parsed in blocks, within a block the cheapest path is a shortest path over the
+//! positions, with literals and every length up to the longest match as edges. Matches at least
+//! [`NICE_LENGTH`] long end the block and are taken as they are, which keeps long runs fast.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::lzss::{GroupWriter, LzssVariant, MatchFinder, MatchLimits};
+
+/// Positions parsed at once.
+const BLOCK_SIZE: usize = 0x1000;
+/// Matches of at least this length are taken without looking for a cheaper path.
+const NICE_LENGTH: u32 = 0x111;
+
+#[derive(Clone, Copy)]
+struct Node {
+    /// Size in bits of the cheapest path to this position within the block.
+    cost: u32,
+    /// Length of the last step to this position, 1 for a literal.
+    length: u32,
+    distance: u32,
+}
+
+/// Bits for a literal or backreference, including its flag in the group header.
+fn cost<V: LzssVariant>(length: u32) -> u32 {
+    if length == 1 {
+        9
+    } else {
+        V::code_size(length) as u32 * 8 + 1
+    }
+}
+
+pub(crate) fn compress<V: LzssVariant, M: MatchFinder>(
+    data: &[u8],
+    mut out_buf: Vec<u8>,
+    finder: &mut M,
+    limits: MatchLimits,
+) -> Vec<u8> {
+    let mut writer = GroupWriter::<V>::new(&mut out_buf, data.len());
+    let mut nodes = vec![
+        Node {
+            cost: 0,
+            length: 0,
+            distance: 0,
+        };
+        BLOCK_SIZE + 1
+    ];
+    let mut path = Vec::new();
+    // the match at the start of the next block, if it was already searched
+    let mut pending = None;
+    let mut pos = 0;
+    while pos < data.len() {
+        let block_len = BLOCK_SIZE.min(data.len() - pos);
+        for node in &mut nodes[1..=block_len] {
+            node.cost = u32::MAX;
+        }
+        let mut end = block_len;
+        for i in 0..block_len {
+            let found = match pending.take() {
+                Some(found) if i == 0 => found,
+                _ => {
+                    let found = limits.find(finder, data, pos + i);
+                    finder.insert(data, pos + i);
+                    found
+                }
+            };
+            if let Some((distance, length)) = found.filter(|&(_, length)| length >= NICE_LENGTH) {
+                if i == 0 {
+                    writer.backref(distance, length);
+                    for p in pos + 1..pos + length as usize {
+                        finder.insert(data, p);
+                    }
+                    pos += length as usize;
+                    end = 0;
+                } else {
+                    // the block ends here, the next one starts with this match
+                    pending = Some(found);
+                    end = i;
+                }
+                break;
+            }
+            let base = nodes[i].cost;
+            let mut relax = |target: usize, length: u32, distance: u32| {
+                let cost = base + cost::<V>(length);
+                if cost < nodes[target].cost {
+                    nodes[target] = Node {
+                        cost,
+                        length,
+                        distance,
+                    };
+                }
+            };
+            relax(i + 1, 1, 0);
+            if let Some((distance, length)) = found {
+                let max_length = length.min((block_len - i) as u32);
+                for length in limits.min_length..=max_length {
+                    relax(i + length as usize, length, distance);
+                }
+            }
+        }
+        if end == 0 {
+            continue;
+        }
+
+        path.clear();
+        let mut i = end;
+        while i > 0 {
+            let node = nodes[i];
+            path.push(node);
+            i -= node.length as usize;
+        }
+        for node in path.iter().rev() {
+            if node.length == 1 {
+                writer.literal(data[pos]);
+            } else {
+                writer.backref(node.distance, node.length);
+            }
+            pos += node.length as usize;
+        }
+    }
+    writer.finish();
+    out_buf
+}
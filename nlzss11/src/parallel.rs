@@ -0,0 +1,78 @@
+//! Multithreaded compression: the data is split into chunks which are compressed on the rayon
+//! thread pool and stitched together into a single stream.
+//!
+//! Every chunk still sees the 4 KiB before it as history, only the backreferences that would
+//! reach into the next chunk are lost, so the result is barely bigger than [`crate::compress`]'s.
+
+use rayon::prelude::*;
+
+use crate::lzss::{compress_bound, encode_greedy, GroupWriter, MatchLimits, MatchSearcher};
+use crate::Lz11;
+
+/// Size of the chunks [`compress`] splits the data into.
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Compresses the chunk from `start` on, returns its groups without a header.
+fn compress_chunk(data: &[u8], start: usize) -> Vec<u8> {
+    let mut out_buf = Vec::with_capacity(compress_bound(data.len() - start));
+    let mut writer = GroupWriter::<Lz11>::headerless(&mut out_buf);
+    encode_greedy(
+        data,
+        start,
+        &mut writer,
+        &mut MatchSearcher::new(),
+        MatchLimits::of::<Lz11>(),
+        1,
+        false,
+        false,
+    );
+    writer.finish();
+    out_buf
+}
+
+/// Compresses `data` in chunks of [`DEFAULT_CHUNK_SIZE`] bytes on the global rayon thread pool.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    compress_with_chunk_size(data, DEFAULT_CHUNK_SIZE)
+}
+
+/// Compresses `data` in chunks of `chunk_size` bytes. Data that fits in a single chunk is
+/// compressed just like [`crate::compress`] does.
+pub fn compress_with_chunk_size(data: &[u8], chunk_size: usize) -> Vec<u8> {
+    let chunk_size = chunk_size.max(1);
+    let chunk_count = data.len().div_ceil(chunk_size);
+    let chunks: Vec<Vec<u8>> = (0..chunk_count)
+        .into_par_iter()
+        .map(|chunk| {
+            let end = ((chunk + 1) * chunk_size).min(data.len());
+            compress_chunk(&data[..end], chunk * chunk_size)
+        })
+        .collect();
+
+    let mut out_buf = Vec::with_capacity(chunks.iter().map(Vec::len).sum::<usize>() + 8);
+    let mut writer = GroupWriter::<Lz11>::new(&mut out_buf, data.len());
+    for (chunk, compressed) in chunks.iter().enumerate() {
+        let len = chunk_size.min(data.len() - chunk * chunk_size);
+        writer.append(compressed, len);
+    }
+    writer.finish();
+    out_buf
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::compress_with_chunk_size;
+    use crate::test_data::sample;
+    use crate::{compress, decompress};
+
+    #[test]
+    pub fn test_chunks() {
+        let data = sample(50000, 0);
+        for chunk_size in [1, 7, 1000, 4096, 20000] {
+            let compressed = compress_with_chunk_size(&data, chunk_size);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+        assert!(compress_with_chunk_size(&data, 4096).len() < data.len() / 4);
+        assert_eq!(compress_with_chunk_size(&data, data.len()), compress(&data));
+        assert_eq!(compress_with_chunk_size(&[], 10), compress(&[]));
+    }
+}
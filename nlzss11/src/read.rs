@@ -0,0 +1,91 @@
+//! Adapters working on a [`Read`] source, they wrap it in a [`BufReader`] and use the
+//! types from [`crate::bufread`].
+
+use std::io::{self, BufReader, Read};
+
+use crate::bufread;
+
+/// Reads uncompressed data from a [`Read`] and yields it compressed.
+#[cfg(feature = "compress")]
+pub struct NlzssEncoder<R> {
+    inner: bufread::NlzssEncoder<BufReader<R>>,
+}
+
+#[cfg(feature = "compress")]
+impl<R: Read> NlzssEncoder<R> {
+    pub fn new(r: R) -> Self {
+        NlzssEncoder {
+            inner: bufread::NlzssEncoder::new(BufReader::new(r)),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut().get_mut()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<R: Read> Read for NlzssEncoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// Reads compressed data from a [`Read`] and yields it decompressed.
+///
+/// The source is read piece by piece while the output is consumed, so neither the compressed
+/// nor the decompressed data has to fit in memory, e.g. with [`io::copy`]:
+///
+/// ```
+/// # #[cfg(feature = "compress")] {
+/// # let compressed = nlzss11::compress(b"copy copy copy copy");
+/// # let source = compressed.as_slice();
+/// // source could be a std::fs::File
+/// let mut decoder = nlzss11::read::NlzssDecoder::new(source);
+/// let mut out = Vec::new();
+/// std::io::copy(&mut decoder, &mut out)?;
+/// assert_eq!(out, b"copy copy copy copy");
+/// # }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[doc(alias = "Lz11Decoder")]
+#[cfg(feature = "decompress")]
+pub struct NlzssDecoder<R> {
+    inner: bufread::NlzssDecoder<BufReader<R>>,
+}
+
+#[cfg(feature = "decompress")]
+impl<R: Read> NlzssDecoder<R> {
+    pub fn new(r: R) -> Self {
+        NlzssDecoder {
+            inner: bufread::NlzssDecoder::new(BufReader::new(r)),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut().get_mut()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+#[cfg(feature = "decompress")]
+impl<R: Read> Read for NlzssDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
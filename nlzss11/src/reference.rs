@@ -0,0 +1,366 @@
+//! A deliberately simple nlzss11 decoder and encoder to check the optimized ones against.
+//!
+//! Both follow the format description as directly as possible and don't care about speed, the
+//! encoder tries every distance at every position. The `cross_check_*` functions compare them
+//! with [`crate::compress`] and [`crate::decompress`], for fuzzers and tests.
+//!
+//! [`cross_check_encoders`] decompresses the output of every compressor setting with
+//! [`decompress_strict`], which also catches streams that only decompress because
+//! [`crate::decompress`] is lenient.
+
+use crate::cue::CueMode;
+use crate::{CompressionOptions, Compressor, DecompressError, Level};
+
+const MAX_DISTANCE: usize = 0x1000;
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 0x10110;
+
+fn byte(data: &[u8], pos: usize) -> Result<u8, DecompressError> {
+    data.get(pos)
+        .copied()
+        .ok_or(DecompressError::InvalidIndex(pos))
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    decode(data, false)
+}
+
+/// Like [`decompress`], but fails with [`DecompressError::NotStrict`] if anything follows the
+/// stream, the last backreference reaches past the decompressed size or an unused flag of the
+/// last group is set, none of which an encoder should write.
+pub fn decompress_strict(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    decode(data, true)
+}
+
+fn decode(data: &[u8], strict: bool) -> Result<Vec<u8>, DecompressError> {
+    if byte(data, 0)? != 0x11 {
+        return Err(DecompressError::InvalidMagic);
+    }
+    let mut size = 0;
+    for i in 0..3 {
+        size |= (byte(data, 1 + i)? as usize) << (8 * i);
+    }
+    let mut pos = 4;
+    if size == 0 {
+        for i in 0..4 {
+            size |= (byte(data, 4 + i)? as usize) << (8 * i);
+        }
+        pos = 8;
+    }
+
+    let mut out = Vec::new();
+    while out.len() < size {
+        let flags = byte(data, pos)?;
+        pos += 1;
+        for bit in (0..8).rev() {
+            if out.len() >= size {
+                if strict && flags as usize & ((2 << bit) - 1) != 0 {
+                    return Err(DecompressError::NotStrict(pos));
+                }
+                break;
+            }
+            if flags & (1 << bit) == 0 {
+                out.push(byte(data, pos)?);
+                pos += 1;
+                continue;
+            }
+            let first = byte(data, pos)? as usize;
+            let (length, distance_high) = match first >> 4 {
+                0 => {
+                    let second = byte(data, pos + 1)? as usize;
+                    pos += 2;
+                    ((first << 4 | second >> 4) + 0x11, second & 0xF)
+                }
+                1 => {
+                    let second = byte(data, pos + 1)? as usize;
+                    let third = byte(data, pos + 2)? as usize;
+                    pos += 3;
+                    (
+                        ((first & 0xF) << 12 | second << 4 | third >> 4) + 0x111,
+                        third & 0xF,
+                    )
+                }
+                nibble => {
+                    pos += 1;
+                    (nibble + 1, first & 0xF)
+                }
+            };
+            let distance = (distance_high << 8 | byte(data, pos)? as usize) + 1;
+            pos += 1;
+            if distance > out.len() {
+                return Err(DecompressError::InvalidIndex(pos));
+            }
+            if strict && length > size - out.len() {
+                return Err(DecompressError::NotStrict(pos));
+            }
+            for _ in 0..length {
+                if out.len() >= size {
+                    break;
+                }
+                out.push(out[out.len() - distance]);
+            }
+        }
+    }
+    if strict && pos != data.len() {
+        return Err(DecompressError::NotStrict(pos));
+    }
+    Ok(out)
+}
+
+/// Compresses `data` with the longest backreference at every position, the closest one if
+/// there are several.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x11];
+    if data.len() < 0xFFFFFF && !data.is_empty() {
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes()[..3]);
+    } else {
+        out.extend_from_slice(&[0, 0, 0]);
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    }
+
+    let mut pos = 0;
+    let mut flags_pos = 0;
+    let mut flag_count = 8;
+    while pos < data.len() {
+        if flag_count == 8 {
+            flags_pos = out.len();
+            out.push(0);
+            flag_count = 0;
+        }
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        for distance in 1..=pos.min(MAX_DISTANCE) {
+            let mut length = 0;
+            while length < MAX_LENGTH
+                && pos + length < data.len()
+                && data[pos + length] == data[pos + length - distance]
+            {
+                length += 1;
+            }
+            if length > best_length {
+                best_length = length;
+                best_distance = distance;
+            }
+        }
+        if best_length >= MIN_LENGTH {
+            out[flags_pos] |= 0x80 >> flag_count;
+            let length = best_length;
+            let distance = best_distance - 1;
+            if length >= 0x111 {
+                let length = length - 0x111;
+                out.push(0x10 | (length >> 12) as u8);
+                out.push((length >> 4) as u8);
+                out.push(((length & 0xF) << 4 | distance >> 8) as u8);
+            } else if length >= 0x11 {
+                let length = length - 0x11;
+                out.push((length >> 4) as u8);
+                out.push(((length & 0xF) << 4 | distance >> 8) as u8);
+            } else {
+                out.push(((length - 1) << 4 | distance >> 8) as u8);
+            }
+            out.push(distance as u8);
+            pos += best_length;
+        } else {
+            out.push(data[pos]);
+            pos += 1;
+        }
+        flag_count += 1;
+    }
+    out
+}
+
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum Mismatch {
+    #[error("decompress returned {optimized}, the reference decoder {reference}")]
+    Decompress {
+        optimized: String,
+        reference: String,
+    },
+    #[error("data compressed with {0} decompresses to different data")]
+    Roundtrip(&'static str),
+}
+
+fn describe(result: &Result<Vec<u8>, DecompressError>) -> String {
+    match result {
+        Ok(out) => format!("{} bytes", out.len()),
+        Err(e) => format!("error '{}'", e),
+    }
+}
+
+/// Checks that [`crate::decompress`] and the reference decoder agree on `data`, both have to
+/// return the same output or both have to fail.
+pub fn cross_check_decompress(data: &[u8]) -> Result<(), Mismatch> {
+    let optimized = crate::decompress(data);
+    let reference = decompress(data);
+    match (&optimized, &reference) {
+        (Ok(a), Ok(b)) if a == b => Ok(()),
+        (Err(_), Err(_)) => Ok(()),
+        _ => Err(Mismatch::Decompress {
+            optimized: describe(&optimized),
+            reference: describe(&reference),
+        }),
+    }
+}
+
+/// Checks that the output of [`crate::compress`] decompresses to `data` with the reference
+/// decoder and the output of the reference encoder with [`crate::decompress`].
+pub fn cross_check_compress(data: &[u8]) -> Result<(), Mismatch> {
+    if decompress_strict(&crate::compress(data)).ok().as_deref() != Some(data) {
+        return Err(Mismatch::Roundtrip("compress"));
+    }
+    if crate::decompress(&compress(data)).ok().as_deref() != Some(data) {
+        return Err(Mismatch::Roundtrip("the reference encoder"));
+    }
+    Ok(())
+}
+
+/// The compressor settings [`cross_check_encoders`] tries, everything that changes which
+/// matches are chosen or how the stream is written except for the alignment.
+fn encoder_options() -> Vec<(&'static str, CompressionOptions)> {
+    let options = CompressionOptions::new;
+    let levels = [
+        "level 0", "level 1", "level 2", "level 3", "level 4", "level 5", "level 6", "level 7",
+        "level 8", "level 9",
+    ];
+    let mut encoders: Vec<_> = (0..)
+        .zip(levels)
+        .map(|(level, name)| (name, options().level(Level::new(level))))
+        .collect();
+    encoders.extend([
+        ("chain depth", options().chain_depth(4)),
+        ("min match", options().min_match(3)),
+        ("max match", options().level(Level::BEST).max_match(0x110)),
+        ("window size", options().window_size(0x100)),
+        ("vram safe", options().level(Level::BEST).vram_safe(true)),
+        ("extended header", options().extended_header(true)),
+        ("hash bits", options().level(Level::new(8)).hash_bits(8)),
+        ("insert step", options().insert_step(3)),
+        ("cue", options().cue(CueMode::default())),
+        (
+            "cue vram optimal",
+            options().cue(CueMode {
+                vram: true,
+                optimal: true,
+            }),
+        ),
+    ]);
+    encoders
+}
+
+/// Checks that the output of every compressor and every setting of
+/// [`crate::compress_with_options`] decompresses to `data` with [`decompress_strict`].
+///
+/// Empty data isn't checked, the compressors write a group header for it that the reference
+/// decoder doesn't expect.
+pub fn cross_check_encoders(data: &[u8]) -> Result<(), Mismatch> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let check = |name, compressed: Vec<u8>| {
+        if decompress_strict(&compressed).ok().as_deref() == Some(data) {
+            Ok(())
+        } else {
+            Err(Mismatch::Roundtrip(name))
+        }
+    };
+    check("compress", crate::compress(data))?;
+    check("compress_canonical", crate::compress_canonical(data))?;
+    check("Compressor", Compressor::new().compress(data))?;
+    #[cfg(feature = "parallel")]
+    check(
+        "parallel",
+        crate::parallel::compress_with_chunk_size(data, 0x1000),
+    )?;
+    for (name, options) in encoder_options() {
+        check(name, crate::compress_with_options(data, &options))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        cross_check_compress, cross_check_decompress, cross_check_encoders, decompress_strict,
+    };
+    use crate::corpus;
+    use crate::DecompressError;
+
+    #[test]
+    pub fn test_cross_check() {
+        let mut state = 0x9E3779B9u32;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for len in [1, 2, 17, 300, 2000] {
+            for alphabet in [1, 2, 20, 256] {
+                let data: Vec<u8> = (0..len).map(|_| (random() % alphabet) as u8).collect();
+                cross_check_compress(&data).unwrap();
+                let mut compressed = crate::compress(&data);
+                cross_check_decompress(&compressed).unwrap();
+                for _ in 0..20 {
+                    let index = random() as usize % compressed.len();
+                    compressed[index] ^= 1 << (random() % 8);
+                    cross_check_decompress(&compressed).unwrap();
+                    cross_check_decompress(&compressed[..index]).unwrap();
+                }
+            }
+        }
+        cross_check_compress(&[7; 0x20000]).unwrap();
+    }
+
+    #[test]
+    pub fn test_cross_check_encoders() {
+        let mut state = 0x2545F491u32;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        for len in [1, 3, 17, 300, 2000] {
+            for alphabet in [1, 2, 4, 256] {
+                let data: Vec<u8> = (0..len).map(|_| (random() % alphabet) as u8).collect();
+                cross_check_encoders(&data).unwrap();
+            }
+        }
+        // runs of the same byte longer than the window
+        let data: Vec<u8> = (0..0x3000u32)
+            .map(|i| {
+                if i % 0x1800 < 0x1100 {
+                    0
+                } else {
+                    (i / 7) as u8
+                }
+            })
+            .collect();
+        cross_check_encoders(&data).unwrap();
+        for entry in corpus::generate() {
+            if entry.decompressed.len() <= 0x10000 {
+                cross_check_encoders(&entry.decompressed).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_decompress_strict() {
+        let compressed = crate::compress(b"strict strict strict");
+        assert_eq!(
+            decompress_strict(&compressed).unwrap(),
+            b"strict strict strict"
+        );
+        let not_strict =
+            |data: &[u8]| matches!(decompress_strict(data), Err(DecompressError::NotStrict(_)));
+        let mut padded = compressed.clone();
+        padded.push(0);
+        assert!(not_strict(&padded));
+        // 'a' and a backreference of 4 bytes for 3 bytes of output
+        assert!(not_strict(b"\x11\x04\x00\x00\x40a\x30\x00"));
+        assert!(decompress_strict(b"\x11\x04\x00\x00\x40a\x20\x00").is_ok());
+        // an unused flag after the only literal
+        assert!(not_strict(b"\x11\x01\x00\x00\x40a"));
+    }
+}
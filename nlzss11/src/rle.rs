@@ -0,0 +1,118 @@
+//! The run-length encoding (type 0x30) of the GBA/DS BIOS, often found next to LZ11 data.
+
+use alloc::vec::Vec;
+
+use crate::lzss::{self, read_header, write_header, Sink};
+use crate::DecompressError;
+
+const MAGIC: u8 = 0x30;
+const MAX_RAW: usize = 0x80;
+const MIN_RUN: usize = 3;
+const MAX_RUN: usize = 0x7F + MIN_RUN;
+
+/// Decodes `data` into `sink`, returns the length of the compressed data that was used.
+pub(crate) fn decode(data: &[u8], sink: &mut impl Sink) -> Result<usize, DecompressError> {
+    let (out_size, mut pos) = read_header(data, MAGIC)?;
+    let get = |pos: usize| {
+        data.get(pos)
+            .copied()
+            .ok_or(DecompressError::InvalidIndex(pos))
+    };
+    while sink.len() < out_size {
+        let flag = get(pos)?;
+        pos += 1;
+        let remaining = out_size - sink.len();
+        if flag & 0x80 != 0 {
+            // 1LLLLLLL BBBBBBBB, B repeated L + 3 times
+            let byte = get(pos)?;
+            pos += 1;
+            for _ in 0..((flag & 0x7F) as usize + MIN_RUN).min(remaining) {
+                sink.push(byte);
+            }
+        } else {
+            // 0LLLLLLL followed by L + 1 literals
+            let length = (flag as usize + 1).min(remaining);
+            for _ in 0..length {
+                sink.push(get(pos)?);
+                pos += 1;
+            }
+        }
+    }
+    Ok(pos)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    let (out_size, header_len) = read_header(data, MAGIC)?;
+    let mut out_buf = Vec::with_capacity(out_size.min(lzss::max_output(data.len() - header_len)));
+    decode(data, &mut out_buf)?;
+    Ok(out_buf)
+}
+
+fn write_raw(out_buf: &mut Vec<u8>, raw: &[u8]) {
+    for chunk in raw.chunks(MAX_RAW) {
+        out_buf.push((chunk.len() - 1) as u8);
+        out_buf.extend_from_slice(chunk);
+    }
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out_buf = Vec::with_capacity(data.len() + data.len() / MAX_RAW + 9);
+    write_header(&mut out_buf, MAGIC, data.len());
+    let mut raw_start = 0;
+    let mut pos = 0;
+    while pos < data.len() {
+        let run = data[pos..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&b| b == data[pos])
+            .count();
+        if run >= MIN_RUN {
+            write_raw(&mut out_buf, &data[raw_start..pos]);
+            out_buf.push(0x80 | (run - MIN_RUN) as u8);
+            out_buf.push(data[pos]);
+            pos += run;
+            raw_start = pos;
+        } else {
+            pos += 1;
+        }
+    }
+    write_raw(&mut out_buf, &data[raw_start..]);
+    out_buf
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data: Vec<u8> = (0..2000u32)
+            .map(|i| (i % 7) as u8)
+            .chain([9; 1000])
+            .chain((0..300).map(|i| i as u8))
+            .collect();
+        let compressed = compress(&data);
+        assert_eq!(compressed[0], 0x30);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+
+        // a run is at most 130 bytes and a literal block at most 128
+        let mut data = vec![7; 131];
+        data.extend(0..129);
+        let mut expected = vec![0x30, 0x04, 0x01, 0x00, 0xFF, 7, 0x7F, 7];
+        expected.extend(0..127);
+        expected.extend([0x01, 127, 128]);
+        assert_eq!(compress(&data), expected);
+        assert_eq!(decompress(&expected).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        let compressed = [0x30, 0x08, 0x00, 0x00, 0x01, b'a', b'b', 0x83, b'c'];
+        assert_eq!(decompress(&compressed).unwrap(), b"abcccccc");
+        assert_eq!(compress(b"abcccccc"), compressed);
+        // 4 GiB declared in a few bytes
+        assert!(decompress(&[0x30, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, b'a']).is_err());
+    }
+}
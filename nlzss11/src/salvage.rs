@@ -0,0 +1,162 @@
+//! Best effort recovery of damaged nlzss11 data, for partially corrupted dumps.
+//!
+//! Decoding stops being reliable at the first error. To get at the data after it, the decoder
+//! tries every following byte as the start of a group and continues at the first one from which
+//! several groups decode without errors. Bytes lost in the damaged part shift everything after
+//! it, so backreferences reaching across it copy the wrong data; the result is a starting point
+//! for manual repair, not a faithful reconstruction.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::lzss::read_header;
+use crate::{DecompressError, LzssCode};
+
+/// Groups that have to decode without errors to resume at a position.
+const RESYNC_GROUPS: usize = 4;
+
+/// A place where decoding failed and was resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resync {
+    /// Length of the decompressed data when the error happened.
+    pub output_offset: usize,
+    /// The compressed bytes skipped, from the failing group header or code to the position
+    /// decoding was resumed at.
+    pub skipped: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SalvageResult {
+    /// Everything that could be decoded, shorter than `declared_len` if data is missing.
+    pub data: Vec<u8>,
+    /// Decompressed size from the header.
+    pub declared_len: usize,
+    /// Bytes at the start of `data` decoded before the first error, these are reliable.
+    pub intact_len: usize,
+    pub resyncs: Vec<Resync>,
+}
+
+impl SalvageResult {
+    /// Whether the data decoded without any errors.
+    pub fn is_intact(&self) -> bool {
+        self.intact_len == self.declared_len
+    }
+
+    /// The share of the declared size that was decoded before the first error, 1.0 for intact
+    /// data.
+    pub fn confidence(&self) -> f64 {
+        if self.declared_len == 0 {
+            1.0
+        } else {
+            self.intact_len as f64 / self.declared_len as f64
+        }
+    }
+}
+
+/// Decodes groups from `pos` on into `out` until it has `out_size` bytes or `max_groups` are
+/// done, returns the position after them. On errors, including the end of the input, returns
+/// the position of the group header or code that failed.
+fn decode_groups(
+    data: &[u8],
+    mut pos: usize,
+    out: &mut Vec<u8>,
+    out_size: usize,
+    max_groups: usize,
+) -> Result<usize, usize> {
+    for _ in 0..max_groups {
+        if out.len() >= out_size {
+            break;
+        }
+        let group_header = *data.get(pos).ok_or(pos)?;
+        pos += 1;
+        for index in 0..8 {
+            if out.len() >= out_size {
+                break;
+            }
+            if group_header & (0x80 >> index) == 0 {
+                out.push(*data.get(pos).ok_or(pos)?);
+                pos += 1;
+                continue;
+            }
+            let (LzssCode { distance, length }, advance) =
+                LzssCode::read(&data[pos..]).ok_or(pos)?;
+            let start = out.len().checked_sub(distance as usize).ok_or(pos)?;
+            pos += advance;
+            let length = (length as usize).min(out_size - out.len());
+            for cpy_pos in start..start + length {
+                out.push(out[cpy_pos]);
+            }
+        }
+    }
+    Ok(pos)
+}
+
+/// Decodes `data` as far as possible, continuing after errors. Only fails if the header is
+/// invalid.
+pub fn salvage(data: &[u8]) -> Result<SalvageResult, DecompressError> {
+    let (declared_len, mut pos) = read_header(data, 0x11)?;
+    let mut out = Vec::new();
+    let mut intact_len = None;
+    let mut resyncs = Vec::new();
+    while let Err(error_pos) = decode_groups(data, pos, &mut out, declared_len, usize::MAX) {
+        intact_len.get_or_insert(out.len());
+        let output_offset = out.len();
+        let resume_pos = (error_pos + 1..data.len()).find(|&candidate| {
+            let ok = decode_groups(data, candidate, &mut out, declared_len, RESYNC_GROUPS).is_ok();
+            out.truncate(output_offset);
+            ok
+        });
+        let Some(resume_pos) = resume_pos else {
+            break;
+        };
+        resyncs.push(Resync {
+            output_offset,
+            skipped: error_pos..resume_pos,
+        });
+        pos = resume_pos;
+    }
+    Ok(SalvageResult {
+        intact_len: intact_len.unwrap_or(out.len()),
+        data: out,
+        declared_len,
+        resyncs,
+    })
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::salvage;
+    use crate::compress;
+
+    #[test]
+    pub fn test_salvage() {
+        let data: Vec<u8> = (0..20000u32)
+            .flat_map(|i| format!("line {}\n", i).into_bytes())
+            .collect();
+        let compressed = compress(&data);
+
+        let salvaged = salvage(&compressed).unwrap();
+        assert!(salvaged.is_intact());
+        assert_eq!(salvaged.data, data);
+
+        // truncated data can't be resynchronized, the prefix is still there
+        let salvaged = salvage(&compressed[..compressed.len() / 2]).unwrap();
+        assert!(salvaged.resyncs.is_empty());
+        assert!(salvaged.intact_len > 0);
+        assert_eq!(salvaged.data, data[..salvaged.intact_len]);
+
+        // backreferences reaching before the start of the data
+        let mut damaged = compressed.clone();
+        damaged[20..40].fill(0xFF);
+        let salvaged = salvage(&damaged).unwrap();
+        assert!(!salvaged.is_intact());
+        assert!(salvaged.confidence() < 0.01);
+        assert_eq!(salvaged.resyncs.len(), 1);
+        assert!(salvaged.resyncs[0].skipped.end > 20);
+        assert_eq!(
+            salvaged.data[..salvaged.intact_len],
+            data[..salvaged.intact_len]
+        );
+        assert!(salvaged.data.len() > data.len() / 2);
+    }
+}
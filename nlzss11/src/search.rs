@@ -0,0 +1,301 @@
+//! Match finders that are slower than the hash table in [`crate::lzss`] but find better matches.
+
+use alloc::boxed::Box;
+use alloc::vec;
+
+use crate::lzss::{make_hash, match_length, MatchFinder, HASH_BITS, MIN_MATCH};
+/// Positions kept in the chains, has to be bigger than the maximum distance.
+const WINDOW_SIZE: usize = 0x1000;
+const EMPTY: u32 = u32::MAX;
+
+/// Keeps all positions in the window with the same hash in a chain and follows it for up to
+/// `depth` positions, taking the longest match, the closest one if several have that length.
+///
+/// All earlier positions starting with the same 4 bytes are in the chain, so with an unlimited
+/// depth it finds the longest match there is.
+pub(crate) struct HashChain {
+    /// The newest position by the hash of the 4 bytes starting there, `1 << bits` entries.
+    head: Box<[u32]>,
+    bits: u32,
+    /// The previous position with the same hash, by position modulo the window size.
+    prev: Box<[u32]>,
+    depth: usize,
+}
+
+impl HashChain {
+    pub fn new(depth: usize, bits: u32) -> Self {
+        HashChain {
+            head: vec![EMPTY; 1 << bits].into_boxed_slice(),
+            bits,
+            prev: vec![EMPTY; WINDOW_SIZE].into_boxed_slice(),
+            depth: depth.max(1),
+        }
+    }
+}
+
+fn hash(data: &[u8], pos: usize, bits: u32) -> Option<usize> {
+    let sequence = data.get(pos..pos + 4)?.try_into().unwrap();
+    Some(make_hash(sequence, bits))
+}
+
+impl MatchFinder for HashChain {
+    fn find(
+        &mut self,
+        data: &[u8],
+        pos: usize,
+        max_distance: u32,
+        max_length: u32,
+    ) -> Option<(u32, u32)> {
+        debug_assert!((max_distance as usize) < WINDOW_SIZE);
+        let max_length = (max_length as usize).min(data.len() - pos);
+        let mut candidate = self.head[hash(data, pos, self.bits)?];
+        let mut best = (0, MIN_MATCH as usize - 1);
+        for _ in 0..self.depth {
+            if candidate == EMPTY || pos - candidate as usize > max_distance as usize {
+                break;
+            }
+            let start = candidate as usize;
+            // can't be longer than the best match if the byte after it differs
+            if data[start + best.1] == data[pos + best.1] {
+                let length = match_length(&data[start..], &data[pos..pos + max_length]);
+                if length > best.1 {
+                    best = (pos - start, length);
+                    if length == max_length {
+                        break;
+                    }
+                }
+            }
+            let next = self.prev[start % WINDOW_SIZE];
+            // the entry was overwritten by a later position
+            if next >= candidate && next != EMPTY {
+                break;
+            }
+            candidate = next;
+        }
+        (best.0 != 0).then_some((best.0 as u32, best.1 as u32))
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        if let Some(hash) = hash(data, pos, self.bits) {
+            self.prev[pos % WINDOW_SIZE] = self.head[hash];
+            self.head[hash] = pos as u32;
+        }
+    }
+}
+
+/// Keeps the positions in the window in binary trees sorted by the data following them, one
+/// tree per hash, like the BT4 match finder of LZMA. Searching walks down from the newest
+/// position and inserts the searched one as the new root on the way, so the longest match in
+/// the window is found while only comparing with a few positions.
+///
+/// [`MatchFinder::find`] already inserts the position, inserting it again afterwards does
+/// nothing.
+pub(crate) struct BinaryTree {
+    /// The root of the tree by the hash of the 4 bytes starting there, `1 << bits` entries.
+    head: Box<[u32]>,
+    bits: u32,
+    /// The smaller and bigger child of every position, by position modulo the window size.
+    children: Box<[[u32; 2]]>,
+    /// The position `find` inserted last.
+    found: Option<usize>,
+    max_distance: usize,
+    max_length: usize,
+}
+
+impl BinaryTree {
+    pub fn new(bits: u32) -> Self {
+        BinaryTree {
+            head: vec![EMPTY; 1 << bits].into_boxed_slice(),
+            bits,
+            children: vec![[EMPTY; 2]; WINDOW_SIZE].into_boxed_slice(),
+            found: None,
+            max_distance: WINDOW_SIZE - 1,
+            max_length: usize::MAX,
+        }
+    }
+
+    /// Makes `pos` the root of its tree, moving the positions in the old tree to its left or
+    /// right, returns the longest match on the way.
+    fn insert_root(&mut self, data: &[u8], pos: usize) -> Option<(u32, u32)> {
+        let hash = hash(data, pos, self.bits)?;
+        let max_length = self.max_length.min(data.len() - pos);
+        let mut candidate = self.head[hash];
+        self.head[hash] = pos as u32;
+        // where the next smaller and bigger positions go, with the length they have in common
+        let mut smaller = (pos % WINDOW_SIZE, 0);
+        let mut bigger = (pos % WINDOW_SIZE, 1);
+        let mut smaller_length = 0;
+        let mut bigger_length = 0;
+        let mut best = (0, MIN_MATCH as usize - 1);
+        loop {
+            if candidate == EMPTY || pos - candidate as usize > self.max_distance {
+                self.children[smaller.0][smaller.1] = EMPTY;
+                self.children[bigger.0][bigger.1] = EMPTY;
+                break;
+            }
+            let start = candidate as usize;
+            let node = start % WINDOW_SIZE;
+            // both neighbours share this many bytes with `pos`, so the candidate does as well
+            let common = smaller_length.min(bigger_length);
+            let length = common
+                + match_length(
+                    &data[start + common..],
+                    &data[pos + common..pos + max_length],
+                );
+            if length > best.1 {
+                best = (pos - start, length);
+            }
+            if length == max_length {
+                // same data as far as it is compared, `pos` takes over its children
+                self.children[smaller.0][smaller.1] = self.children[node][0];
+                self.children[bigger.0][bigger.1] = self.children[node][1];
+                break;
+            }
+            if data[start + length] < data[pos + length] {
+                self.children[smaller.0][smaller.1] = candidate;
+                smaller = (node, 1);
+                smaller_length = length;
+                candidate = self.children[node][1];
+            } else {
+                self.children[bigger.0][bigger.1] = candidate;
+                bigger = (node, 0);
+                bigger_length = length;
+                candidate = self.children[node][0];
+            }
+        }
+        (best.0 != 0).then_some((best.0 as u32, best.1 as u32))
+    }
+}
+
+impl MatchFinder for BinaryTree {
+    fn find(
+        &mut self,
+        data: &[u8],
+        pos: usize,
+        max_distance: u32,
+        max_length: u32,
+    ) -> Option<(u32, u32)> {
+        debug_assert!((max_distance as usize) < WINDOW_SIZE);
+        self.max_distance = max_distance as usize;
+        self.max_length = max_length as usize;
+        self.found = Some(pos);
+        self.insert_root(data, pos)
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        if self.found != Some(pos) {
+            self.insert_root(data, pos);
+        }
+    }
+}
+
+/// Positions kept for the matches of 3 bytes, which are only worth it close by.
+const SHORT_HASH_COUNT: usize = 0x1000;
+
+/// Adds matches of 3 bytes, which fit into the shortest code, to the ones of `inner`, which
+/// only finds matches of at least [`MIN_MATCH`] bytes. Without `enabled` it only passes the
+/// calls through.
+pub(crate) struct ShortMatches<M> {
+    inner: M,
+    /// The last position by the hash of the 3 bytes starting there, empty if not enabled.
+    head: Box<[u32]>,
+}
+
+impl<M: MatchFinder> ShortMatches<M> {
+    pub fn new(inner: M, enabled: bool) -> Self {
+        ShortMatches {
+            inner,
+            head: vec![EMPTY; if enabled { SHORT_HASH_COUNT } else { 0 }].into_boxed_slice(),
+        }
+    }
+
+    fn short_hash(data: &[u8], pos: usize) -> Option<usize> {
+        let sequence = data.get(pos..pos + 3)?;
+        Some(make_hash([sequence[0], sequence[1], sequence[2], 0], HASH_BITS) % SHORT_HASH_COUNT)
+    }
+}
+
+impl<M: MatchFinder> MatchFinder for ShortMatches<M> {
+    fn find(
+        &mut self,
+        data: &[u8],
+        pos: usize,
+        max_distance: u32,
+        max_length: u32,
+    ) -> Option<(u32, u32)> {
+        let found = self.inner.find(data, pos, max_distance, max_length);
+        if found.is_some() || self.head.is_empty() {
+            return found;
+        }
+        let candidate = self.head[Self::short_hash(data, pos)?];
+        if candidate == EMPTY || pos - candidate as usize > max_distance as usize {
+            return None;
+        }
+        let rest = &data[pos..data.len().min(pos + max_length as usize)];
+        let length = match_length(rest, &data[candidate as usize..]);
+        (length >= 3).then_some(((pos - candidate as usize) as u32, length as u32))
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        self.inner.insert(data, pos);
+        if !self.head.is_empty() {
+            if let Some(hash) = Self::short_hash(data, pos) {
+                self.head[hash] = pos as u32;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BinaryTree, HashChain, ShortMatches};
+    use crate::lzss::{MatchFinder, HASH_BITS};
+
+    #[test]
+    pub fn test_longest_match() {
+        let mut state = 0x9E3779B9u32;
+        let data: Vec<u8> = (0..50000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                b"abc"[state as usize % 3]
+            })
+            .collect();
+        let mut chain = HashChain::new(usize::MAX, HASH_BITS);
+        let mut tree = BinaryTree::new(HASH_BITS);
+        for pos in 0..data.len() {
+            let expected = chain.find(&data, pos, 0xFFF, 0x200);
+            let found = tree.find(&data, pos, 0xFFF, 0x200);
+            assert_eq!(found.map(|m| m.1), expected.map(|m| m.1), "at {}", pos);
+            if let Some((distance, length)) = found {
+                let start = pos - distance as usize;
+                assert_eq!(
+                    data[start..start + length as usize],
+                    data[pos..pos + length as usize]
+                );
+            }
+            chain.insert(&data, pos);
+            tree.insert(&data, pos);
+        }
+    }
+
+    #[test]
+    pub fn test_short_matches() {
+        let data = b"abcXabcYabcdZabcd";
+        let mut finder = ShortMatches::new(HashChain::new(16, HASH_BITS), true);
+        let mut found = Vec::new();
+        for pos in 0..data.len() {
+            found.push(finder.find(data, pos, 0xFFF, 0x200));
+            finder.insert(data, pos);
+        }
+        assert_eq!(found[4], Some((4, 3)));
+        assert_eq!(found[8], Some((4, 3)));
+        assert_eq!(found[13], Some((5, 4)));
+        let mut finder = ShortMatches::new(HashChain::new(16, HASH_BITS), false);
+        for pos in 0..8 {
+            finder.insert(data, pos);
+        }
+        assert_eq!(finder.find(data, 8, 0xFFF, 0x200), None);
+    }
+}
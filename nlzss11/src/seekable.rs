@@ -0,0 +1,184 @@
+//! Random access to compressed data without decompressing all of it at once.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{DecompressError, Decompressor};
+
+/// Decompressed bytes between checkpoints by default.
+const DEFAULT_INTERVAL: usize = 0x10000;
+
+/// Reads the decompressed view of nlzss11 data held in memory, with seeking.
+///
+/// While decoding it keeps a checkpoint every `interval` decompressed bytes: the decoder state
+/// at that point, which includes the input offset and the last 4 KiB of output. Seeking
+/// backwards restarts at the closest checkpoint, seeking forward decodes and discards the data
+/// in between, or restarts at a checkpoint past the current position if there already is one.
+pub struct SeekableReader<T> {
+    data: T,
+    decompressor: Decompressor,
+    /// `checkpoints[i]` has produced `i * interval` bytes.
+    checkpoints: Vec<Decompressor>,
+    interval: usize,
+    position: u64,
+}
+
+impl<T: AsRef<[u8]>> SeekableReader<T> {
+    /// Reads the header of `data`, the rest is decoded when reading.
+    pub fn new(data: T) -> Result<Self, DecompressError> {
+        Self::with_interval(data, DEFAULT_INTERVAL)
+    }
+
+    /// Like [`SeekableReader::new`], with a checkpoint every `interval` decompressed bytes.
+    /// Smaller intervals make seeking backwards faster and take 4 KiB per checkpoint.
+    pub fn with_interval(data: T, interval: usize) -> Result<Self, DecompressError> {
+        assert!(interval > 0, "interval must not be 0");
+        let mut decompressor = Decompressor::new();
+        decompressor.decompress(data.as_ref(), &mut [])?;
+        if decompressor.decompressed_size().is_none() {
+            return Err(DecompressError::LibraryError("Too short"));
+        }
+        Ok(SeekableReader {
+            data,
+            checkpoints: vec![decompressor.clone()],
+            decompressor,
+            interval,
+            position: 0,
+        })
+    }
+
+    /// Size of the decompressed data.
+    pub fn len(&self) -> usize {
+        self.decompressor.decompressed_size().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.data
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+
+    /// Decodes into `out` from the current decoder position, without going past the next
+    /// checkpoint.
+    fn decode(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let total_out = self.decompressor.total_out();
+        if total_out == self.checkpoints.len() * self.interval {
+            self.checkpoints.push(self.decompressor.clone());
+        }
+        let limit = self.interval - total_out % self.interval;
+        let out_len = out.len().min(limit);
+        let input = &self.data.as_ref()[self.decompressor.total_in()..];
+        let (_, written) = self
+            .decompressor
+            .decompress(input, &mut out[..out_len])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if written == 0 && out_len != 0 && !self.decompressor.is_finished() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "compressed data is truncated",
+            ));
+        }
+        Ok(written)
+    }
+
+    /// Moves the decoder to `target`, which has to be within the data.
+    fn decode_to(&mut self, target: usize) -> io::Result<()> {
+        let current = self.decompressor.total_out();
+        let index = (target / self.interval).min(self.checkpoints.len() - 1);
+        if target < current || self.checkpoints[index].total_out() > current {
+            self.decompressor = self.checkpoints[index].clone();
+        }
+        let mut scratch = [0; 0x1000];
+        while self.decompressor.total_out() < target {
+            let skip = (target - self.decompressor.total_out()).min(scratch.len());
+            self.decode(&mut scratch[..skip])?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]>> Read for SeekableReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.len() as u64 {
+            return Ok(0);
+        }
+        self.decode_to(self.position as usize)?;
+        let written = self.decode(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+}
+
+impl<T: AsRef<[u8]>> Seek for SeekableReader<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use std::io::{Read, Seek, SeekFrom};
+
+    use super::SeekableReader;
+    use crate::compress;
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_seek() {
+        let data = sample(100000, 0);
+        let compressed = compress(&data);
+        let mut reader = SeekableReader::with_interval(&compressed, 1000).unwrap();
+        assert_eq!(reader.len(), data.len());
+
+        let mut state = 0x2545F491u32;
+        let mut buf = [0; 3000];
+        for _ in 0..200 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let pos = state as usize % data.len();
+            let len = (state >> 8) as usize % buf.len();
+            reader.seek(SeekFrom::Start(pos as u64)).unwrap();
+            reader
+                .read_exact(&mut buf[..len.min(data.len() - pos)])
+                .unwrap();
+            assert_eq!(
+                buf[..len.min(data.len() - pos)],
+                data[pos..(pos + len).min(data.len())]
+            );
+        }
+
+        reader.seek(SeekFrom::End(-10)).unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, data[data.len() - 10..]);
+        reader.seek(SeekFrom::Current(-20)).unwrap();
+        reader.read_exact(&mut buf[..5]).unwrap();
+        assert_eq!(buf[..5], data[data.len() - 20..data.len() - 15]);
+        assert!(reader.seek(SeekFrom::Current(-100000)).is_err());
+
+        reader.rewind().unwrap();
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, data);
+    }
+}
@@ -0,0 +1,125 @@
+//! Statistics about the literals and matches of compressed data, for tuning how data is laid out
+//! before it is compressed.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::lzss::LzssVariant;
+use crate::tokens::{parse_tokens, Token};
+#[cfg(feature = "compress")]
+use crate::{compress_with_options, CompressionOptions};
+use crate::{DecompressError, Lz11};
+
+/// The literals, matches and sizes of a compressed stream.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompressionStats {
+    pub literals: usize,
+    pub matches: usize,
+    /// Number of matches by their length.
+    pub match_lengths: BTreeMap<u32, usize>,
+    /// Number of matches by their distance.
+    pub match_distances: BTreeMap<u32, usize>,
+    /// Size of the header with the magic and the decompressed size, 4 or 8 bytes and 4 more with
+    /// the "LZ77" prefix.
+    pub header_bytes: usize,
+    /// Bytes taken up by the group headers flagging literals and matches.
+    pub flag_bytes: usize,
+    /// Bytes taken up by the codes of the matches.
+    pub match_bytes: usize,
+    pub compressed_size: usize,
+    pub decompressed_size: usize,
+}
+
+impl CompressionStats {
+    /// Collects the statistics of the compressed stream in `compressed`.
+    pub fn from_compressed(compressed: &[u8]) -> Result<Self, DecompressError> {
+        let (decompressed_size, header_bytes) = Lz11::read_header(compressed)?;
+        let tokens = parse_tokens(compressed)?;
+        let mut stats = CompressionStats {
+            header_bytes,
+            flag_bytes: tokens.len().div_ceil(8),
+            compressed_size: compressed.len(),
+            decompressed_size,
+            ..Default::default()
+        };
+        for token in tokens {
+            match token {
+                Token::Literal(_) => stats.literals += 1,
+                Token::Match { distance, length } => {
+                    stats.matches += 1;
+                    *stats.match_lengths.entry(length).or_default() += 1;
+                    *stats.match_distances.entry(distance).or_default() += 1;
+                    stats.match_bytes += Lz11::code_size(length);
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Compressed size divided by the decompressed size, 1.0 for empty data.
+    pub fn ratio(&self) -> f64 {
+        if self.decompressed_size == 0 {
+            1.0
+        } else {
+            self.compressed_size as f64 / self.decompressed_size as f64
+        }
+    }
+}
+
+/// Like [`compress_with_options`], but also returns the statistics of the output.
+///
+/// ```
+/// use nlzss11::stats::compress_with_stats;
+/// use nlzss11::CompressionOptions;
+///
+/// let (compressed, stats) = compress_with_stats(b"stats stats stats", &CompressionOptions::new());
+/// assert_eq!(stats.compressed_size, compressed.len());
+/// assert_eq!((stats.literals, stats.matches), (6, 1));
+/// assert_eq!(stats.match_lengths[&11], 1);
+/// ```
+#[cfg(feature = "compress")]
+pub fn compress_with_stats(
+    data: &[u8],
+    options: &CompressionOptions,
+) -> (Vec<u8>, CompressionStats) {
+    let compressed = compress_with_options(data, options);
+    let stats = CompressionStats::from_compressed(&compressed)
+        .expect("the compressor produced a stream that can't be parsed");
+    (compressed, stats)
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::{compress_with_stats, CompressionStats};
+    use crate::{decompress, CompressionOptions, Level};
+
+    #[test]
+    pub fn test_stats() {
+        let data: Vec<u8> = (0..20000u32)
+            .flat_map(|i| format!("{} {} ", i % 1000, i % 7).into_bytes())
+            .chain([0; 0x2000])
+            .collect();
+        for level in [Level::FAST, Level::DEFAULT, Level::BEST] {
+            let options = CompressionOptions::new().level(level).align(true);
+            let (compressed, stats) = compress_with_stats(&data, &options);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+            assert_eq!(stats.decompressed_size, data.len());
+            assert_eq!(stats.compressed_size, compressed.len());
+            assert_eq!(stats.header_bytes, 4);
+            assert_eq!(stats.match_lengths.values().sum::<usize>(), stats.matches);
+            assert_eq!(stats.match_distances.values().sum::<usize>(), stats.matches);
+            let matched: usize = stats
+                .match_lengths
+                .iter()
+                .map(|(&length, &count)| length as usize * count)
+                .sum();
+            assert_eq!(stats.literals + matched, data.len());
+            // everything but the alignment
+            let used = stats.header_bytes + stats.flag_bytes + stats.literals + stats.match_bytes;
+            assert!((0..4).contains(&(compressed.len() - used)), "{:?}", level);
+            assert!(stats.ratio() < 1.0, "{}", stats.ratio());
+        }
+        assert!(CompressionStats::from_compressed(b"\x10\x04\x00\x00abcd").is_err());
+    }
+}
@@ -0,0 +1,390 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{DecompressError, LzssCode, LZ77_PREFIX};
+
+/// Backreferences reach at most this far back.
+const WINDOW_SIZE: usize = 0x1000;
+/// [`Decompressor::push`] grows the output by at most this much at a time.
+const PUSH_CHUNK_SIZE: usize = 0x10000;
+
+/// Returned by [`Decompressor::push`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// All of the input has been used and the stream isn't finished yet.
+    NeedsMoreInput,
+    /// The stream is finished, the rest of the input is ignored.
+    Done,
+}
+
+/// Decompresses data that arrives in pieces, keeping only the last 4 KiB of output around.
+///
+/// ```
+/// # #[cfg(feature = "compress")] {
+/// # let compressed = nlzss11::compress(b"hello hello hello hello");
+/// let mut decompressor = nlzss11::Decompressor::new();
+/// let mut out = [0; 4];
+/// let mut decompressed = Vec::new();
+/// for chunk in compressed.chunks(3) {
+///     let mut input = chunk;
+///     loop {
+///         let (consumed, written) = decompressor.decompress(input, &mut out)?;
+///         decompressed.extend_from_slice(&out[..written]);
+///         input = &input[consumed..];
+///         if consumed == 0 && written == 0 {
+///             break;
+///         }
+///     }
+/// }
+/// assert!(decompressor.is_finished());
+/// assert_eq!(decompressed, b"hello hello hello hello");
+/// # }
+/// # Ok::<(), nlzss11::DecompressError>(())
+/// ```
+#[derive(Clone)]
+pub struct Decompressor {
+    /// Bytes of the "LZ77" prefix read so far.
+    prefix_len: usize,
+    header: [u8; 8],
+    header_len: usize,
+    out_size: Option<usize>,
+    written: usize,
+    consumed: usize,
+    window: Box<[u8; WINDOW_SIZE]>,
+    group_header: u8,
+    remaining_chunks: u8,
+    code: [u8; 4],
+    code_len: usize,
+    copy_distance: usize,
+    copy_remaining: usize,
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decompressor {
+    pub fn new() -> Self {
+        Decompressor {
+            prefix_len: 0,
+            header: [0; 8],
+            header_len: 0,
+            out_size: None,
+            written: 0,
+            consumed: 0,
+            window: Box::new([0; WINDOW_SIZE]),
+            group_header: 0,
+            remaining_chunks: 0,
+            code: [0; 4],
+            code_len: 0,
+            copy_distance: 0,
+            copy_remaining: 0,
+        }
+    }
+
+    /// Size of the decompressed data, known once the header has been read.
+    pub fn decompressed_size(&self) -> Option<usize> {
+        self.out_size
+    }
+
+    /// Whether all of the decompressed data has been produced.
+    pub fn is_finished(&self) -> bool {
+        self.out_size == Some(self.written)
+    }
+
+    /// Total number of compressed bytes consumed so far.
+    pub fn total_in(&self) -> usize {
+        self.consumed
+    }
+
+    /// Total number of decompressed bytes produced so far.
+    pub fn total_out(&self) -> usize {
+        self.written
+    }
+
+    /// Errors if the stream isn't complete, to be called once there is no more input.
+    pub fn finish(&self) -> Result<(), DecompressError> {
+        if self.is_finished() {
+            Ok(())
+        } else {
+            // same error as decompressing the truncated data at once
+            Err(DecompressError::InvalidIndex(self.consumed))
+        }
+    }
+
+    /// Decompresses as much of `input` into `output` as possible.
+    ///
+    /// Returns how many bytes of the input have been consumed and how many bytes have been
+    /// written to the output. It stops when either the input is used up, the output is full
+    /// or the stream is finished, bytes after the end of the stream are never consumed.
+    pub fn decompress(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(usize, usize), DecompressError> {
+        let mut in_pos = 0;
+        let mut out_pos = 0;
+        let result = self.decompress_inner(input, output, &mut in_pos, &mut out_pos);
+        self.consumed += in_pos;
+        result.map(|_| (in_pos, out_pos))
+    }
+
+    /// Decompresses all of `input` and appends the output to `output`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "compress")] {
+    /// use nlzss11::{Decompressor, Status};
+    ///
+    /// # let compressed = nlzss11::compress(b"hello hello hello hello");
+    /// let mut decompressor = Decompressor::new();
+    /// let mut decompressed = Vec::new();
+    /// let mut packets = compressed.chunks(5);
+    /// while decompressor.push(packets.next().unwrap(), &mut decompressed)? != Status::Done {}
+    /// assert_eq!(decompressed, b"hello hello hello hello");
+    /// # }
+    /// # Ok::<(), nlzss11::DecompressError>(())
+    /// ```
+    pub fn push(
+        &mut self,
+        mut input: &[u8],
+        output: &mut Vec<u8>,
+    ) -> Result<Status, DecompressError> {
+        loop {
+            if self.is_finished() {
+                return Ok(Status::Done);
+            }
+            let chunk_size = self
+                .out_size
+                .map_or(WINDOW_SIZE, |out_size| out_size - self.written)
+                .min(PUSH_CHUNK_SIZE);
+            let start = output.len();
+            output.resize(start + chunk_size, 0);
+            let result = self.decompress(input, &mut output[start..]);
+            let (consumed, written) = result.inspect_err(|_| output.truncate(start))?;
+            output.truncate(start + written);
+            input = &input[consumed..];
+            if input.is_empty() && written < chunk_size && !self.is_finished() {
+                return Ok(Status::NeedsMoreInput);
+            }
+        }
+    }
+
+    fn decompress_inner(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        in_pos: &mut usize,
+        out_pos: &mut usize,
+    ) -> Result<(), DecompressError> {
+        let out_size = match self.out_size {
+            Some(out_size) => out_size,
+            None => match self.read_header(input, in_pos)? {
+                Some(out_size) => out_size,
+                None => return Ok(()),
+            },
+        };
+        while self.written < out_size {
+            if self.copy_remaining > 0 {
+                let Some(out) = output.get_mut(*out_pos) else {
+                    return Ok(());
+                };
+                let byte = self.window[(self.written - self.copy_distance) % WINDOW_SIZE];
+                *out = byte;
+                *out_pos += 1;
+                self.push_window(byte);
+                self.copy_remaining -= 1;
+                continue;
+            }
+            let Some(&byte) = input.get(*in_pos) else {
+                return Ok(());
+            };
+            // one byte indicates if the next 8 blocks are literals or backreferences
+            if self.remaining_chunks == 0 {
+                self.group_header = byte;
+                self.remaining_chunks = 8;
+                *in_pos += 1;
+                continue;
+            }
+            if (self.group_header & 0x80) == 0 {
+                let Some(out) = output.get_mut(*out_pos) else {
+                    return Ok(());
+                };
+                *out = byte;
+                *out_pos += 1;
+                *in_pos += 1;
+                self.push_window(byte);
+            } else {
+                self.code[self.code_len] = byte;
+                self.code_len += 1;
+                *in_pos += 1;
+                let code_len = match self.code[0] >> 4 {
+                    0 => 3,
+                    1 => 4,
+                    _ => 2,
+                };
+                if self.code_len < code_len {
+                    continue;
+                }
+                self.code_len = 0;
+                let (LzssCode { distance, length }, _) =
+                    LzssCode::read(&self.code[..code_len]).unwrap();
+                if distance as usize > self.written {
+                    return Err(DecompressError::InvalidIndex(0));
+                }
+                self.copy_distance = distance as usize;
+                // the last backreference might be longer than the remaining output
+                self.copy_remaining = (length as usize).min(out_size - self.written);
+            }
+            self.group_header <<= 1;
+            self.remaining_chunks -= 1;
+        }
+        Ok(())
+    }
+
+    fn read_header(
+        &mut self,
+        input: &[u8],
+        in_pos: &mut usize,
+    ) -> Result<Option<usize>, DecompressError> {
+        while self.header_len == 0 && self.prefix_len < LZ77_PREFIX.len() {
+            let Some(&byte) = input.get(*in_pos) else {
+                return Ok(None);
+            };
+            if byte != LZ77_PREFIX[self.prefix_len] {
+                if self.prefix_len == 0 {
+                    break;
+                }
+                return Err(DecompressError::InvalidMagic);
+            }
+            self.prefix_len += 1;
+            *in_pos += 1;
+        }
+        let header_size = if self.header_len >= 4 && self.header[1..4] == [0, 0, 0] {
+            8
+        } else {
+            4
+        };
+        let missing = (header_size - self.header_len).min(input.len() - *in_pos);
+        self.header[self.header_len..self.header_len + missing]
+            .copy_from_slice(&input[*in_pos..*in_pos + missing]);
+        self.header_len += missing;
+        *in_pos += missing;
+        if self.header_len >= 1 && self.header[0] != 0x11 {
+            return Err(DecompressError::InvalidMagic);
+        }
+        if self.header_len < 4 {
+            return Ok(None);
+        }
+        let mut out_size = u32::from_le_bytes([self.header[1], self.header[2], self.header[3], 0]);
+        if out_size == 0 {
+            if self.header_len < 8 {
+                // the extended size might be in the rest of the input
+                return if *in_pos < input.len() {
+                    self.read_header(input, in_pos)
+                } else {
+                    Ok(None)
+                };
+            }
+            out_size = u32::from_le_bytes(self.header[4..8].try_into().unwrap());
+        }
+        self.out_size = Some(out_size as usize);
+        Ok(self.out_size)
+    }
+
+    #[inline(always)]
+    fn push_window(&mut self, byte: u8) {
+        self.window[self.written % WINDOW_SIZE] = byte;
+        self.written += 1;
+    }
+}
+
+#[cfg(all(test, feature = "compress"))]
+mod test {
+    use super::{Decompressor, Status};
+    use crate::compress;
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_chunked() {
+        let data = sample(20000, 5000);
+        let compressed = compress(&data);
+        for (in_chunk, out_chunk) in [(1, 1), (3, 17), (100, 4096), (compressed.len(), 1 << 20)] {
+            let mut decompressor = Decompressor::new();
+            let mut out = vec![0; out_chunk];
+            let mut decompressed = Vec::new();
+            for mut input in compressed.chunks(in_chunk) {
+                loop {
+                    let (consumed, written) = decompressor.decompress(input, &mut out).unwrap();
+                    decompressed.extend_from_slice(&out[..written]);
+                    input = &input[consumed..];
+                    if input.is_empty() && written < out.len() {
+                        break;
+                    }
+                }
+            }
+            decompressor.finish().unwrap();
+            assert_eq!(decompressor.total_in(), compressed.len());
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    #[test]
+    pub fn test_lz77_prefix() {
+        let data = b"prefixed prefixed prefixed".repeat(10);
+        let mut compressed = b"LZ77".to_vec();
+        compressed.extend_from_slice(&compress(&data));
+        let mut decompressor = Decompressor::new();
+        let mut decompressed = Vec::new();
+        for byte in compressed.chunks(1) {
+            decompressor.push(byte, &mut decompressed).unwrap();
+        }
+        assert!(decompressor.is_finished());
+        assert_eq!(decompressor.total_in(), compressed.len());
+        assert_eq!(decompressed, data);
+        let mut out = [0; 16];
+        assert!(Decompressor::new()
+            .decompress(b"LZ7\x11", &mut out)
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_push() {
+        let data = sample(30_000, 30_000);
+        let mut compressed = compress(&data);
+        let len = compressed.len();
+        compressed.extend_from_slice(b"trailing");
+        for packet_size in [1, 5, 1500, compressed.len()] {
+            let mut decompressor = Decompressor::new();
+            let mut decompressed = Vec::new();
+            let mut packets = compressed.chunks(packet_size);
+            while decompressor
+                .push(packets.next().unwrap(), &mut decompressed)
+                .unwrap()
+                == Status::NeedsMoreInput
+            {}
+            // still done when pushing the bytes after the stream
+            let rest = packets.next().unwrap_or_default();
+            assert_eq!(
+                decompressor.push(rest, &mut decompressed).unwrap(),
+                Status::Done
+            );
+            assert_eq!(decompressor.total_in(), len);
+            assert_eq!(decompressed, data);
+        }
+        let mut decompressor = Decompressor::new();
+        assert!(decompressor.push(b"\x10abc", &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    pub fn test_truncated() {
+        let compressed = compress(&[5; 1000]);
+        let mut decompressor = Decompressor::new();
+        let mut out = [0; 1000];
+        decompressor
+            .decompress(&compressed[..compressed.len() - 1], &mut out)
+            .unwrap();
+        assert!(decompressor.finish().is_err());
+    }
+}
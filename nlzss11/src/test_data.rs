@@ -0,0 +1,31 @@
+//! Data for the tests of the different formats.
+
+use alloc::vec::Vec;
+
+/// `len` bytes repeating every 17 bytes with a different pattern every 2000 bytes, followed
+/// by `run` zeros.
+pub(crate) fn sample(len: usize, run: usize) -> Vec<u8> {
+    let mut data: Vec<u8> = (0..len)
+        .map(|i| (i % 17) as u8 ^ (i / 2000) as u8)
+        .collect();
+    data.resize(len + run, 0);
+    data
+}
+
+/// An LH stream put together bit by bit after the layout of the Wii SDK, with nested tree
+/// nodes and a distance with extra bits, and what it decompresses to.
+pub(crate) fn lh_sample() -> (Vec<u8>, &'static [u8]) {
+    let header = [0x40, 20, 0, 0];
+    // the root and two inner nodes (0x000, 0x180, 0x181) with the leaves 'a', 'b', 'c' and
+    // length 8 (0x105), so every symbol takes 2 bits
+    let length_tree = [
+        0x02, 0x00, 0x00, 0x60, 0x30, 0x26, 0x13, 0x11, 0x8E, 0x0A, 0x00, 0x00,
+    ];
+    // root with the leaves 2 and 0 distance bits
+    let distance_tree = [0x00, 0xC0, 0x80, 0x00];
+    // 'a', 'b', 'c', length 8 from 3 back (2 distance bits, the lower one is 0), 'c', length 8
+    // from 1 back
+    let stream = [0b0001_1011, 0b0010_1110];
+    let compressed = [&header[..], &length_tree, &distance_tree, &stream].concat();
+    (compressed, b"abcabcabcabccccccccc")
+}
@@ -0,0 +1,179 @@
+//! The literals and backreferences of a compressed stream, for tools that want to inspect or
+//! rewrite the stream instead of only the decompressed data.
+//!
+//! ```
+//! # #[cfg(all(feature = "compress", feature = "decompress"))] {
+//! use nlzss11::tokens::{encode_tokens, parse_tokens, Token};
+//!
+//! let compressed = nlzss11::compress_canonical(b"abcabcabcabc");
+//! let tokens = parse_tokens(&compressed)?;
+//! assert_eq!(
+//!     tokens,
+//!     [
+//!         Token::Literal(b'a'),
+//!         Token::Literal(b'b'),
+//!         Token::Literal(b'c'),
+//!         Token::Match { distance: 3, length: 9 },
+//!     ]
+//! );
+//! assert_eq!(encode_tokens(&tokens, 12).unwrap(), compressed);
+//! # }
+//! # Ok::<(), nlzss11::DecompressError>(())
+//! ```
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "decompress")]
+use crate::lzss::LzssVariant;
+#[cfg(feature = "compress")]
+use crate::lzss::{GroupWriter, MAX_HEADER_LEN};
+#[cfg(feature = "compress")]
+use crate::CompressError;
+#[cfg(feature = "decompress")]
+use crate::DecompressError;
+use crate::{Lz11, MIN_LENGTH, TOTAL_BACKREF_LEN, TOTAL_BACKREF_POS};
+
+/// A literal or a backreference of a compressed stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Token {
+    Literal(u8),
+    /// Copies `length` bytes starting `distance` bytes back, the length can be longer than the
+    /// rest of the output for the last one.
+    Match {
+        distance: u32,
+        length: u32,
+    },
+}
+
+impl Token {
+    /// How many bytes the token decompresses to.
+    pub fn decompressed_len(&self) -> usize {
+        match self {
+            Token::Literal(_) => 1,
+            Token::Match { length, .. } => *length as usize,
+        }
+    }
+}
+
+/// Reads all tokens of the stream in `data`, they make up [`crate::decompressed_size`] bytes.
+#[cfg(feature = "decompress")]
+pub fn parse_tokens(data: &[u8]) -> Result<Vec<Token>, DecompressError> {
+    let (out_size, mut pos) = Lz11::read_header(data)?;
+    let mut tokens = Vec::new();
+    let mut produced = 0;
+    while produced < out_size {
+        let group_header = *data.get(pos).ok_or(DecompressError::InvalidIndex(pos))?;
+        pos += 1;
+        for index in 0..8 {
+            if produced >= out_size {
+                break;
+            }
+            let token = if Lz11::is_backref(group_header, index) {
+                let (code, size) = data
+                    .get(pos..)
+                    .and_then(Lz11::read_code)
+                    .ok_or(DecompressError::InvalidIndex(data.len()))?;
+                if code.distance as usize > produced {
+                    return Err(DecompressError::InvalidIndex(0));
+                }
+                pos += size;
+                Token::Match {
+                    distance: code.distance,
+                    length: code.length,
+                }
+            } else {
+                let byte = *data.get(pos).ok_or(DecompressError::InvalidIndex(pos))?;
+                pos += 1;
+                Token::Literal(byte)
+            };
+            produced += token.decompressed_len();
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Writes `tokens` as a stream decompressing to `out_size` bytes.
+///
+/// Fails with [`CompressError::InvalidToken`] and the index of the first token that can't be
+/// encoded, reaches before the start of the output or comes after `out_size` bytes, or
+/// `tokens.len()` if the tokens make up less than `out_size` bytes.
+#[cfg(feature = "compress")]
+pub fn encode_tokens(tokens: &[Token], out_size: usize) -> Result<Vec<u8>, CompressError> {
+    if out_size as u64 > MAX_HEADER_LEN {
+        return Err(CompressError::InputTooLarge(out_size));
+    }
+    let mut out_buf = Vec::with_capacity(8 + tokens.len() * 2);
+    let mut writer = GroupWriter::<Lz11>::new(&mut out_buf, out_size);
+    let mut produced = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if produced >= out_size {
+            return Err(CompressError::InvalidToken(index));
+        }
+        match *token {
+            Token::Literal(byte) => writer.literal(byte),
+            Token::Match { distance, length } => {
+                if !(MIN_LENGTH..=TOTAL_BACKREF_LEN).contains(&length)
+                    || !(1..=TOTAL_BACKREF_POS + 1).contains(&distance)
+                    || distance as usize > produced
+                {
+                    return Err(CompressError::InvalidToken(index));
+                }
+                writer.backref(distance, length);
+            }
+        }
+        produced += token.decompressed_len();
+    }
+    if produced < out_size {
+        return Err(CompressError::InvalidToken(tokens.len()));
+    }
+    writer.finish();
+    Ok(out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{encode_tokens, parse_tokens, Token};
+    use crate::test_data::sample;
+    use crate::{compress, compress_with_level, decompress, CompressError, Level};
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 70000);
+        for level in [Level::FAST, Level::DEFAULT, Level::new(7)] {
+            let compressed = compress_with_level(&data, level);
+            let tokens = parse_tokens(&compressed).unwrap();
+            assert!(tokens.iter().any(|token| token.decompressed_len() > 0x111));
+            assert_eq!(encode_tokens(&tokens, data.len()).unwrap(), compressed);
+        }
+        // the last match may be cut off
+        let tokens = [
+            Token::Literal(1),
+            Token::Match {
+                distance: 1,
+                length: 10,
+            },
+        ];
+        let compressed = encode_tokens(&tokens, 5).unwrap();
+        assert_eq!(decompress(&compressed).unwrap(), [1; 5]);
+        assert_eq!(parse_tokens(&compressed).unwrap(), tokens);
+        assert!(parse_tokens(&compress(&data)[..100]).is_err());
+    }
+
+    #[test]
+    pub fn test_invalid() {
+        let invalid = |tokens: &[Token], out_size| match encode_tokens(tokens, out_size) {
+            Err(CompressError::InvalidToken(index)) => index,
+            _ => panic!("{:?} should be invalid", tokens),
+        };
+        let literal = Token::Literal(0);
+        let reference = |distance, length| Token::Match { distance, length };
+        assert_eq!(invalid(&[reference(1, 3)], 3), 0);
+        assert_eq!(invalid(&[literal, reference(1, 2)], 3), 1);
+        assert_eq!(invalid(&[literal, reference(2, 3)], 4), 1);
+        assert_eq!(invalid(&[literal, reference(1, 0x10111)], 4), 1);
+        assert_eq!(invalid(&[literal; 3], 4), 3);
+        assert_eq!(invalid(&[literal; 3], 2), 2);
+        assert!(encode_tokens(&[literal, reference(1, 0x10110)], 0x10111).is_ok());
+    }
+}
@@ -0,0 +1,152 @@
+//! Adapters working on an [`AsyncBufRead`] source.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use ::tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+use pin_project_lite::pin_project;
+
+use crate::{Compressor, Decompressor};
+
+pin_project! {
+    /// Reads uncompressed data from an [`AsyncBufRead`] and yields it compressed.
+    ///
+    /// Every chunk of the source is fed to a [`Compressor`] as it arrives, which only keeps the
+    /// window and the compressed data. The header holds the decompressed size, so the output
+    /// starts once the source reached its end.
+    pub struct NlzssEncoder<R> {
+        #[pin]
+        reader: R,
+        compressor: Compressor,
+        output: Option<Vec<u8>>,
+        output_pos: usize,
+    }
+}
+
+impl<R: AsyncBufRead> NlzssEncoder<R> {
+    pub fn new(reader: R) -> Self {
+        NlzssEncoder {
+            reader,
+            compressor: Compressor::new(),
+            output: None,
+            output_pos: 0,
+        }
+    }
+}
+
+impl<R> NlzssEncoder<R> {
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead> AsyncRead for NlzssEncoder<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        loop {
+            if let Some(output) = this.output {
+                let rest = &output[*this.output_pos..];
+                let len = rest.len().min(buf.remaining());
+                buf.put_slice(&rest[..len]);
+                *this.output_pos += len;
+                return Poll::Ready(Ok(()));
+            }
+            let chunk = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+            if chunk.is_empty() {
+                *this.output = Some(this.compressor.finish());
+            } else {
+                let len = chunk.len();
+                this.compressor.feed(chunk);
+                this.reader.as_mut().consume(len);
+            }
+        }
+    }
+}
+
+pin_project! {
+    /// Reads compressed data from an [`AsyncBufRead`] and yields it decompressed.
+    ///
+    /// The data is decompressed straight out of the buffer of the source as it arrives, like
+    /// [`crate::bufread::NlzssDecoder`] does. Reading stops at the end of the compressed
+    /// stream, anything after it is left in the source. Invalid or truncated data results in
+    /// an [`io::ErrorKind::InvalidData`] error.
+    pub struct NlzssDecoder<R> {
+        #[pin]
+        reader: R,
+        decompressor: Decompressor,
+    }
+}
+
+impl<R: AsyncBufRead> NlzssDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        NlzssDecoder {
+            reader,
+            decompressor: Decompressor::new(),
+        }
+    }
+}
+
+impl<R> NlzssDecoder<R> {
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncBufRead> AsyncRead for NlzssDecoder<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let invalid_data = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+        let mut this = self.project();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            let input = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+            let eof = input.is_empty();
+            let (consumed, written) = this
+                .decompressor
+                .decompress(input, buf.initialize_unfilled())
+                .map_err(invalid_data)?;
+            this.reader.as_mut().consume(consumed);
+            buf.advance(written);
+            if written > 0 || this.decompressor.is_finished() {
+                return Poll::Ready(Ok(()));
+            }
+            if eof {
+                return Poll::Ready(this.decompressor.finish().map_err(invalid_data));
+            }
+        }
+    }
+}
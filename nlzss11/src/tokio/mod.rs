@@ -0,0 +1,83 @@
+//! Async adapters for tokio, enabled with the `tokio` feature.
+//!
+//! They have the same layout and interface as the adapters in the `async-compression` crate,
+//! so nlzss11 can be used like any other codec there.
+
+pub mod bufread;
+pub mod write;
+
+#[cfg(test)]
+mod test {
+    use ::tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+
+    use super::{bufread, write};
+    use crate::test_data::sample;
+    use crate::{compress, compress_canonical};
+
+    #[::tokio::test]
+    pub async fn test_roundtrip() {
+        let data = b"some async data, some more async data".repeat(10);
+
+        let mut encoder = write::NlzssEncoder::new(Vec::new());
+        for chunk in data.chunks(5) {
+            encoder.write_all(chunk).await.unwrap();
+        }
+        encoder.shutdown().await.unwrap();
+        let compressed = encoder.into_inner();
+        assert_eq!(compressed, compress_canonical(&data));
+
+        let mut decompressed = Vec::new();
+        bufread::NlzssDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, data);
+
+        let mut recompressed = Vec::new();
+        bufread::NlzssEncoder::new(data.as_slice())
+            .read_to_end(&mut recompressed)
+            .await
+            .unwrap();
+        assert_eq!(recompressed, compressed);
+
+        let mut decoder = write::NlzssDecoder::new(Vec::new());
+        decoder.write_all(&compressed).await.unwrap();
+        decoder.shutdown().await.unwrap();
+        assert_eq!(decoder.into_inner(), data);
+    }
+
+    #[::tokio::test]
+    pub async fn test_streaming() {
+        let data = sample(100_000, 5000);
+        let compressed = compress(&data);
+        let (half, rest) = compressed.split_at(compressed.len() / 2);
+
+        // output is read while the source is still open
+        let (mut source, sink) = ::tokio::io::duplex(compressed.len());
+        let mut decoder = bufread::NlzssDecoder::new(BufReader::with_capacity(100, sink));
+        source.write_all(half).await.unwrap();
+        let mut start = vec![0; data.len() / 4];
+        decoder.read_exact(&mut start).await.unwrap();
+        assert_eq!(start, data[..start.len()]);
+        source.write_all(rest).await.unwrap();
+        drop(source);
+        let mut end = Vec::new();
+        decoder.read_to_end(&mut end).await.unwrap();
+        assert_eq!(end, data[start.len()..]);
+
+        let mut decoder = write::NlzssDecoder::new(Vec::new());
+        decoder.write_all(half).await.unwrap();
+        decoder.flush().await.unwrap();
+        let written = decoder.get_ref().len();
+        assert!(written > data.len() / 4);
+        assert_eq!(decoder.get_ref()[..], data[..written]);
+        decoder.write_all(rest).await.unwrap();
+        decoder.shutdown().await.unwrap();
+        assert_eq!(decoder.into_inner(), data);
+
+        let mut decoder = write::NlzssDecoder::new(Vec::new());
+        decoder.write_all(half).await.unwrap();
+        let err = decoder.shutdown().await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,207 @@
+//! Adapters writing into an [`AsyncWrite`] sink.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use ::tokio::io::AsyncWrite;
+use pin_project_lite::pin_project;
+
+use crate::{Compressor, Decompressor};
+
+/// Size of the buffer [`NlzssDecoder`] decompresses into before writing to the sink.
+const DECODE_CHUNK_SIZE: usize = 0x8000;
+
+/// Writes `output[*pos..]` to `writer`.
+fn poll_write_all<W: AsyncWrite>(
+    mut writer: Pin<&mut W>,
+    cx: &mut Context<'_>,
+    output: &[u8],
+    pos: &mut usize,
+) -> Poll<io::Result<()>> {
+    while *pos < output.len() {
+        let written = ready!(writer.as_mut().poll_write(cx, &output[*pos..]))?;
+        if written == 0 {
+            return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+        }
+        *pos += written;
+    }
+    Poll::Ready(Ok(()))
+}
+
+pin_project! {
+    /// Compresses everything written to it into the sink.
+    ///
+    /// The data is fed to a [`Compressor`] as it is written, which only keeps the window and
+    /// the compressed data. The header holds the decompressed size, so the output is written
+    /// to the sink when shutting down the encoder.
+    pub struct NlzssEncoder<W> {
+        #[pin]
+        writer: W,
+        compressor: Compressor,
+        output: Option<Vec<u8>>,
+        output_pos: usize,
+    }
+}
+
+impl<W: AsyncWrite> NlzssEncoder<W> {
+    pub fn new(writer: W) -> Self {
+        NlzssEncoder {
+            writer,
+            compressor: Compressor::new(),
+            output: None,
+            output_pos: 0,
+        }
+    }
+}
+
+impl<W> NlzssEncoder<W> {
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for NlzssEncoder<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if this.output.is_some() {
+            return Poll::Ready(Err(io::Error::other("write after shutdown")));
+        }
+        this.compressor.feed(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // nothing is written to the sink before shutting down
+        self.project().writer.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        let output = this.output.get_or_insert_with(|| this.compressor.finish());
+        ready!(poll_write_all(
+            this.writer.as_mut(),
+            cx,
+            output,
+            this.output_pos
+        ))?;
+        this.writer.poll_shutdown(cx)
+    }
+}
+
+pin_project! {
+    /// Decompresses everything written to it into the sink.
+    ///
+    /// The data is decompressed through a [`Decompressor`] as it is written and the output goes
+    /// to the sink right away, only the last 4 KiB of it are kept for backreferences. Bytes
+    /// after the end of the stream are ignored. Shutting down checks that the stream is
+    /// complete, invalid or truncated data results in an [`io::ErrorKind::InvalidData`] error.
+    pub struct NlzssDecoder<W> {
+        #[pin]
+        writer: W,
+        decompressor: Decompressor,
+        output: Vec<u8>,
+        output_len: usize,
+        output_pos: usize,
+    }
+}
+
+impl<W: AsyncWrite> NlzssDecoder<W> {
+    pub fn new(writer: W) -> Self {
+        NlzssDecoder {
+            writer,
+            decompressor: Decompressor::new(),
+            output: vec![0; DECODE_CHUNK_SIZE],
+            output_len: 0,
+            output_pos: 0,
+        }
+    }
+}
+
+impl<W> NlzssDecoder<W> {
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: AsyncWrite> NlzssDecoder<W> {
+    /// Writes the decompressed data that hasn't reached the sink yet.
+    fn poll_write_output(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        ready!(poll_write_all(
+            this.writer,
+            cx,
+            &this.output[..*this.output_len],
+            this.output_pos
+        ))?;
+        *this.output_len = 0;
+        *this.output_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for NlzssDecoder<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            ready!(self.as_mut().poll_write_output(cx))?;
+            let this = self.as_mut().project();
+            if buf.is_empty() || this.decompressor.is_finished() {
+                return Poll::Ready(Ok(buf.len()));
+            }
+            let (consumed, written) = this
+                .decompressor
+                .decompress(buf, this.output)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            *this.output_len = written;
+            if consumed > 0 {
+                return Poll::Ready(Ok(consumed));
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_write_output(cx))?;
+        self.project().writer.poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_write_output(cx))?;
+        let this = self.project();
+        this.decompressor
+            .finish()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        this.writer.poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,245 @@
+//! Adapters writing into a [`Write`] sink.
+
+use std::io::{self, Write};
+
+#[cfg(feature = "compress")]
+use crate::Compressor;
+#[cfg(feature = "decompress")]
+use crate::Decompressor;
+
+/// Size of the buffer [`NlzssDecoder`] decompresses into before writing to the sink.
+#[cfg(feature = "decompress")]
+const DECODE_CHUNK_SIZE: usize = 0x8000;
+
+/// Compresses everything written to it into the sink.
+///
+/// The data is fed to a [`Compressor`] as it is written, which only keeps the window and the
+/// compressed data. The header holds the decompressed size, so nothing is written to the sink
+/// before [`NlzssEncoder::finish`] or dropping the encoder, call `finish` to handle errors.
+///
+/// ```
+/// # #[cfg(feature = "decompress")] {
+/// use std::io::Write;
+///
+/// let mut encoder = nlzss11::write::NlzssEncoder::new(Vec::new());
+/// for part in ["written ", "in ", "parts, ", "written in parts"] {
+///     encoder.write_all(part.as_bytes())?;
+/// }
+/// let compressed = encoder.finish()?;
+/// assert_eq!(nlzss11::decompress(&compressed).unwrap(), b"written in parts, written in parts");
+/// # }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[doc(alias = "Lz11Encoder")]
+#[cfg(feature = "compress")]
+pub struct NlzssEncoder<W: Write> {
+    inner: Option<W>,
+    compressor: Compressor,
+    finished: bool,
+}
+
+#[cfg(feature = "compress")]
+impl<W: Write> NlzssEncoder<W> {
+    pub fn new(w: W) -> Self {
+        NlzssEncoder {
+            inner: Some(w),
+            compressor: Compressor::new(),
+            finished: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Writes the result to the sink, further writes fail afterwards.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        if !self.finished {
+            let output = self.compressor.finish();
+            self.finished = true;
+            let inner = self.get_mut();
+            inner.write_all(&output)?;
+            inner.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the result to the sink and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<W: Write> Write for NlzssEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            return Err(io::Error::other("write after finish"));
+        }
+        self.compressor.feed(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // nothing is written to the sink before finishing
+        self.get_mut().flush()
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<W: Write> Drop for NlzssEncoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+/// Decompresses everything written to it into the sink.
+///
+/// The data is decompressed through a [`Decompressor`] as it is written and the output goes
+/// to the sink right away, only the last 4 KiB of it are kept for backreferences. Bytes after
+/// the end of the stream are ignored. [`NlzssDecoder::finish`] checks that the stream is
+/// complete, dropping the decoder ignores that.
+#[cfg(feature = "decompress")]
+pub struct NlzssDecoder<W: Write> {
+    inner: Option<W>,
+    decompressor: Decompressor,
+    output: Vec<u8>,
+    finished: bool,
+}
+
+#[cfg(feature = "decompress")]
+impl<W: Write> NlzssDecoder<W> {
+    pub fn new(w: W) -> Self {
+        NlzssDecoder {
+            inner: Some(w),
+            decompressor: Decompressor::new(),
+            output: vec![0; DECODE_CHUNK_SIZE],
+            finished: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Checks that the stream is complete and flushes the sink, further writes fail afterwards.
+    pub fn try_finish(&mut self) -> io::Result<()> {
+        if !self.finished {
+            self.finished = true;
+            self.decompressor
+                .finish()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.get_mut().flush()?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the stream is complete and returns the sink.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+}
+
+#[cfg(feature = "decompress")]
+impl<W: Write> Write for NlzssDecoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            return Err(io::Error::other("write after finish"));
+        }
+        let mut input = buf;
+        while !input.is_empty() && !self.decompressor.is_finished() {
+            let (consumed, written) = self
+                .decompressor
+                .decompress(input, &mut self.output)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            input = &input[consumed..];
+            self.inner
+                .as_mut()
+                .unwrap()
+                .write_all(&self.output[..written])?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use std::io::{Read, Write};
+
+    use crate::test_data::sample;
+    use crate::{bufread, compress, compress_canonical, read};
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = b"this is some data, this is some more data".repeat(10);
+        let mut encoder = super::NlzssEncoder::new(Vec::new());
+        for chunk in data.chunks(7) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+
+        let mut decoder = super::NlzssDecoder::new(Vec::new());
+        decoder.write_all(&compressed).unwrap();
+        assert_eq!(decoder.finish().unwrap(), data);
+
+        let mut decompressed = Vec::new();
+        read::NlzssDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+
+        let mut recompressed = Vec::new();
+        bufread::NlzssEncoder::new(data.as_slice())
+            .read_to_end(&mut recompressed)
+            .unwrap();
+        assert_eq!(recompressed, compress(&data));
+    }
+
+    #[test]
+    pub fn test_encoder_chunks() {
+        let data = sample(100_000, 5000);
+        let mut encoder = super::NlzssEncoder::new(Vec::new());
+        for chunk in data.chunks(4096) {
+            encoder.write_all(chunk).unwrap();
+        }
+        assert!(encoder.get_ref().is_empty());
+        assert_eq!(encoder.finish().unwrap(), compress_canonical(&data));
+    }
+
+    #[test]
+    pub fn test_decoder_streaming() {
+        let data = sample(100_000, 5000);
+        let mut compressed = compress(&data);
+        let len = compressed.len();
+        let mut decoder = super::NlzssDecoder::new(Vec::new());
+        decoder.write_all(&compressed[..len / 2]).unwrap();
+        // the output of the first half is already in the sink
+        let written = decoder.get_ref().len();
+        assert!(written > data.len() / 4);
+        assert_eq!(decoder.get_ref()[..], data[..written]);
+        compressed.extend_from_slice(b"trailing");
+        decoder.write_all(&compressed[len / 2..]).unwrap();
+        assert_eq!(decoder.finish().unwrap(), data);
+
+        let mut decoder = super::NlzssDecoder::new(Vec::new());
+        decoder.write_all(&compressed[..len - 1]).unwrap();
+        let err = decoder.finish().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
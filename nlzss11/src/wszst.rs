@@ -0,0 +1,37 @@
+//! Output conventions of Wiimm's `wszst` and `wimgt`, so files compressed by this crate can be
+//! compared with files from these tools.
+//!
+//! The tools pad the compressed data with zeros to a multiple of 4 bytes, which all decoders
+//! ignore. The backreferences are still the ones chosen by this crate, so only the layout of the
+//! files matches.
+
+use alloc::vec::Vec;
+
+/// Compresses with nlzss11, called `LZ` by the tools.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    crate::compress_with_options(data, &crate::CompressionOptions::new().align(true))
+}
+
+#[cfg(feature = "yaz0")]
+pub fn compress_yaz0(data: &[u8]) -> Vec<u8> {
+    let mut out_buf = crate::yaz0::compress(data);
+    out_buf.resize(out_buf.len().next_multiple_of(4), 0);
+    out_buf
+}
+
+#[cfg(all(test, feature = "decompress"))]
+mod test {
+    use super::compress;
+    use crate::detect::{identify, Confidence};
+
+    #[test]
+    pub fn test_padding() {
+        for len in [1, 10, 11, 12, 13, 1000] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 7) as u8).collect();
+            let compressed = compress(&data);
+            assert_eq!(compressed.len() % 4, 0);
+            assert_eq!(crate::decompress(&compressed).unwrap(), data);
+            assert_eq!(identify(&compressed).unwrap().confidence, Confidence::High);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Yay0, used by older first-party games. Instead of mixing everything in one stream, it
+//! stores the group flags, the backreferences and the literals in three separate tables.
+//!
+//! The older MIO0 format has the same layout, the code is shared with [`crate::mio0`].
+
+use alloc::vec::Vec;
+
+use byteorder::{ByteOrder, BE};
+
+#[cfg(feature = "compress")]
+use crate::lzss::MatchSearcher;
+use crate::lzss::{self, Sink};
+use crate::DecompressError;
+
+const MAX_DISTANCE: u32 = 0x1000;
+
+/// The differences between Yay0 and MIO0.
+pub(crate) struct SplitFormat {
+    pub magic: &'static [u8; 4],
+    /// Whether backreferences with a length of 0 take the real length from the chunk table.
+    pub long_lengths: bool,
+}
+
+impl SplitFormat {
+    fn max_length(&self) -> u32 {
+        if self.long_lengths {
+            0xFF + 0x12
+        } else {
+            0xF + 3
+        }
+    }
+}
+
+pub(crate) const YAY0: SplitFormat = SplitFormat {
+    magic: b"Yay0",
+    long_lengths: true,
+};
+
+/// Reads the size from the header.
+pub(crate) fn read_header(data: &[u8], format: &SplitFormat) -> Result<usize, DecompressError> {
+    if data.len() < 16 {
+        return Err(DecompressError::LibraryError("Too short"));
+    }
+    if &data[..4] != format.magic {
+        return Err(DecompressError::InvalidMagic);
+    }
+    Ok(BE::read_u32(&data[4..]) as usize)
+}
+
+/// Decodes `data` into `sink`, returns the length of the compressed data that was used.
+pub(crate) fn decode(
+    data: &[u8],
+    format: &SplitFormat,
+    sink: &mut impl Sink,
+) -> Result<usize, DecompressError> {
+    let out_size = read_header(data, format)?;
+    let mut link_pos = BE::read_u32(&data[8..]) as usize;
+    let mut chunk_pos = BE::read_u32(&data[12..]) as usize;
+    let mut flags_pos = 16;
+    let mut end = flags_pos;
+    let get = |pos: usize, len: usize| {
+        data.get(pos..pos + len)
+            .ok_or(DecompressError::InvalidIndex(pos))
+    };
+
+    let mut flags = 0;
+    let mut remaining_flags = 0;
+    while sink.len() < out_size {
+        if remaining_flags == 0 {
+            flags = BE::read_u32(get(flags_pos, 4)?);
+            flags_pos += 4;
+            remaining_flags = 32;
+        }
+        if flags & 0x8000_0000 != 0 {
+            sink.push(get(chunk_pos, 1)?[0]);
+            chunk_pos += 1;
+        } else {
+            // LLLLDDDD DDDDDDDD, D + 1
+            // Yay0: L + 2 or the next chunk byte + 0x12 if L is 0, MIO0: L + 3
+            let link = BE::read_u16(get(link_pos, 2)?) as usize;
+            link_pos += 2;
+            let distance = (link & 0xFFF) + 1;
+            let length = if !format.long_lengths {
+                (link >> 12) + 3
+            } else if link >> 12 == 0 {
+                let length = get(chunk_pos, 1)?[0] as usize + 0x12;
+                chunk_pos += 1;
+                length
+            } else {
+                (link >> 12) + 2
+            };
+            if distance > sink.len() {
+                return Err(DecompressError::InvalidIndex(0));
+            }
+            // the last backreference might be longer than the remaining output
+            sink.copy(distance, length.min(out_size - sink.len()));
+        }
+        flags <<= 1;
+        remaining_flags -= 1;
+        end = end.max(flags_pos).max(link_pos).max(chunk_pos);
+    }
+    Ok(end)
+}
+
+#[cfg(feature = "decompress")]
+pub(crate) fn decompress_split(
+    data: &[u8],
+    format: &SplitFormat,
+) -> Result<Vec<u8>, DecompressError> {
+    let out_size = read_header(data, format)?;
+    let mut out_buf = Vec::with_capacity(out_size.min(lzss::max_output(data.len())));
+    decode(data, format, &mut out_buf)?;
+    Ok(out_buf)
+}
+
+#[cfg(feature = "compress")]
+pub(crate) fn compress_split(data: &[u8], format: &SplitFormat) -> Vec<u8> {
+    let mut searcher = MatchSearcher::new();
+    let mut flags: Vec<u32> = Vec::new();
+    let mut links: Vec<u8> = Vec::new();
+    let mut chunks: Vec<u8> = Vec::with_capacity(data.len());
+
+    let mut flag_count = 0;
+    let mut pos: usize = 0;
+    while pos < data.len() {
+        if flag_count % 32 == 0 {
+            flags.push(0);
+        }
+        let flag = 0x8000_0000 >> (flag_count % 32);
+        flag_count += 1;
+        if let Some((distance, length)) =
+            searcher.get_lz_code(data, pos as u32, MAX_DISTANCE, format.max_length())
+        {
+            let adj_dist = distance - 1;
+            let link = if !format.long_lengths {
+                ((length - 3) << 12) | adj_dist
+            } else if length >= 0x12 {
+                chunks.push((length - 0x12) as u8);
+                adj_dist
+            } else {
+                ((length - 2) << 12) | adj_dist
+            };
+            links.extend_from_slice(&(link as u16).to_be_bytes());
+            for p in pos..(pos + length as usize) {
+                searcher.submit_val(data, p as u32);
+            }
+            pos += length as usize;
+        } else {
+            *flags.last_mut().unwrap() |= flag;
+            chunks.push(data[pos]);
+            searcher.submit_val(data, pos as u32);
+            pos += 1;
+        }
+    }
+
+    let link_offset = 16 + flags.len() * 4;
+    // keep the chunk table 4 byte aligned
+    let chunk_offset = (link_offset + links.len()).next_multiple_of(4);
+    let mut out_buf = Vec::with_capacity(chunk_offset + chunks.len());
+    out_buf.extend_from_slice(format.magic);
+    out_buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out_buf.extend_from_slice(&(link_offset as u32).to_be_bytes());
+    out_buf.extend_from_slice(&(chunk_offset as u32).to_be_bytes());
+    for flag in flags {
+        out_buf.extend_from_slice(&flag.to_be_bytes());
+    }
+    out_buf.extend_from_slice(&links);
+    out_buf.resize(chunk_offset, 0);
+    out_buf.extend_from_slice(&chunks);
+    out_buf
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    compress_split(data, &YAY0)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    decompress_split(data, &YAY0)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 1000);
+        let compressed = compress(&data);
+        assert_eq!(&compressed[..4], b"Yay0");
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_lengths() {
+        // a literal and one backreference, whose length goes to the chunk table from 0x12 on
+        for (length, chunks_len) in [(0x11, 1), (0x12, 2), (0xFF + 0x12, 2)] {
+            let data = vec![b'a'; length + 1];
+            let compressed = compress(&data);
+            // header, flags and the link padded to 4 bytes
+            assert_eq!(compressed.len(), 24 + chunks_len, "{:#x}", length);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        // "abc", a backreference of length 4 and distance 3, then one of length 20 and distance 1
+        let mut compressed = b"Yay0\0\0\0\x1B\0\0\0\x14\0\0\0\x18".to_vec();
+        compressed.extend_from_slice(&[0xE0, 0, 0, 0, 0x20, 0x02, 0x00, 0x00]);
+        compressed.extend_from_slice(&[b'a', b'b', b'c', 0x02]);
+        let expected: Vec<u8> = b"abcabca".iter().copied().chain([b'a'; 20]).collect();
+        assert_eq!(decompress(&compressed).unwrap(), expected);
+        // 4 GiB declared in a few bytes
+        assert!(decompress(b"Yay0\xFF\xFF\xFF\xFF\0\0\0\x14\0\0\0\x18\xFF\xFF\xFF\xFF").is_err());
+    }
+}
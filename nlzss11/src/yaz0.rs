@@ -0,0 +1,114 @@
+//! Yaz0 (`.szs`), the format most Wii and Switch games use next to or instead of nlzss11,
+//! enabled with the `yaz0` feature.
+//!
+//! It has a 16 byte header with a big endian size and, unlike the other formats, set flags
+//! in the group headers mark literals.
+
+use byteorder::{ByteOrder, BE};
+
+use crate::lzss::{self, LzssVariant};
+use crate::{DecompressError, LzssCode};
+
+pub(crate) struct Yaz0;
+
+impl LzssVariant for Yaz0 {
+    const MAGIC: u8 = b'Y';
+    const MAX_DISTANCE: u32 = 0x1000;
+    const MAX_LENGTH: u32 = 0xFF + 0x12;
+    const FLAG_MARKS_LITERAL: bool = true;
+
+    fn read_code(buf: &[u8]) -> Option<(LzssCode, usize)> {
+        let pair = u16::from_be_bytes(buf.get(..2)?.try_into().ok()?) as u32;
+        let distance = (pair & 0xFFF) + 1;
+        Some(match pair >> 12 {
+            // 0000DDDD DDDDDDDD LLLLLLLL
+            // L + 0x12, D + 1
+            0 => {
+                let length = *buf.get(2)? as u32 + 0x12;
+                (LzssCode { distance, length }, 3)
+            }
+            // LLLLDDDD DDDDDDDD
+            // L + 2, D + 1
+            length => (
+                LzssCode {
+                    distance,
+                    length: length + 2,
+                },
+                2,
+            ),
+        })
+    }
+
+    fn write_code(code: &LzssCode, out_buf: &mut Vec<u8>) {
+        let adj_dist = code.distance - 1;
+        if code.length >= 0x12 {
+            out_buf.push((adj_dist >> 8) as u8);
+            out_buf.push((adj_dist & 0xFF) as u8);
+            out_buf.push((code.length - 0x12) as u8);
+        } else {
+            out_buf.push((((code.length - 2) << 4) + (adj_dist >> 8)) as u8);
+            out_buf.push((adj_dist & 0xFF) as u8);
+        }
+    }
+
+    fn read_header(data: &[u8]) -> Result<(usize, usize), DecompressError> {
+        if data.len() < 16 {
+            return Err(DecompressError::LibraryError("Too short"));
+        }
+        if &data[..4] != b"Yaz0" {
+            return Err(DecompressError::InvalidMagic);
+        }
+        Ok((BE::read_u32(&data[4..]) as usize, 16))
+    }
+
+    fn write_header(out_buf: &mut Vec<u8>, len: usize) {
+        out_buf.extend_from_slice(b"Yaz0");
+        out_buf.extend_from_slice(&(len as u32).to_be_bytes());
+        // alignment (only used by later games) and padding
+        out_buf.extend_from_slice(&[0; 8]);
+    }
+}
+
+#[cfg(feature = "compress")]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    lzss::compress::<Yaz0>(data)
+}
+
+#[cfg(feature = "decompress")]
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressError> {
+    lzss::decompress_with_consumed::<Yaz0>(data).map(|(out_buf, _)| out_buf)
+}
+
+#[cfg(all(test, feature = "compress", feature = "decompress"))]
+mod test {
+    use super::{compress, decompress};
+    use crate::test_data::sample;
+
+    #[test]
+    pub fn test_roundtrip() {
+        let data = sample(20000, 1000);
+        let compressed = compress(&data);
+        assert_eq!(&compressed[..8], b"Yaz0\0\0\x52\x08");
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    pub fn test_lengths() {
+        // a literal and one backreference, which needs a third byte from 0x12 on
+        for (length, code_len) in [(0x11, 2), (0x12, 3), (0xFF + 0x12, 3)] {
+            let data = vec![b'a'; length + 1];
+            let compressed = compress(&data);
+            assert_eq!(compressed.len(), 16 + 1 + 1 + code_len, "{:#x}", length);
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    pub fn test_decompress() {
+        // "abc", a backreference of length 4 and distance 3, then one of length 20 and distance 1
+        let mut compressed = b"Yaz0\0\0\0\x1B\0\0\0\0\0\0\0\0".to_vec();
+        compressed.extend_from_slice(&[0xE0, b'a', b'b', b'c', 0x20, 0x02, 0x00, 0x00, 0x02]);
+        let expected: Vec<u8> = b"abcabca".iter().copied().chain([b'a'; 20]).collect();
+        assert_eq!(decompress(&compressed).unwrap(), expected);
+    }
+}
@@ -0,0 +1,72 @@
+//! Checks the known-good streams in `tests/corpus`, see `tests/corpus/README.md`.
+#![cfg(all(feature = "compress", feature = "decompress"))]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use nlzss11::{compress, compress_with_level, decompress, decompressed_size, Level};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus")
+}
+
+/// The names of the pairs in the corpus, `name.LZ` and `name.bin`.
+fn names() -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for entry in fs::read_dir(corpus_dir()).unwrap() {
+        let path = entry.unwrap().path();
+        if let Some("LZ" | "bin") = path.extension().and_then(|extension| extension.to_str()) {
+            names.insert(path.file_stem().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    names
+}
+
+/// Everything wrong with the pair `name`, to report all of them at once.
+fn check(name: &str) -> Vec<String> {
+    let read = |extension| fs::read(corpus_dir().join(format!("{}.{}", name, extension)));
+    let (compressed, decompressed) = match (read("LZ"), read("bin")) {
+        (Ok(compressed), Ok(decompressed)) => (compressed, decompressed),
+        _ => return vec![format!("{}: needs both {0}.LZ and {0}.bin", name)],
+    };
+    let mut problems = Vec::new();
+    match decompress(&compressed) {
+        Ok(out) if out == decompressed => {}
+        Ok(_) => problems.push(format!("{}.LZ decompresses to different data", name)),
+        Err(e) => problems.push(format!("{}.LZ doesn't decompress: {}", name, e)),
+    }
+    if decompressed_size(&compressed).ok() != Some(decompressed.len() as u64) {
+        problems.push(format!(
+            "{}.LZ: wrong decompressed size in the header",
+            name
+        ));
+    }
+    if decompress(&compress(&decompressed)).ok().as_ref() != Some(&decompressed) {
+        problems.push(format!(
+            "{}.bin doesn't survive compress and decompress",
+            name
+        ));
+    }
+    // the best level has to be at least as good as every known-good stream
+    let best = compress_with_level(&decompressed, Level::BEST).len();
+    if best > compressed.len() {
+        problems.push(format!(
+            "{}.bin: Level::BEST compresses to {} bytes, more than the {} of {0}.LZ",
+            name,
+            best,
+            compressed.len()
+        ));
+    }
+    problems
+}
+
+#[test]
+pub fn test_corpus() {
+    let names = names();
+    assert!(!names.is_empty(), "no files in {}", corpus_dir().display());
+    let problems: Vec<String> = names.iter().flat_map(|name| check(name)).collect();
+    assert!(problems.is_empty(), "\n{}", problems.join("\n"));
+}
@@ -1,42 +1,125 @@
+use std::io::{Read, Write};
 use std::path::PathBuf;
 
+use clap::builder::PossibleValuesParser;
 use clap::Parser;
-use nlzss11::{compress, decompress, DecompressError};
+use nlzss11::codec::{DynCodec, Registry};
+use nlzss11::detect;
+use nlzss11::{CompressError, DecompressError};
 use thiserror::Error;
 
+/// Accepts the names of all codecs in the registry of the nlzss11 crate.
+fn format_parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(Registry::with_builtin().names())
+}
+
 #[derive(Parser, Debug)]
 #[clap(
-    about = "(de)compress nlzss11 data (usually has .LZ as extension, also brresC also uses it)"
+    about = "(de)compress nlzss11 data (usually has .LZ as extension, also brresC also uses it), LZ10, LZ40, LH (decompression only), LZ60, Yaz0, Yay0, MIO0, RLE and Huffman are supported too"
 )]
 enum Args {
     #[clap(about = "Compress a file")]
     Compress {
-        #[clap(help = "Name of the file to compress")]
+        #[clap(help = "Name of the file to compress, - for stdin")]
         filename: String,
-        #[clap(help = "Name of the compressed file (default: filename + .LZ)")]
+        #[clap(help = "Name of the compressed file, - for stdout (default: filename + extension of the format, stdout when reading stdin)")]
         out_filename: Option<String>,
+        #[clap(
+            short,
+            long,
+            default_value = "nlzss11",
+            value_parser = format_parser(),
+            help = "Format to compress with"
+        )]
+        format: String,
     },
     #[clap(about = "Alias for compress")]
     C {
-        #[clap(help = "Name of the file to compress")]
+        #[clap(help = "Name of the file to compress, - for stdin")]
         filename: String,
-        #[clap(help = "Name of the compressed file (default: filename + .LZ)")]
+        #[clap(help = "Name of the compressed file, - for stdout (default: filename + extension of the format, stdout when reading stdin)")]
         out_filename: Option<String>,
+        #[clap(
+            short,
+            long,
+            default_value = "nlzss11",
+            value_parser = format_parser(),
+            help = "Format to compress with"
+        )]
+        format: String,
     },
     #[clap(about = "Decompress a file")]
     Decompress {
-        #[clap(help = "Name of the file to decompress")]
+        #[clap(help = "Name of the file to decompress, - for stdin")]
         filename: String,
-        #[clap(help = "Name of the decompressed file (default: LZ gets stripped or .decompressed appended)")]
+        #[clap(help = "Name of the decompressed file, - for stdout (default: extension of the format gets stripped or .decompressed appended, stdout when reading stdin)")]
         out_filename: Option<String>,
     },
     #[clap(about = "Alias for decompress")]
     D {
-        #[clap(help = "Name of the file to decompress")]
+        #[clap(help = "Name of the file to decompress, - for stdin")]
+        filename: String,
+        #[clap(help = "Name of the decompressed file, - for stdout (default: extension of the format gets stripped or .decompressed appended, stdout when reading stdin)")]
+        out_filename: Option<String>,
+    },
+    #[clap(about = "Decompress a file and compress it in another format")]
+    Convert {
+        #[clap(help = "Name of the file to convert, - for stdin")]
+        filename: String,
+        #[clap(help = "Name of the converted file, - for stdout")]
+        out_filename: String,
+        #[clap(short, long, value_parser = format_parser(), help = "Format to convert to")]
+        format: String,
+    },
+    #[clap(about = "Decompress a file if it is compressed in a known format, compress it with nlzss11 otherwise")]
+    Auto {
+        #[clap(help = "Name of the file, - for stdin")]
+        filename: String,
+        #[clap(help = "Name of the output file, - for stdout (default: like compress or decompress)")]
+        out_filename: Option<String>,
+    },
+    #[clap(about = "Show the format and sizes of compressed files")]
+    Info {
+        #[clap(required = true, help = "Names of the files, - for stdin")]
+        filenames: Vec<String>,
+    },
+    #[clap(about = "Check that files can be decompressed by the GBA/DS BIOS")]
+    Check {
+        #[clap(required = true, help = "Names of the files, - for stdin")]
+        filenames: Vec<String>,
+        #[clap(long, help = "Also check that the files can be decompressed to VRAM")]
+        vram: bool,
+    },
+    #[clap(about = "Find compressed data embedded in a file")]
+    Scan {
+        #[clap(help = "Name of the file to scan, - for stdin")]
+        filename: String,
+        #[clap(
+            short,
+            long,
+            default_value_t = 4,
+            help = "Only check offsets that are a multiple of this"
+        )]
+        align: usize,
+        #[clap(
+            long,
+            default_value_t = 0x10,
+            help = "Ignore streams decompressing to less bytes than this"
+        )]
+        min_size: usize,
+    },
+    #[clap(about = "Recover as much as possible of a damaged nlzss11 file")]
+    Salvage {
+        #[clap(help = "Name of the damaged file, - for stdin")]
         filename: String,
-        #[clap(help = "Name of the decompressed file (default: LZ gets stripped or .decompressed appended)")]
+        #[clap(help = "Name of the recovered file, - for stdout (default: like decompress)")]
         out_filename: Option<String>,
     },
+    #[clap(about = "Write pathological but valid nlzss11 files for testing decoders, with the decompressed data next to them")]
+    GenCorpus {
+        #[clap(help = "Directory to write the files to, gets created if it doesn't exist")]
+        directory: String,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -53,31 +136,83 @@ enum MyError {
     },
     #[error("error decompressing, file is probably invalid: {0:?}!")]
     DecompressError(DecompressError),
+    #[error("error compressing: {0}!")]
+    CompressError(CompressError),
+}
+
+/// Default name of the compressed file.
+fn compressed_filename(filename: &str, codec: &dyn DynCodec) -> String {
+    if filename == "-" {
+        "-".to_string()
+    } else {
+        let extension = codec.extensions().first().unwrap_or(&"compressed");
+        format!("{}.{}", filename, extension)
+    }
+}
+
+/// Default name of the decompressed file.
+fn decompressed_filename(registry: &Registry, filename: &str) -> String {
+    if filename == "-" {
+        "-".to_string()
+    } else if let Some(stripped) = registry.strip_extension(filename) {
+        stripped.to_string()
+    } else {
+        filename.to_string() + ".decompressed"
+    }
+}
+
+/// Reads the file, `-` reads from stdin.
+fn read_file(filename: &str) -> Result<Vec<u8>, MyError> {
+    let result = if filename == "-" {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data).map(|_| data)
+    } else {
+        std::fs::read(PathBuf::from(filename))
+    };
+    result.map_err(|e| MyError::FileRead {
+        filename: filename.to_string(),
+        os_error: e,
+    })
+}
+
+/// Writes the file, `-` writes to stdout.
+fn write_file(filename: &str, data: &[u8]) -> Result<(), MyError> {
+    let result = if filename == "-" {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(data).and_then(|_| stdout.flush())
+    } else {
+        std::fs::write(PathBuf::from(filename), data)
+    };
+    result.map_err(|e| MyError::FileWrite {
+        filename: filename.to_string(),
+        os_error: e,
+    })
 }
 
 fn main() -> Result<(), MyError> {
     let args = Args::parse();
+    let registry = Registry::with_builtin();
     match args {
         Args::Compress {
             filename,
             out_filename,
+            format,
         }
         | Args::C {
             filename,
             out_filename,
+            format,
         } => {
-            let path = PathBuf::from(filename.clone());
-            let out_filename = out_filename.unwrap_or_else(|| filename.clone() + ".LZ");
-            let out_path = PathBuf::from(out_filename.clone());
-            let uncompressed = std::fs::read(path).map_err(|e| MyError::FileRead {
-                filename,
-                os_error: e,
-            })?;
-            let compressed = compress(&uncompressed);
-            std::fs::write(out_path, compressed).map_err(|e| MyError::FileWrite {
-                filename: out_filename,
-                os_error: e,
-            })?;
+            let codec = registry
+                .by_name(&format)
+                .expect("all formats are registered");
+            let out_filename =
+                out_filename.unwrap_or_else(|| compressed_filename(&filename, codec));
+            let uncompressed = read_file(&filename)?;
+            let compressed = codec
+                .compress(&uncompressed)
+                .map_err(MyError::CompressError)?;
+            write_file(&out_filename, &compressed)?;
         }
         Args::Decompress {
             filename,
@@ -87,24 +222,142 @@ fn main() -> Result<(), MyError> {
             filename,
             out_filename,
         } => {
-            let path = PathBuf::from(filename.clone());
-            let out_filename = out_filename.unwrap_or_else(|| {
-                if filename.ends_with(".LZ") {
-                    filename[..filename.len() - 3].to_string()
-                } else {
-                    filename.clone() + ".decompressed"
+            let out_filename =
+                out_filename.unwrap_or_else(|| decompressed_filename(&registry, &filename));
+            let compressed = read_file(&filename)?;
+            // the format is recognized by the magic
+            let decompressed = registry
+                .decompress(&compressed)
+                .map_err(MyError::DecompressError)?;
+            write_file(&out_filename, &decompressed)?;
+        }
+        Args::Convert {
+            filename,
+            out_filename,
+            format,
+        } => {
+            let compressed = read_file(&filename)?;
+            let decompressed = registry
+                .decompress(&compressed)
+                .map_err(MyError::DecompressError)?;
+            let codec = registry
+                .by_name(&format)
+                .expect("all formats are registered");
+            let compressed = codec
+                .compress(&decompressed)
+                .map_err(MyError::CompressError)?;
+            write_file(&out_filename, &compressed)?;
+        }
+        Args::Auto {
+            filename,
+            out_filename,
+        } => {
+            let data = read_file(&filename)?;
+            if let Some(codec) = registry.codec_for(&data) {
+                eprintln!("{}: decompressing {}", filename, codec.name());
+                let decompressed = codec.decompress(&data).map_err(MyError::DecompressError)?;
+                let out_filename =
+                    out_filename.unwrap_or_else(|| decompressed_filename(&registry, &filename));
+                write_file(&out_filename, &decompressed)?;
+            } else {
+                let codec = registry
+                    .by_name("nlzss11")
+                    .expect("all formats are registered");
+                eprintln!("{}: compressing with nlzss11", filename);
+                let out_filename =
+                    out_filename.unwrap_or_else(|| compressed_filename(&filename, codec));
+                let compressed = codec.compress(&data).map_err(MyError::CompressError)?;
+                write_file(&out_filename, &compressed)?;
+            }
+        }
+        Args::Info { filenames } => {
+            for filename in filenames {
+                let data = read_file(&filename)?;
+                match detect::identify(&data) {
+                    Some(identification) => println!(
+                        "{}: {}, {} -> {} bytes ({:?} confidence)",
+                        filename,
+                        identification.format,
+                        identification
+                            .compressed_len
+                            .map_or("truncated".to_string(), |len| len.to_string()),
+                        identification.decompressed_len,
+                        identification.confidence,
+                    ),
+                    None => println!("{}: unknown", filename),
                 }
-            });
-            let out_path = PathBuf::from(out_filename.clone());
-            let compressed = std::fs::read(path).map_err(|e| MyError::FileRead {
+            }
+        }
+        Args::Check { filenames, vram } => {
+            let mut all_ok = true;
+            for filename in filenames {
+                let data = read_file(&filename)?;
+                match nlzss11::bios::check(&data, vram) {
+                    Ok(()) => println!("{}: ok", filename),
+                    Err(e) => {
+                        println!("{}: {}", filename, e);
+                        all_ok = false;
+                    }
+                }
+            }
+            if !all_ok {
+                std::process::exit(1);
+            }
+        }
+        Args::Scan {
+            filename,
+            align,
+            min_size,
+        } => {
+            let data = read_file(&filename)?;
+            for (offset, identification) in detect::scan(&data, align)
+                .filter(|(_, identification)| identification.decompressed_len >= min_size)
+            {
+                println!(
+                    "{:#010x}: {}, {} -> {} bytes",
+                    offset,
+                    identification.format,
+                    identification.compressed_len.unwrap_or_default(),
+                    identification.decompressed_len,
+                );
+            }
+        }
+        Args::Salvage {
+            filename,
+            out_filename,
+        } => {
+            let out_filename =
+                out_filename.unwrap_or_else(|| decompressed_filename(&registry, &filename));
+            let compressed = read_file(&filename)?;
+            let salvaged =
+                nlzss11::salvage::salvage(&compressed).map_err(MyError::DecompressError)?;
+            eprintln!(
+                "{}: recovered {} of {} bytes, the first {} intact ({:.1}%)",
                 filename,
+                salvaged.data.len(),
+                salvaged.declared_len,
+                salvaged.intact_len,
+                salvaged.confidence() * 100.0,
+            );
+            for resync in &salvaged.resyncs {
+                eprintln!(
+                    "  damaged at output {:#x}, skipped input {:#x}..{:#x}",
+                    resync.output_offset, resync.skipped.start, resync.skipped.end
+                );
+            }
+            write_file(&out_filename, &salvaged.data)?;
+        }
+        Args::GenCorpus { directory } => {
+            std::fs::create_dir_all(&directory).map_err(|e| MyError::FileWrite {
+                filename: directory.clone(),
                 os_error: e,
             })?;
-            let decompressed = decompress(&compressed).map_err(|e| MyError::DecompressError(e))?;
-            std::fs::write(out_path, decompressed).map_err(|e| MyError::FileWrite {
-                filename: out_filename,
-                os_error: e,
-            })?;
+            for entry in nlzss11::corpus::generate() {
+                let path = PathBuf::from(&directory).join(entry.name.replace(' ', "_"));
+                let path = path.to_string_lossy();
+                write_file(&format!("{}.LZ", path), &entry.compressed)?;
+                write_file(&format!("{}.bin", path), &entry.decompressed)?;
+            }
         }
     }
     Ok(())